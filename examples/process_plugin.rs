@@ -0,0 +1,76 @@
+//! A tiny plugin for the `process` backend that echoes the last message back.
+//! Oatmeal writes a single JSON request to stdin, and the plugin answers with
+//! JSON lines on stdout, ending with `done`. See the Backends section of the
+//! README for the full protocol.
+//!
+//! ```sh
+//! cargo build --example process_plugin
+//! oatmeal --backend process --process-command ./target/debug/examples/process_plugin
+//! ```
+
+#![allow(clippy::needless_return)]
+
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+use serde_json::Value;
+use serde_json::json;
+
+fn send(stdout: &mut impl Write, res: Value) -> io::Result<()> {
+    writeln!(stdout, "{res}")?;
+    return stdout.flush();
+}
+
+fn main() -> io::Result<()> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let req: Value = serde_json::from_str(&line).unwrap_or_default();
+    let mut stdout = io::stdout().lock();
+
+    if req["version"] != 1 {
+        return send(
+            &mut stdout,
+            json!({"type": "error", "message": "Unsupported protocol version"}),
+        );
+    }
+
+    match req["type"].as_str().unwrap_or_default() {
+        "health" => {}
+        "list_models" => {
+            send(
+                &mut stdout,
+                json!({"type": "models", "models": [{"name": "echo", "context_length": 4096}]}),
+            )?;
+        }
+        "completion" => {
+            let text = req["messages"]
+                .as_array()
+                .and_then(|messages| return messages.last())
+                .and_then(|message| return message["content"].as_str())
+                .unwrap_or_default();
+            if text == "fail" {
+                eprintln!("echo: asked to fail");
+                std::process::exit(1);
+            }
+
+            let words = text.split_inclusive(' ').collect::<Vec<&str>>();
+            for word in &words {
+                send(&mut stdout, json!({"type": "chunk", "text": word}))?;
+            }
+
+            return send(
+                &mut stdout,
+                json!({"type": "done", "usage": {"completion_tokens": words.len()}}),
+            );
+        }
+        other => {
+            return send(
+                &mut stdout,
+                json!({"type": "error", "message": format!("Unsupported request type {other}")}),
+            );
+        }
+    }
+
+    return send(&mut stdout, json!({"type": "done"}));
+}
@@ -126,6 +126,7 @@ async fn load_config_from_session(session_id: &str) -> Result<()> {
     let session = Sessions::default().load(session_id).await?;
     Config::set(ConfigKey::Backend, &session.state.backend_name);
     Config::set(ConfigKey::Model, &session.state.backend_model);
+    Config::set(ConfigKey::SystemPrompt, &session.state.system_prompt);
     Config::set(ConfigKey::SessionID, session_id);
 
     return Ok(());
@@ -252,10 +253,10 @@ fn arg_backend() -> Arg {
         .env("OATMEAL_BACKEND")
         .num_args(1)
         .help(format!(
-            "The initial backend hosting a model to connect to. [default: {}]",
+            "The initial backend hosting a model to connect to. Either a built in backend ({}), or the name of an OpenAI compatible provider profile from the config file. [default: {}]",
+            BackendName::VARIANTS.join(", "),
             Config::default(ConfigKey::Backend)
-        ))
-        .value_parser(PossibleValuesParser::new(BackendName::VARIANTS));
+        ));
 }
 
 fn arg_backend_health_check_timeout() -> Arg {
@@ -268,6 +269,72 @@ fn arg_backend_health_check_timeout() -> Arg {
         );
 }
 
+fn arg_backend_max_retries() -> Arg {
+    return Arg::new(ConfigKey::BackendMaxRetries.to_string())
+        .long(ConfigKey::BackendMaxRetries.to_string())
+        .env("OATMEAL_BACKEND_MAX_RETRIES")
+        .num_args(1)
+        .help(
+            format!("Number of times to retry a backend request that fails from rate limiting, server errors or connection failures. [default: {}]", Config::default(ConfigKey::BackendMaxRetries)),
+        );
+}
+
+fn arg_backend_retry_delay() -> Arg {
+    return Arg::new(ConfigKey::BackendRetryDelay.to_string())
+        .long(ConfigKey::BackendRetryDelay.to_string())
+        .env("OATMEAL_BACKEND_RETRY_DELAY")
+        .num_args(1)
+        .help(
+            format!("Time to wait in milliseconds before the first retry of a backend request, doubling for each attempt after. A Retry-After header from the backend takes precedence. [default: {}]", Config::default(ConfigKey::BackendRetryDelay)),
+        );
+}
+
+fn arg_request_timeout() -> Arg {
+    return Arg::new(ConfigKey::RequestTimeout.to_string())
+        .long(ConfigKey::RequestTimeout.to_string())
+        .env("OATMEAL_REQUEST_TIMEOUT")
+        .num_args(1)
+        .help(
+            format!("Time in milliseconds to wait to connect to a backend, and for a completion to start responding. Streamed responses and model downloads are limited by stream-idle-timeout instead. 0 waits forever. [default: {}]", Config::default(ConfigKey::RequestTimeout)),
+        );
+}
+
+fn arg_stream_idle_timeout() -> Arg {
+    return Arg::new(ConfigKey::StreamIdleTimeout.to_string())
+        .long(ConfigKey::StreamIdleTimeout.to_string())
+        .env("OATMEAL_STREAM_IDLE_TIMEOUT")
+        .num_args(1)
+        .help(
+            format!("Time in milliseconds to wait for the next part of a streamed response before giving up on it. 0 waits forever. [default: {}]", Config::default(ConfigKey::StreamIdleTimeout)),
+        );
+}
+
+fn arg_http_proxy() -> Arg {
+    return Arg::new(ConfigKey::HttpProxy.to_string())
+        .long(ConfigKey::HttpProxy.to_string())
+        .env("OATMEAL_HTTP_PROXY")
+        .num_args(1)
+        .help("Proxy URL used for all backend requests, such as http://proxy.example.com:8080.");
+}
+
+fn arg_http_ca_bundle() -> Arg {
+    return Arg::new(ConfigKey::HttpCaBundle.to_string())
+        .long(ConfigKey::HttpCaBundle.to_string())
+        .env("OATMEAL_HTTP_CA_BUNDLE")
+        .num_args(1)
+        .help("Path to a PEM file of extra root certificates to trust for backend requests.");
+}
+
+fn arg_http_headers() -> Arg {
+    return Arg::new(ConfigKey::HttpHeaders.to_string())
+        .long(ConfigKey::HttpHeaders.to_string())
+        .env("OATMEAL_HTTP_HEADERS")
+        .num_args(1)
+        .help(
+            "Extra headers sent with all backend requests, as comma separated 'Name: value' pairs.",
+        );
+}
+
 fn arg_model() -> Arg {
     return Arg::new(ConfigKey::Model.to_string())
         .short('m')
@@ -277,12 +344,99 @@ fn arg_model() -> Arg {
         .help("The initial model on a backend to consume. Defaults to the first model available from the backend if not set.");
 }
 
+fn arg_system_prompt() -> Arg {
+    return Arg::new(ConfigKey::SystemPrompt.to_string())
+        .long(ConfigKey::SystemPrompt.to_string())
+        .env("OATMEAL_SYSTEM_PROMPT")
+        .num_args(1)
+        .help("System prompt sent to the model to steer its behaviour for the whole session.");
+}
+
+fn arg_temperature() -> Arg {
+    return Arg::new(ConfigKey::Temperature.to_string())
+        .long(ConfigKey::Temperature.to_string())
+        .env("OATMEAL_TEMPERATURE")
+        .num_args(1)
+        .help("Sampling temperature between 0 and 2 sent to the backend. Defaults to the backend's own default if not set.");
+}
+
+fn arg_max_tokens() -> Arg {
+    return Arg::new(ConfigKey::MaxTokens.to_string())
+        .long(ConfigKey::MaxTokens.to_string())
+        .env("OATMEAL_MAX_TOKENS")
+        .num_args(1)
+        .help("Maximum number of tokens the backend should generate per response. Defaults to the backend's own default if not set.");
+}
+
+fn arg_top_p() -> Arg {
+    return Arg::new(ConfigKey::TopP.to_string())
+        .long(ConfigKey::TopP.to_string())
+        .env("OATMEAL_TOP_P")
+        .num_args(1)
+        .help("Nucleus sampling probability between 0 and 1 sent to the backend. Defaults to the backend's own default if not set.");
+}
+
+fn arg_stop() -> Arg {
+    return Arg::new(ConfigKey::Stop.to_string())
+        .long(ConfigKey::Stop.to_string())
+        .env("OATMEAL_STOP")
+        .num_args(1)
+        .help(
+            "Comma separated list of sequences that stop generation when produced by the backend.",
+        );
+}
+
+fn arg_tools() -> Arg {
+    return Arg::new(ConfigKey::Tools.to_string())
+        .long(ConfigKey::Tools.to_string())
+        .env("OATMEAL_TOOLS")
+        .num_args(1)
+        .value_parser(PossibleValuesParser::new(["true", "false"]))
+        .help(
+            format!("Lets the model read files, search and view git changes in the current directory. Each tool call is confirmed before it runs. [default: {}]", Config::default(ConfigKey::Tools)),
+        );
+}
+
+fn arg_context_limit() -> Arg {
+    return Arg::new(ConfigKey::ContextLimit.to_string())
+        .long(ConfigKey::ContextLimit.to_string())
+        .env("OATMEAL_CONTEXT_LIMIT")
+        .num_args(1)
+        .help("Estimated number of tokens the conversation can reach before its oldest messages are cut, either for all models or as comma separated 'model=tokens' pairs. Unset or 0 means no limit.");
+}
+
+fn arg_context_strategy() -> Arg {
+    return Arg::new(ConfigKey::ContextStrategy.to_string())
+        .long(ConfigKey::ContextStrategy.to_string())
+        .env("OATMEAL_CONTEXT_STRATEGY")
+        .num_args(1)
+        .value_parser(PossibleValuesParser::new(["trim", "summarize"]))
+        .help(
+            format!("How the oldest messages are cut when nearing the context limit, either dropping them or replacing them with a summary from the model. [default: {}]", Config::default(ConfigKey::ContextStrategy)),
+        );
+}
+
 fn subcommand_chat() -> Command {
     return Command::new("chat")
         .about("Start a new chat session.")
         .arg(arg_backend())
         .arg(arg_backend_health_check_timeout())
-        .arg(arg_model());
+        .arg(arg_backend_max_retries())
+        .arg(arg_backend_retry_delay())
+        .arg(arg_request_timeout())
+        .arg(arg_stream_idle_timeout())
+        .arg(arg_http_proxy())
+        .arg(arg_http_ca_bundle())
+        .arg(arg_http_headers())
+        .arg(arg_model())
+        .arg(arg_system_prompt())
+        .arg(arg_temperature())
+        .arg(arg_max_tokens())
+        .arg(arg_top_p())
+        .arg(arg_stop())
+        .arg(arg_tools())
+        .arg(arg_context_limit())
+        .arg(arg_context_strategy());
 }
 
 fn subcommand_sessions() -> Command {
@@ -350,7 +504,22 @@ pub fn build() -> Command {
         .subcommand(subcommand_sessions())
         .arg(arg_backend())
         .arg(arg_backend_health_check_timeout())
+        .arg(arg_backend_max_retries())
+        .arg(arg_backend_retry_delay())
+        .arg(arg_request_timeout())
+        .arg(arg_stream_idle_timeout())
+        .arg(arg_http_proxy())
+        .arg(arg_http_ca_bundle())
+        .arg(arg_http_headers())
         .arg(arg_model())
+        .arg(arg_system_prompt())
+        .arg(arg_temperature())
+        .arg(arg_max_tokens())
+        .arg(arg_top_p())
+        .arg(arg_stop())
+        .arg(arg_tools())
+        .arg(arg_context_limit())
+        .arg(arg_context_strategy())
         .arg(
             Arg::new(ConfigKey::ConfigFile.to_string())
                 .short('c')
@@ -406,6 +575,14 @@ pub fn build() -> Command {
                 .help(format!("Ollama API URL when using the Ollama backend. [default: {}]", Config::default(ConfigKey::OllamaURL)))
                 .global(true),
         )
+        .arg(
+            Arg::new(ConfigKey::LlamacppURL.to_string())
+                .long(ConfigKey::LlamacppURL.to_string())
+                .env("OATMEAL_LLAMACPP_URL")
+                .num_args(1)
+                .help(format!("llama.cpp server API URL when using the llamacpp backend. [default: {}]", Config::default(ConfigKey::LlamacppURL)))
+                .global(true),
+        )
         .arg(
             Arg::new(ConfigKey::OpenAiURL.to_string())
                 .long(ConfigKey::OpenAiURL.to_string())
@@ -430,6 +607,14 @@ pub fn build() -> Command {
                 .help("Anthropic's Claude API token when using the Claude backend.")
                 .global(true),
         )
+        .arg(
+            Arg::new(ConfigKey::ClaudeThinkingBudget.to_string())
+                .long(ConfigKey::ClaudeThinkingBudget.to_string())
+                .env("OATMEAL_CLAUDE_THINKING_BUDGET")
+                .num_args(1)
+                .help("Enables extended thinking with the Claude backend, allowing the model to use up to this many tokens to reason before it answers. Must be at least 1024. Temperature is ignored while thinking is enabled.")
+                .global(true),
+        )
         .arg(
             Arg::new(ConfigKey::GeminiToken.to_string())
                 .long(ConfigKey::GeminiToken.to_string())
@@ -437,6 +622,78 @@ pub fn build() -> Command {
                 .num_args(1)
                 .help("Google Gemini API token when using the Gemini backend.")
                 .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureEndpoint.to_string())
+                .long(ConfigKey::AzureEndpoint.to_string())
+                .env("OATMEAL_AZURE_ENDPOINT")
+                .num_args(1)
+                .help("Azure OpenAI resource endpoint when using the Azure backend, such as https://my-resource.openai.azure.com.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureToken.to_string())
+                .long(ConfigKey::AzureToken.to_string())
+                .env("OATMEAL_AZURE_TOKEN")
+                .num_args(1)
+                .help("Azure OpenAI API key when using the Azure backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureApiVersion.to_string())
+                .long(ConfigKey::AzureApiVersion.to_string())
+                .env("OATMEAL_AZURE_API_VERSION")
+                .num_args(1)
+                .help(format!("Azure OpenAI API version when using the Azure backend. [default: {}]", Config::default(ConfigKey::AzureApiVersion)))
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureDeployments.to_string())
+                .long(ConfigKey::AzureDeployments.to_string())
+                .env("OATMEAL_AZURE_DEPLOYMENTS")
+                .num_args(1)
+                .help("Comma separated list of Azure OpenAI deployment names offered as models when using the Azure backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::MistralToken.to_string())
+                .long(ConfigKey::MistralToken.to_string())
+                .env("OATMEAL_MISTRAL_TOKEN")
+                .num_args(1)
+                .help("Mistral API key when using the Mistral backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::MistralFimModel.to_string())
+                .long(ConfigKey::MistralFimModel.to_string())
+                .env("OATMEAL_MISTRAL_FIM_MODEL")
+                .num_args(1)
+                .help(format!("Mistral code model used for `/fim` completions when using the Mistral backend. [default: {}]", Config::default(ConfigKey::MistralFimModel)))
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::BedrockRegion.to_string())
+                .long(ConfigKey::BedrockRegion.to_string())
+                .env("OATMEAL_BEDROCK_REGION")
+                .num_args(1)
+                .help("AWS region when using the Bedrock backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::BedrockProfile.to_string())
+                .long(ConfigKey::BedrockProfile.to_string())
+                .env("OATMEAL_BEDROCK_PROFILE")
+                .num_args(1)
+                .help("Profile in the AWS credentials file when using the Bedrock backend. Credentials in the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY environment variables are used first.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::ProcessCommand.to_string())
+                .long(ConfigKey::ProcessCommand.to_string())
+                .env("OATMEAL_PROCESS_COMMAND")
+                .num_args(1)
+                .help("Command to run as a plugin when using the process backend. Arguments are split as a shell would, so quote paths that contain spaces.")
+                .global(true),
         );
 }
 
@@ -25,7 +25,6 @@ use crate::configuration::ConfigKey;
 use crate::domain::models::AcceptType;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
-use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::EditorName;
 use crate::domain::models::Event;
@@ -89,7 +88,7 @@ async fn start_loop<B: Backend>(
     let mut events = EventsService::new(rx);
     let mut textarea = TextArea::default();
     let mut app_state = AppState::new(app_state_props).await?;
-    let loading = Loading::default();
+    let mut loading = Loading::default();
 
     #[cfg(feature = "dev")]
     {
@@ -119,11 +118,29 @@ async fn start_loop<B: Backend>(
                 app_state.set_rect(layout[0]);
             }
 
-            app_state.bubble_list.render(
-                layout[0],
-                frame.buffer_mut(),
-                app_state.scroll.position.try_into().unwrap(),
-            );
+            if app_state.is_comparing() {
+                let columns_len = app_state.compare_lists.len() as u32;
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![
+                        Constraint::Ratio(1, columns_len);
+                        columns_len as usize
+                    ])
+                    .split(layout[0]);
+                for (bubble_list, column) in app_state.compare_lists.iter().zip(columns.iter()) {
+                    bubble_list.render(
+                        *column,
+                        frame.buffer_mut(),
+                        app_state.scroll.position.try_into().unwrap(),
+                    );
+                }
+            } else {
+                app_state.bubble_list.render(
+                    layout[0],
+                    frame.buffer_mut(),
+                    app_state.scroll.position.try_into().unwrap(),
+                );
+            }
 
             frame.render_stateful_widget(
                 Scrollbar::new(ScrollbarOrientation::VerticalRight),
@@ -151,6 +168,10 @@ async fn start_loop<B: Backend>(
                 textarea = TextArea::default();
                 app_state.add_message(msg);
 
+                if app_state.answer_confirmation(&input_str) {
+                    continue;
+                }
+
                 let (should_break, should_continue) =
                     app_state.handle_slash_commands(input_str, &tx)?;
 
@@ -161,14 +182,20 @@ async fn start_loop<B: Backend>(
                     continue;
                 }
 
+                if app_state.is_comparing() {
+                    app_state.send_compare_prompt(input_str, &tx)?;
+                    app_state.save_session().await?;
+                    continue;
+                }
+
                 app_state.waiting_for_backend = true;
                 let mut prompt =
-                    BackendPrompt::new(input_str.to_string(), app_state.backend_context.clone());
+                    BackendPrompt::new(input_str.to_string(), app_state.transcript.clone());
 
-                if app_state.backend_context.is_empty() && SlashCommand::parse(&input_str).is_none()
-                {
+                if app_state.transcript.is_empty() && SlashCommand::parse(&input_str).is_none() {
                     prompt.append_chat_context(&app_state.editor_context);
                 }
+                app_state.attach_pending_images(&mut prompt);
 
                 tx.send(Action::BackendRequest(prompt))?;
                 app_state.save_session().await?;
@@ -177,15 +204,48 @@ async fn start_loop<B: Backend>(
 
         match events.next().await? {
             Event::BackendMessage(msg) => {
+                loading.reset();
                 app_state.add_message(msg);
                 app_state.waiting_for_backend = false;
             }
             Event::BackendPromptResponse(msg) => {
+                loading.reset();
                 app_state.handle_backend_response(msg.clone());
                 if msg.done {
                     app_state.save_session().await?;
                 }
             }
+            Event::BackendRetry(text) => {
+                loading.set_text(&text);
+            }
+            Event::BackendProgress(text) => {
+                app_state.handle_progress(&text);
+            }
+            Event::BackendConfirm(question, confirm_tx) => {
+                loading.reset();
+                app_state.handle_confirm(&question, confirm_tx);
+            }
+            Event::BackendToolCall(call, confirm_tx) => {
+                loading.reset();
+                app_state.handle_tool_call(call, confirm_tx);
+            }
+            Event::BackendToolResult(msg) | Event::BackendWarning(msg) => {
+                app_state.add_message(msg);
+            }
+            Event::CompareMessage(idx, msg) => {
+                loading.reset();
+                app_state.handle_compare_message(idx, msg);
+                if !app_state.waiting_for_backend {
+                    app_state.save_session().await?;
+                }
+            }
+            Event::ComparePromptResponse(idx, msg) => {
+                loading.reset();
+                app_state.handle_compare_response(idx, msg);
+                if !app_state.waiting_for_backend {
+                    app_state.save_session().await?;
+                }
+            }
             Event::KeyboardCharInput(input) => {
                 if app_state.waiting_for_backend {
                     continue;
@@ -206,8 +266,10 @@ async fn start_loop<B: Backend>(
                 textarea.input(input);
             }
             Event::KeyboardCTRLC() => {
-                if app_state.waiting_for_backend {
+                if app_state.waiting_for_backend || app_state.pending_confirmation.is_some() {
                     app_state.waiting_for_backend = false;
+                    app_state.pending_confirmation = None;
+                    loading.reset();
                     tx.send(Action::BackendAbort())?;
                 } else if !app_state.exit_warning {
                     app_state.add_message(Message::new(
@@ -239,6 +301,9 @@ async fn start_loop<B: Backend>(
                     send_user_message!(&message.text);
                 }
             }
+            Event::KeyboardCTRLT() => {
+                app_state.toggle_thinking();
+            }
             Event::KeyboardEnter() => {
                 if app_state.waiting_for_backend {
                     continue;
@@ -327,6 +392,7 @@ pub fn destruct_terminal_for_panic() {
 pub async fn start(
     tx: mpsc::UnboundedSender<Action>,
     rx: mpsc::UnboundedReceiver<Event>,
+    client: reqwest::Client,
 ) -> Result<()> {
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
@@ -346,8 +412,7 @@ pub async fn start(
         session_id = Some(Config::get(ConfigKey::SessionID));
     }
 
-    let backend =
-        BackendManager::get(BackendName::parse(Config::get(ConfigKey::Backend)).unwrap())?;
+    let backend = BackendManager::get(&Config::get(ConfigKey::Backend), client)?;
     let editor = EditorManager::get(EditorName::parse(Config::get(ConfigKey::Editor)).unwrap())?;
     let app_state_pros = AppStateProps {
         backend,
@@ -6,6 +6,7 @@ use std::env;
 use std::path;
 
 use anyhow::Result;
+use anyhow::anyhow;
 use anyhow::bail;
 use clap::ArgMatches;
 use clap::Command;
@@ -16,25 +17,55 @@ use strum::IntoEnumIterator;
 use strum::VariantNames;
 use tokio::fs;
 
+use super::Provider;
 use crate::domain::models::BackendName;
+use crate::domain::models::ContextWindow;
 use crate::domain::models::EditorName;
+use crate::domain::models::GenerationParams;
 
 static CONFIG: Lazy<DashMap<String, String>> = Lazy::new(DashMap::new);
+static PROVIDERS: Lazy<DashMap<String, Provider>> = Lazy::new(DashMap::new);
 
 #[derive(Clone, Copy, Eq, PartialEq, EnumIter, VariantNames, strum::Display)]
 #[strum(serialize_all = "kebab-case")]
 pub enum ConfigKey {
     Backend,
     BackendHealthCheckTimeout,
+    BackendMaxRetries,
+    BackendRetryDelay,
+    RequestTimeout,
+    StreamIdleTimeout,
+    HttpProxy,
+    HttpCaBundle,
+    HttpHeaders,
     Editor,
     Model,
+    SystemPrompt,
+    Temperature,
+    MaxTokens,
+    TopP,
+    Stop,
+    Tools,
+    ContextLimit,
+    ContextStrategy,
     ConfigFile,
     LangChainURL,
     OllamaURL,
+    LlamacppURL,
     OpenAiToken,
     OpenAiURL,
     ClaudeToken,
+    ClaudeThinkingBudget,
     GeminiToken,
+    AzureEndpoint,
+    AzureToken,
+    AzureApiVersion,
+    AzureDeployments,
+    MistralToken,
+    MistralFimModel,
+    BedrockRegion,
+    BedrockProfile,
+    ProcessCommand,
     SessionID,
     Theme,
     ThemeFile,
@@ -56,6 +87,36 @@ impl Config {
         CONFIG.insert(key.to_string(), value.to_string());
     }
 
+    pub fn get_provider(name: &str) -> Option<Provider> {
+        return PROVIDERS.get(name).map(|e| return e.value().clone());
+    }
+
+    pub fn set_provider(provider: Provider) {
+        PROVIDERS.insert(provider.name.to_string(), provider);
+    }
+
+    pub fn list_providers() -> Vec<String> {
+        let mut names = PROVIDERS
+            .iter()
+            .map(|e| return e.key().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+
+        return names;
+    }
+
+    /// Returns all values accepted by the `backend` key, being the built in
+    /// backends followed by any configured provider profiles.
+    pub fn backend_names() -> Vec<String> {
+        let mut names = BackendName::VARIANTS
+            .iter()
+            .map(|e| return e.to_string())
+            .collect::<Vec<String>>();
+        names.extend(Config::list_providers());
+
+        return names;
+    }
+
     pub fn default(key: ConfigKey) -> String {
         if key == ConfigKey::Username {
             let mut user = env::var("USER").unwrap_or_else(|_| return String::new());
@@ -96,17 +157,49 @@ impl Config {
         let anthropic_api_key = env::var("ANTHROPIC_API_KEY").unwrap_or_default();
         let openai_api_key = env::var("OPENAI_API_KEY").unwrap_or_default();
         let gemini_api_key = env::var("GEMINI_API_KEY").unwrap_or_default();
+        let azure_api_key = env::var("AZURE_OPENAI_API_KEY").unwrap_or_default();
+        let mistral_api_key = env::var("MISTRAL_API_KEY").unwrap_or_default();
+        let aws_region = env::var("AWS_REGION")
+            .or_else(|_| return env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| return "us-east-1".to_string());
+        let aws_profile = env::var("AWS_PROFILE").unwrap_or_else(|_| return "default".to_string());
         let res = match key {
             ConfigKey::Backend => &default_backend,
             ConfigKey::BackendHealthCheckTimeout => "1000",
+            ConfigKey::BackendMaxRetries => "3",
+            ConfigKey::BackendRetryDelay => "1000",
+            ConfigKey::RequestTimeout => "0",
+            ConfigKey::StreamIdleTimeout => "120000",
+            ConfigKey::HttpProxy => "",
+            ConfigKey::HttpCaBundle => "",
+            ConfigKey::HttpHeaders => "",
             ConfigKey::Editor => &default_editor,
             ConfigKey::Model => "",
+            ConfigKey::SystemPrompt => "",
+            ConfigKey::Temperature => "",
+            ConfigKey::MaxTokens => "",
+            ConfigKey::TopP => "",
+            ConfigKey::Stop => "",
+            ConfigKey::Tools => "false",
+            ConfigKey::ContextLimit => "",
+            ConfigKey::ContextStrategy => "trim",
             ConfigKey::LangChainURL => "http://localhost:8000",
             ConfigKey::OllamaURL => "http://localhost:11434",
+            ConfigKey::LlamacppURL => "http://localhost:8080",
             ConfigKey::OpenAiToken => openai_api_key.as_str(),
             ConfigKey::OpenAiURL => "https://api.openai.com",
             ConfigKey::ClaudeToken => anthropic_api_key.as_str(),
+            ConfigKey::ClaudeThinkingBudget => "",
             ConfigKey::GeminiToken => gemini_api_key.as_str(),
+            ConfigKey::AzureEndpoint => "",
+            ConfigKey::AzureToken => azure_api_key.as_str(),
+            ConfigKey::AzureApiVersion => "2024-10-21",
+            ConfigKey::AzureDeployments => "",
+            ConfigKey::MistralToken => mistral_api_key.as_str(),
+            ConfigKey::MistralFimModel => "codestral-latest",
+            ConfigKey::BedrockRegion => aws_region.as_str(),
+            ConfigKey::BedrockProfile => aws_profile.as_str(),
+            ConfigKey::ProcessCommand => "",
             ConfigKey::Theme => "base16-onedark",
             ConfigKey::ThemeFile => "",
 
@@ -138,6 +231,23 @@ impl Config {
             let toml_str = fs::read_to_string(config_path).await?;
             let doc = toml_str.parse::<toml_edit::DocumentMut>()?;
 
+            if let Some(providers) = doc.get("providers") {
+                let providers_table = providers.as_table_like().ok_or_else(|| {
+                    return anyhow!(
+                        "config.toml has an invalid value for key 'providers', expected a table"
+                    );
+                })?;
+
+                for (name, item) in providers_table.iter() {
+                    if BackendName::parse(name.to_string()).is_some() {
+                        bail!(format!(
+                            "config.toml has a provider named '{name}' which conflicts with a built in backend"
+                        ));
+                    }
+                    Config::set_provider(Provider::parse(name, item)?);
+                }
+            }
+
             for key in ConfigKey::iter() {
                 if let Some(val) = doc.get(&key.to_string()) {
                     // Use clap value parsers to do validation.
@@ -157,10 +267,23 @@ impl Config {
 
                     if let Some(val_int) = val.as_integer() {
                         Config::set(key, &val_int.to_string());
+                    } else if let Some(val_float) = val.as_float() {
+                        Config::set(key, &val_float.to_string());
+                    } else if let Some(val_bool) = val.as_bool() {
+                        Config::set(key, &val_bool.to_string());
+                    } else if let Some(val_arr) = val.as_array() {
+                        let vals = val_arr
+                            .iter()
+                            .filter_map(|e| return e.as_str())
+                            .collect::<Vec<_>>();
+                        Config::set(key, &vals.join(","));
                     } else if let Some(val_str) = val.as_str() {
                         if val_str.is_empty() {
                             continue;
                         }
+                        if key == ConfigKey::Backend {
+                            possible_values = Config::backend_names();
+                        }
                         if !possible_values.is_empty()
                             && !possible_values.contains(&val_str.to_string())
                         {
@@ -181,11 +304,20 @@ impl Config {
                     if val.is_empty() {
                         continue;
                     }
+                    if key == ConfigKey::Backend && !Config::backend_names().contains(val) {
+                        bail!(format!(
+                            "Invalid value for '--{key}': {val}\nPossible values are: {}",
+                            Config::backend_names().join(", ")
+                        ));
+                    }
                     Config::set(key, val);
                 }
             }
         }
 
+        GenerationParams::from_config()?;
+        ContextWindow::from_config(&Config::get(ConfigKey::Model))?;
+
         tracing::debug!(
             username = Config::get(ConfigKey::Username),
             backend = Config::get(ConfigKey::Backend),
@@ -240,7 +372,7 @@ impl Config {
                 let mut val = Config::default(key);
                 if val.is_empty() {
                     val = format!("# {key} = \"\"");
-                } else if val.parse::<i32>().is_ok() {
+                } else if val.parse::<i32>().is_ok() || val.parse::<bool>().is_ok() {
                     val = format!("{key} = {val}");
                 } else {
                     val = format!("{key} = \"{val}\"");
@@ -251,6 +383,16 @@ impl Config {
             .collect::<Vec<String>>()
             .join("\n\n");
 
-        return toml_str;
+        let providers_example = r#"
+# OpenAI compatible provider profiles. Each profile can be selected by name with `backend`.
+# [providers.groq]
+# url = "https://api.groq.com/openai"
+# token = ""
+# models = ["llama3-70b-8192"]
+#
+# [providers.groq.headers]
+# X-Custom-Header = "value""#;
+
+        return format!("{toml_str}\n{providers_example}");
     }
 }
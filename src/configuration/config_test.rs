@@ -11,6 +11,11 @@ fn teardown_default_api_keys() {
         env::remove_var("ANTHROPIC_API_KEY");
         env::remove_var("OPENAI_API_KEY");
         env::remove_var("GEMINI_API_KEY");
+        env::remove_var("AZURE_OPENAI_API_KEY");
+        env::remove_var("MISTRAL_API_KEY");
+        env::remove_var("AWS_REGION");
+        env::remove_var("AWS_DEFAULT_REGION");
+        env::remove_var("AWS_PROFILE");
     }
 }
 
@@ -41,3 +46,40 @@ async fn it_fails_to_loads_config_from_file() -> Result<()> {
     assert!(res.is_err());
     return Ok(());
 }
+
+#[tokio::test]
+async fn it_loads_providers_from_file() -> Result<()> {
+    let matches =
+        cli::build().try_get_matches_from(vec!["chat", "-c", "./test/providers-config.toml"])?;
+    Config::load(cli::build(), vec![&matches]).await?;
+
+    let provider = Config::get_provider("groq").unwrap();
+    assert_eq!(provider.url, "https://api.groq.com/openai");
+    assert_eq!(provider.token, "abc");
+    assert_eq!(provider.models, vec![
+        "llama3-70b-8192".to_string(),
+        "mixtral-8x7b-32768".to_string()
+    ]);
+    assert_eq!(provider.headers.get("X-Gateway").unwrap(), "team-a");
+
+    let lmstudio = Config::get_provider("lmstudio").unwrap();
+    assert_eq!(lmstudio.url, "http://localhost:1234");
+    assert!(lmstudio.token.is_empty());
+    assert!(lmstudio.models.is_empty());
+
+    assert!(Config::backend_names().contains(&"groq".to_string()));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_to_load_providers_named_after_backends() -> Result<()> {
+    let matches = cli::build().try_get_matches_from(vec![
+        "chat",
+        "-c",
+        "./test/bad-providers-config.toml",
+    ])?;
+    let res = Config::load(cli::build(), vec![&matches]).await;
+    assert!(res.is_err());
+    return Ok(());
+}
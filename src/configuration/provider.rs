@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+
+/// A named OpenAI compatible endpoint configured under `[providers.<name>]` in
+/// the config file, allowing several compatible APIs (Groq, vLLM, LM Studio,
+/// OpenRouter, etc) to be configured side by side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Provider {
+    pub name: String,
+    pub url: String,
+    pub token: String,
+    pub headers: HashMap<String, String>,
+    pub models: Vec<String>,
+}
+
+fn invalid_value(name: &str, key: &str, expected: &str) -> anyhow::Error {
+    return anyhow!(
+        "config.toml has an invalid value for key '{key}' in provider '{name}', expected {expected}"
+    );
+}
+
+fn get_str(table: &dyn toml_edit::TableLike, name: &str, key: &str) -> Result<String> {
+    if let Some(item) = table.get(key) {
+        let val = item
+            .as_str()
+            .ok_or_else(|| return invalid_value(name, key, "a string"))?;
+        return Ok(val.to_string());
+    }
+
+    return Ok(String::new());
+}
+
+impl Provider {
+    pub fn parse(name: &str, item: &toml_edit::Item) -> Result<Provider> {
+        let table = item.as_table_like().ok_or_else(|| {
+            return anyhow!(
+                "config.toml has an invalid value for provider '{name}', expected a table"
+            );
+        })?;
+
+        let mut provider = Provider {
+            name: name.to_string(),
+            url: get_str(table, name, "url")?,
+            token: get_str(table, name, "token")?,
+            ..Provider::default()
+        };
+
+        if provider.url.is_empty() {
+            bail!(format!(
+                "config.toml is missing key 'url' for provider '{name}'"
+            ));
+        }
+
+        if let Some(headers) = table.get("headers") {
+            let headers_table = headers
+                .as_table_like()
+                .ok_or_else(|| return invalid_value(name, "headers", "a table"))?;
+
+            for (key, _) in headers_table.iter() {
+                provider
+                    .headers
+                    .insert(key.to_string(), get_str(headers_table, name, key)?);
+            }
+        }
+
+        if let Some(models) = table.get("models") {
+            provider.models = models
+                .as_array()
+                .ok_or_else(|| return invalid_value(name, "models", "an array"))?
+                .iter()
+                .filter_map(|e| return e.as_str().map(|s| return s.to_string()))
+                .collect();
+        }
+
+        return Ok(provider);
+    }
+}
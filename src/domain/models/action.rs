@@ -1,11 +1,20 @@
 use super::AcceptType;
 use super::BackendPrompt;
+use super::CompareTarget;
 use super::EditorContext;
+use super::FimPrompt;
 use super::Message;
 
 pub enum Action {
     AcceptCodeBlock(Option<EditorContext>, String, AcceptType),
     BackendAbort(),
     BackendRequest(BackendPrompt),
+    BackendSet(String),
+    /// Sends a prompt to each target at once, one prompt per comparison
+    /// column.
+    CompareRequest(Vec<(CompareTarget, BackendPrompt)>),
     CopyMessages(Vec<Message>),
+    FillInMiddle(FimPrompt),
+    ModelDelete(String),
+    ModelPull(String),
 }
@@ -3,6 +3,7 @@
 mod tests;
 
 use anyhow::Result;
+use anyhow::bail;
 use async_trait::async_trait;
 use strum::EnumIter;
 use strum::IntoEnumIterator;
@@ -12,6 +13,19 @@ use tokio::sync::mpsc;
 use super::Author;
 use super::EditorContext;
 use super::Event;
+use super::FimPrompt;
+use super::GenerationParams;
+use super::ImageAttachment;
+use super::ModelInfo;
+use super::Role;
+use super::ToolCall;
+use super::ToolDefinition;
+use super::ToolResult;
+use super::Transcript;
+use super::Turn;
+use super::Usage;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
 
 #[derive(Clone, Debug, PartialEq, Eq, EnumIter, VariantNames, strum::Display)]
 #[strum(serialize_all = "lowercase")]
@@ -21,6 +35,11 @@ pub enum BackendName {
     OpenAI,
     Claude,
     Gemini,
+    LlamaCpp,
+    Azure,
+    Mistral,
+    Bedrock,
+    Process,
 }
 
 impl BackendName {
@@ -31,17 +50,46 @@ impl BackendName {
 
 pub struct BackendPrompt {
     pub text: String,
-    pub backend_context: String,
+    /// The model to request the completion from.
+    pub model: String,
+    /// The conversation so far, not including this prompt.
+    pub transcript: Transcript,
+    pub system_prompt: String,
+    pub params: GenerationParams,
+    pub images: Vec<ImageAttachment>,
+    /// Tools the model may call. Empty when tool calling is disabled.
+    pub tools: Vec<ToolDefinition>,
+    /// Results of the tool calls from the previous response, sent in place of
+    /// a user message.
+    pub tool_results: Vec<ToolResult>,
 }
 
 impl BackendPrompt {
-    pub fn new(text: String, backend_context: String) -> BackendPrompt {
+    pub fn new(text: String, transcript: Transcript) -> BackendPrompt {
         return BackendPrompt {
             text,
-            backend_context,
+            model: Config::get(ConfigKey::Model),
+            transcript,
+            system_prompt: Config::get(ConfigKey::SystemPrompt),
+            params: GenerationParams::from_config().unwrap_or_default(),
+            images: vec![],
+            tools: vec![],
+            tool_results: vec![],
         };
     }
 
+    /// Returns the transcript with this prompt added as the latest user turn.
+    pub fn to_transcript(&self) -> Transcript {
+        let mut turn = Turn::new(Role::User, &self.text);
+        turn.images = self.images.clone();
+        turn.tool_results = self.tool_results.clone();
+
+        let mut transcript = self.transcript.clone();
+        transcript.push(turn);
+
+        return transcript;
+    }
+
     pub fn append_chat_context(&mut self, editor_context: &Option<EditorContext>) {
         if let Some(context) = editor_context {
             let lang = &context.language;
@@ -65,8 +113,15 @@ impl BackendPrompt {
 pub struct BackendResponse {
     pub author: Author,
     pub text: String,
+    /// Reasoning streamed by the model, shown apart from the answer and left
+    /// out of the transcript.
+    pub thinking: String,
     pub done: bool,
-    pub context: Option<String>,
+    /// The transcript including this response, set on the final response.
+    pub transcript: Option<Transcript>,
+    pub usage: Option<Usage>,
+    /// Tools the model asked to run, set on the final response.
+    pub tool_calls: Vec<ToolCall>,
 }
 
 #[async_trait]
@@ -79,8 +134,58 @@ pub trait Backend {
     async fn health_check(&self) -> Result<()>;
 
     /// Called when using the `/modellist` slash commands to provide all
-    /// available models for the backend.
-    async fn list_models<'a>(&'a self) -> Result<Vec<String>>;
+    /// available models for the backend, with whatever details the backend
+    /// reports about them.
+    async fn list_models<'a>(&'a self) -> Result<Vec<ModelInfo>>;
+
+    /// Models to offer when `list_models` fails. They aren't kept for the
+    /// session, so the next listing tries the backend again.
+    fn fallback_models(&self) -> Vec<ModelInfo> {
+        return vec![];
+    }
+
+    /// Whether models can be downloaded and removed with the `/pull` and
+    /// `/rmmodel` slash commands.
+    fn can_manage_models(&self) -> bool {
+        return false;
+    }
+
+    /// Downloads a model, streaming progress to the UI as `BackendProgress`
+    /// events.
+    #[allow(clippy::implicit_return)]
+    async fn pull_model<'a>(
+        &self,
+        _model: &str,
+        _tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        bail!(format!(
+            "The {} backend doesn't support pulling models",
+            self.name()
+        ));
+    }
+
+    /// Removes a downloaded model.
+    #[allow(clippy::implicit_return)]
+    async fn delete_model(&self, _model: &str) -> Result<()> {
+        bail!(format!(
+            "The {} backend doesn't support removing models",
+            self.name()
+        ));
+    }
+
+    /// Completes the code between a prefix and a suffix for the `/fim` slash
+    /// command, returning only the code in between.
+    #[allow(clippy::implicit_return)]
+    async fn fill_in_middle<'a>(
+        &self,
+        _prompt: FimPrompt,
+        _tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<String> {
+        bail!(format!(
+            "The {} backend doesn't support fill in the middle completions",
+            self.name()
+        ));
+    }
 
     /// Requests completions from the backend. Completion results may be
     /// streamed back to the UI by passing each response through a channel.
@@ -88,9 +193,10 @@ pub trait Backend {
     /// Upon receiving all results, a final `done` boolean
     /// is provided as the last message to the channel.
     ///
-    /// In order for a backend to maintain history, a context array may be
-    /// provided by the backend. This can be passed alongside the `done`
-    /// boolean, and will be provided on the next prompt to the backend.
+    /// History is kept in the prompt's transcript, which the backend renders
+    /// into its own request format. The final response carries the transcript
+    /// with the prompt and the model's reply added, and is provided on the next
+    /// prompt to the backend.
     async fn get_completion<'a>(
         &self,
         prompt: BackendPrompt,
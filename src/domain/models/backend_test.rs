@@ -1,9 +1,13 @@
 use super::super::EditorContext;
 use super::BackendPrompt;
+use super::Role;
+use super::ToolResult;
+use super::Transcript;
+use super::Turn;
 
 #[test]
 fn it_adds_default_system_prompt() {
-    let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
+    let mut prompt = BackendPrompt::new("Hello world".to_string(), Transcript::default());
     prompt.append_chat_context(&None);
 
     insta::assert_snapshot!(prompt.text, @"Hello world. Add language to any code blocks.");
@@ -11,7 +15,7 @@ fn it_adds_default_system_prompt() {
 
 #[test]
 fn it_adds_language_system_prompt() {
-    let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
+    let mut prompt = BackendPrompt::new("Hello world".to_string(), Transcript::default());
     prompt.append_chat_context(&Some(EditorContext {
         file_path: "./test.rs".to_string(),
         language: "rust".to_string(),
@@ -25,7 +29,7 @@ fn it_adds_language_system_prompt() {
 
 #[test]
 fn it_adds_language_and_code_system_prompt() {
-    let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
+    let mut prompt = BackendPrompt::new("Hello world".to_string(), Transcript::default());
     prompt.append_chat_context(&Some(EditorContext {
         file_path: "./test.rs".to_string(),
         language: "rust".to_string(),
@@ -39,3 +43,24 @@ fn it_adds_language_and_code_system_prompt() {
     println!("Test!")
     "###);
 }
+
+#[test]
+fn it_adds_prompt_to_transcript() {
+    let mut transcript = Transcript::default();
+    transcript.push(Turn::new(Role::User, "Hello"));
+    transcript.push(Turn::new(Role::Assistant, "Hi!"));
+
+    let mut prompt = BackendPrompt::new("".to_string(), transcript);
+    prompt.tool_results = vec![ToolResult {
+        id: "call_1".to_string(),
+        name: "read_file".to_string(),
+        content: "# Hello".to_string(),
+        is_error: false,
+    }];
+    let res = prompt.to_transcript();
+
+    assert_eq!(res.turns.len(), 3);
+    assert_eq!(res.turns[2].role, Role::User);
+    assert_eq!(res.turns[2].tool_results, prompt.tool_results);
+    assert_eq!(prompt.transcript.turns.len(), 2);
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+#[path = "comparison_test.rs"]
+mod tests;
+
+use anyhow::Result;
+use anyhow::bail;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Author;
+use super::BackendPrompt;
+use super::BackendResponse;
+use super::Message;
+use super::Transcript;
+
+/// A backend and model pair to send prompts to while comparing, written as
+/// `backend:model`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareTarget {
+    pub backend: String,
+    pub model: String,
+}
+
+impl CompareTarget {
+    pub fn parse(text: &str) -> Result<CompareTarget> {
+        // Model names can contain colons, such as Ollama tags, so only the
+        // first one separates the backend.
+        let (backend, model) = match text.split_once(':') {
+            Some((backend, model)) if !backend.is_empty() && !model.is_empty() => (backend, model),
+            _ => {
+                bail!(format!(
+                    "Invalid comparison target '{text}', expected 'backend:model'"
+                ))
+            }
+        };
+
+        return Ok(CompareTarget {
+            backend: backend.to_string(),
+            model: model.to_string(),
+        });
+    }
+}
+
+impl std::fmt::Display for CompareTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}:{}", self.backend, self.model);
+    }
+}
+
+/// One column of a side-by-side comparison, keeping its own history with its
+/// target.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CompareColumn {
+    pub target: CompareTarget,
+    pub transcript: Transcript,
+    pub messages: Vec<Message>,
+    #[serde(skip)]
+    pub waiting: bool,
+}
+
+impl CompareColumn {
+    pub fn new(target: CompareTarget) -> CompareColumn {
+        return CompareColumn {
+            target,
+            transcript: Transcript::default(),
+            messages: vec![],
+            waiting: false,
+        };
+    }
+
+    /// Builds the prompt for this column's target from its own history.
+    pub fn prompt(&self, text: &str) -> BackendPrompt {
+        let mut prompt = BackendPrompt::new(text.to_string(), self.transcript.clone());
+        prompt.model = self.target.model.to_string();
+
+        return prompt;
+    }
+
+    pub fn handle_backend_response(&mut self, msg: BackendResponse) {
+        let last_message = self.messages.last_mut();
+        if let Some(last_message) = last_message
+            && last_message.author == Author::Model
+        {
+            last_message.append(&msg.text);
+        } else {
+            self.messages.push(Message::new(msg.author, &msg.text));
+        }
+        self.messages
+            .last_mut()
+            .unwrap()
+            .append_thinking(&msg.thinking);
+
+        if msg.usage.is_some() {
+            self.messages.last_mut().unwrap().usage = msg.usage;
+        }
+
+        if msg.done {
+            self.waiting = false;
+            if let Some(transcript) = msg.transcript {
+                self.transcript = transcript;
+            }
+        }
+    }
+}
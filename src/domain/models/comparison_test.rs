@@ -0,0 +1,82 @@
+use anyhow::Result;
+
+use super::CompareColumn;
+use super::CompareTarget;
+use crate::domain::models::Author;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Message;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+
+#[test]
+fn it_parses_targets() -> Result<()> {
+    let target = CompareTarget::parse("ollama:llama3:8b")?;
+
+    assert_eq!(target.backend, "ollama");
+    assert_eq!(target.model, "llama3:8b");
+    assert_eq!(target.to_string(), "ollama:llama3:8b");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_parse_targets_without_model() {
+    let res = CompareTarget::parse("ollama");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid comparison target 'ollama', expected 'backend:model'"
+    );
+}
+
+#[test]
+fn it_creates_prompts_for_target() -> Result<()> {
+    let column = CompareColumn::new(CompareTarget::parse("openai:gpt-4o")?);
+    let prompt = column.prompt("Hello");
+
+    assert_eq!(prompt.text, "Hello");
+    assert_eq!(prompt.model, "gpt-4o");
+    assert!(prompt.transcript.is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn it_handles_backend_responses() -> Result<()> {
+    let mut column = CompareColumn::new(CompareTarget::parse("openai:gpt-4o")?);
+    column.messages.push(Message::new(Author::User, "Hello"));
+    column.waiting = true;
+
+    column.handle_backend_response(BackendResponse {
+        author: Author::Model,
+        text: "Hi ".to_string(),
+        thinking: "".to_string(),
+        done: false,
+        transcript: None,
+        usage: None,
+        tool_calls: vec![],
+    });
+    column.handle_backend_response(BackendResponse {
+        author: Author::Model,
+        text: "there".to_string(),
+        thinking: "".to_string(),
+        done: true,
+        transcript: Some(Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![
+                Turn::new(Role::User, "Hello"),
+                Turn::new(Role::Assistant, "Hi there"),
+            ],
+        }),
+        usage: None,
+        tool_calls: vec![],
+    });
+
+    assert!(!column.waiting);
+    assert_eq!(column.messages.len(), 2);
+    assert_eq!(column.messages[1].text, "Hi there");
+    assert_eq!(column.transcript.turns.len(), 2);
+
+    return Ok(());
+}
@@ -0,0 +1,220 @@
+#[cfg(test)]
+#[path = "context_window_test.rs"]
+mod tests;
+
+use anyhow::Result;
+use anyhow::bail;
+
+use super::BackendPrompt;
+use super::Role;
+use super::Transcript;
+use super::Turn;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
+/// Roughly how many characters make up a token. Each model has its own
+/// tokenizer, so counts are only ever an estimate.
+const CHARS_PER_TOKEN: usize = 4;
+/// Most backends charge images a roughly fixed number of tokens.
+const IMAGE_TOKENS: usize = 1000;
+/// Role markers and formatting added to each turn on top of its content.
+const TURN_OVERHEAD_TOKENS: usize = 4;
+/// The history is fitted once a prompt reaches this share of the limit,
+/// leaving room for the response.
+const FIT_THRESHOLD_PERCENT: usize = 90;
+/// The share of the limit the history is cut down to, so it isn't fitted
+/// again on the very next message.
+const FIT_TARGET_PERCENT: usize = 60;
+
+const SUMMARY_PROMPT: &str = "Summarize our conversation so far in a few short paragraphs. Keep any facts, decisions and code that later messages may rely on. Reply with only the summary.";
+
+pub fn estimate_tokens(text: &str) -> usize {
+    return text.chars().count().div_ceil(CHARS_PER_TOKEN);
+}
+
+fn estimate_turn_tokens(turn: &Turn) -> usize {
+    let tool_calls = turn
+        .tool_calls
+        .iter()
+        .map(|call| {
+            return estimate_tokens(&call.name) + estimate_tokens(&call.arguments.to_string());
+        })
+        .sum::<usize>();
+    let tool_results = turn
+        .tool_results
+        .iter()
+        .map(|res| return estimate_tokens(&res.content))
+        .sum::<usize>();
+
+    return TURN_OVERHEAD_TOKENS
+        + estimate_tokens(&turn.text)
+        + turn.images.len() * IMAGE_TOKENS
+        + tool_calls
+        + tool_results;
+}
+
+/// Estimates the tokens a prompt takes up, including its history.
+pub fn estimate_prompt_tokens(prompt: &BackendPrompt) -> usize {
+    let history = prompt
+        .transcript
+        .turns
+        .iter()
+        .map(estimate_turn_tokens)
+        .sum::<usize>();
+    let tool_results = prompt
+        .tool_results
+        .iter()
+        .map(|res| return estimate_tokens(&res.content))
+        .sum::<usize>();
+
+    return history
+        + TURN_OVERHEAD_TOKENS
+        + estimate_tokens(&prompt.text)
+        + estimate_tokens(&prompt.system_prompt)
+        + prompt.images.len() * IMAGE_TOKENS
+        + tool_results;
+}
+
+/// How the oldest turns are cut once the history nears the context limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContextStrategy {
+    /// Drops the oldest turns.
+    Trim,
+    /// Replaces the oldest turns with a summary written by the model.
+    Summarize,
+}
+
+impl ContextStrategy {
+    pub fn parse(text: &str) -> Result<ContextStrategy> {
+        return match text {
+            "" | "trim" => Ok(ContextStrategy::Trim),
+            "summarize" => Ok(ContextStrategy::Summarize),
+            _ => {
+                bail!(format!(
+                    "Invalid value for {}: {text}. Possible values are: trim, summarize",
+                    ConfigKey::ContextStrategy
+                ))
+            }
+        };
+    }
+}
+
+/// Finds the limit for a model from `context-limit`, being either a number of
+/// tokens or comma separated `model=tokens` pairs. A bare number applies to
+/// models without their own pair, and zero means no limit.
+fn parse_limit(value: &str, model: &str) -> Result<Option<usize>> {
+    let mut limit = None;
+    for entry in value.split(',').map(|e| return e.trim()) {
+        if entry.is_empty() {
+            continue;
+        }
+
+        let (entry_model, tokens) = match entry.rsplit_once('=') {
+            Some((entry_model, tokens)) => (Some(entry_model.trim()), tokens.trim()),
+            None => (None, entry),
+        };
+        let tokens = match tokens.parse::<usize>() {
+            Ok(tokens) => tokens,
+            Err(_) => {
+                bail!(format!(
+                    "Invalid value for {} '{entry}', expected a number of tokens or 'model=tokens'",
+                    ConfigKey::ContextLimit
+                ))
+            }
+        };
+
+        match entry_model {
+            Some(entry_model) if entry_model == model => {
+                limit = Some(tokens);
+                break;
+            }
+            Some(_) => {}
+            None => limit = Some(tokens),
+        }
+    }
+
+    return Ok(limit.filter(|tokens| return *tokens > 0));
+}
+
+/// The context limit of a model, along with how to keep the history under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContextWindow {
+    pub limit: usize,
+    pub strategy: ContextStrategy,
+}
+
+impl ContextWindow {
+    /// Returns the context window configured for the model, or `None` when it
+    /// has no limit.
+    pub fn from_config(model: &str) -> Result<Option<ContextWindow>> {
+        let strategy = ContextStrategy::parse(&Config::get(ConfigKey::ContextStrategy))?;
+        let limit = parse_limit(&Config::get(ConfigKey::ContextLimit), model)?;
+
+        return Ok(limit.map(|limit| return ContextWindow { limit, strategy }));
+    }
+
+    pub fn is_near_limit(&self, prompt: &BackendPrompt) -> bool {
+        return estimate_prompt_tokens(prompt) * 100 >= self.limit * FIT_THRESHOLD_PERCENT;
+    }
+
+    /// Splits the history into the oldest turns to cut and the turns to keep,
+    /// cutting as few as needed to get well under the limit. Cuts are only
+    /// made before a new user message, so tool calls stay with their results.
+    /// Returns `None` when there's nothing that can be cut.
+    pub fn split_oldest(&self, prompt: &BackendPrompt) -> Option<(Vec<Turn>, Vec<Turn>)> {
+        let turns = &prompt.transcript.turns;
+        let target = self.limit * FIT_TARGET_PERCENT / 100;
+        let mut tokens = estimate_prompt_tokens(prompt);
+        let mut cut = None;
+
+        for idx in 0..=turns.len() {
+            let starts_message = match turns.get(idx) {
+                Some(turn) => turn.role == Role::User && turn.tool_results.is_empty(),
+                None => prompt.tool_results.is_empty(),
+            };
+            if idx > 0 && starts_message {
+                cut = Some(idx);
+                if tokens <= target {
+                    break;
+                }
+            }
+            if let Some(turn) = turns.get(idx) {
+                tokens = tokens.saturating_sub(estimate_turn_tokens(turn));
+            }
+        }
+
+        let mut kept = turns.clone();
+        let cut_turns = kept.drain(..cut?).collect();
+
+        return Some((cut_turns, kept));
+    }
+}
+
+/// Builds the request asking the model to summarize the turns being cut.
+pub fn summary_prompt(prompt: &BackendPrompt, turns: Vec<Turn>) -> BackendPrompt {
+    let mut summary_prompt = BackendPrompt::new(SUMMARY_PROMPT.to_string(), Transcript {
+        turns,
+        ..Transcript::default()
+    });
+    summary_prompt.model = prompt.model.to_string();
+    summary_prompt.system_prompt = prompt.system_prompt.to_string();
+    summary_prompt.params = prompt.params.clone();
+
+    return summary_prompt;
+}
+
+/// Returns the turns standing in for the history replaced by a summary. A
+/// reply is added so user and assistant turns keep alternating, which some
+/// backends require.
+pub fn summary_turns(summary: &str) -> Vec<Turn> {
+    return vec![
+        Turn::new(
+            Role::User,
+            &format!("Here's a summary of our conversation so far:\n\n{summary}"),
+        ),
+        Turn::new(
+            Role::Assistant,
+            "Thanks, I'll keep that in mind as we continue.",
+        ),
+    ];
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use serde_json::json;
+
+use super::ContextStrategy;
+use super::ContextWindow;
+use super::estimate_prompt_tokens;
+use super::estimate_tokens;
+use super::parse_limit;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::Role;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolResult;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+
+fn prompt(text: &str, turns: Vec<Turn>) -> BackendPrompt {
+    return BackendPrompt {
+        text: text.to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            turns,
+            ..Transcript::default()
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+}
+
+fn window(limit: usize) -> ContextWindow {
+    return ContextWindow {
+        limit,
+        strategy: ContextStrategy::Trim,
+    };
+}
+
+#[test]
+fn it_estimates_tokens() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("abcd"), 1);
+    assert_eq!(estimate_tokens("abcde"), 2);
+}
+
+#[test]
+fn it_estimates_prompt_tokens() {
+    let prompt = prompt("a".repeat(40).as_str(), vec![
+        Turn::new(Role::User, &"a".repeat(40)),
+        Turn::new(Role::Assistant, &"a".repeat(40)),
+    ]);
+
+    // Each turn, including the prompt itself, adds 4 tokens of overhead.
+    assert_eq!(estimate_prompt_tokens(&prompt), 42);
+}
+
+#[test]
+fn it_parses_limits() -> Result<()> {
+    assert_eq!(parse_limit("", "model-1")?, None);
+    assert_eq!(parse_limit("0", "model-1")?, None);
+    assert_eq!(parse_limit("8192", "model-1")?, Some(8192));
+    assert_eq!(parse_limit("model-1=4096", "model-1")?, Some(4096));
+    assert_eq!(parse_limit("model-2=4096", "model-1")?, None);
+    assert_eq!(
+        parse_limit("8192, llama3:8b=4096", "llama3:8b")?,
+        Some(4096)
+    );
+    assert_eq!(parse_limit("llama3:8b=4096,8192", "model-1")?, Some(8192));
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_parse_invalid_limits() {
+    let res = parse_limit("model-1=lots", "model-1");
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid value for context-limit 'model-1=lots', expected a number of tokens or 'model=tokens'"
+    );
+}
+
+#[test]
+fn it_parses_strategies() -> Result<()> {
+    assert_eq!(ContextStrategy::parse("")?, ContextStrategy::Trim);
+    assert_eq!(ContextStrategy::parse("trim")?, ContextStrategy::Trim);
+    assert_eq!(
+        ContextStrategy::parse("summarize")?,
+        ContextStrategy::Summarize
+    );
+    assert!(ContextStrategy::parse("forget").is_err());
+
+    return Ok(());
+}
+
+#[test]
+fn it_detects_nearing_the_limit() {
+    let prompt = prompt(&"a".repeat(400), vec![]);
+
+    assert!(!window(200).is_near_limit(&prompt));
+    assert!(window(110).is_near_limit(&prompt));
+}
+
+#[test]
+fn it_splits_the_oldest_turns() {
+    let prompt = prompt("Next", vec![
+        Turn::new(Role::User, &"a".repeat(400)),
+        Turn::new(Role::Assistant, &"a".repeat(400)),
+        Turn::new(Role::User, "Short"),
+        Turn::new(Role::Assistant, "Reply"),
+    ]);
+
+    let (cut, kept) = window(100).split_oldest(&prompt).unwrap();
+
+    assert_eq!(cut.len(), 2);
+    assert_eq!(kept, vec![
+        Turn::new(Role::User, "Short"),
+        Turn::new(Role::Assistant, "Reply"),
+    ]);
+}
+
+#[test]
+fn it_keeps_tool_calls_with_their_results() {
+    let mut call_turn = Turn::new(Role::Assistant, "");
+    call_turn.tool_calls = vec![ToolCall {
+        id: "call_1".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }];
+    let mut results_turn = Turn::new(Role::User, "");
+    results_turn.tool_results = vec![ToolResult {
+        id: "call_1".to_string(),
+        name: "read_file".to_string(),
+        content: "a".repeat(400),
+        is_error: false,
+    }];
+    let prompt = prompt("Next", vec![
+        Turn::new(Role::User, "Read the README"),
+        call_turn.clone(),
+        results_turn.clone(),
+        Turn::new(Role::Assistant, "It says hello"),
+    ]);
+
+    let (cut, kept) = window(100).split_oldest(&prompt).unwrap();
+
+    assert_eq!(cut.len(), 4);
+    assert!(kept.is_empty());
+
+    // While answering tool calls the last turn has to stay, so there's
+    // nothing to cut after the first message.
+    let mut prompt = prompt;
+    prompt.text = "".to_string();
+    prompt.transcript.turns.truncate(2);
+    prompt.tool_results = results_turn.tool_results;
+
+    assert_eq!(window(100).split_oldest(&prompt), None);
+}
@@ -1,9 +1,11 @@
 use std::cmp::Ordering;
 
+use tokio::sync::oneshot;
 use tui_textarea::Input;
 
 use super::BackendResponse;
 use super::Message;
+use super::ToolCall;
 
 #[derive(Clone, Copy, Default, Eq, PartialEq)]
 pub struct Point {
@@ -38,10 +40,30 @@ impl PartialOrd for Point {
 pub enum Event {
     BackendMessage(Message),
     BackendPromptResponse(BackendResponse),
+    BackendRetry(String),
+    /// Shows the progress of a long running command, such as pulling a model,
+    /// updating the same message until the command finishes.
+    BackendProgress(String),
+    /// Asks the user a yes or no question. The answer is sent back through the
+    /// channel.
+    BackendConfirm(String, oneshot::Sender<bool>),
+    /// Asks the user to confirm a tool call. The answer is sent back through
+    /// the channel.
+    BackendToolCall(ToolCall, oneshot::Sender<bool>),
+    /// Shows the output of a tool call while the completion continues.
+    BackendToolResult(Message),
+    /// Warns about something the backend worker did while the completion
+    /// continues, such as cutting the history to fit the context limit.
+    BackendWarning(Message),
+    /// A message for the comparison column at the index.
+    CompareMessage(usize, Message),
+    /// A streamed response for the comparison column at the index.
+    ComparePromptResponse(usize, BackendResponse),
     KeyboardCharInput(Input),
     KeyboardCTRLC(),
     KeyboardCTRLO(),
     KeyboardCTRLR(),
+    KeyboardCTRLT(),
     KeyboardEnter(),
     KeyboardPaste(String),
     UITick(),
@@ -0,0 +1,60 @@
+#[cfg(test)]
+#[path = "fim_test.rs"]
+mod tests;
+
+use std::fs;
+
+use anyhow::Result;
+use anyhow::bail;
+
+use super::EditorContext;
+use super::GenerationParams;
+
+/// A fill in the middle request, asking a code model for what belongs between
+/// the code before and after a point in a file.
+#[derive(Clone, Debug)]
+pub struct FimPrompt {
+    /// The coding language of the file, used to tag the returned code block.
+    pub language: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub params: GenerationParams,
+}
+
+impl FimPrompt {
+    /// Reads the file open in the editor and splits it around the selection.
+    pub fn from_editor_context(context: &EditorContext) -> Result<FimPrompt> {
+        let text = match fs::read_to_string(&context.file_path) {
+            Ok(text) => text,
+            Err(err) => bail!(format!("Failed to read file {}: {err}", context.file_path)),
+        };
+
+        return Ok(FimPrompt::from_text(context, &text));
+    }
+
+    /// Splits the text around the selected lines, which the completion is
+    /// meant to replace. Without a selection the completion goes after the
+    /// line with the cursor. Lines are numbered from 1.
+    pub fn from_text(context: &EditorContext, text: &str) -> FimPrompt {
+        let lines = text.split_inclusive('\n').collect::<Vec<&str>>();
+        let start_line = usize::try_from(context.start_line).unwrap_or(1).max(1);
+        let (prefix_end, suffix_start) = match context.end_line {
+            Some(end_line) => {
+                (
+                    start_line - 1,
+                    usize::try_from(end_line).unwrap_or(start_line),
+                )
+            }
+            None => (start_line, start_line),
+        };
+        let prefix_end = prefix_end.min(lines.len());
+        let suffix_start = suffix_start.clamp(prefix_end, lines.len());
+
+        return FimPrompt {
+            language: context.language.to_string(),
+            prefix: lines[..prefix_end].concat(),
+            suffix: lines[suffix_start..].concat(),
+            params: GenerationParams::from_config().unwrap_or_default(),
+        };
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+
+use anyhow::Result;
+
+use super::FimPrompt;
+use crate::domain::models::EditorContext;
+
+const CODE: &str = "fn main() {\n    let x = 1;\n    let y = 2;\n    println!(\"{x}\");\n}\n";
+
+fn context(start_line: i64, end_line: Option<i64>) -> EditorContext {
+    return EditorContext {
+        file_path: "main.rs".to_string(),
+        language: "rust".to_string(),
+        code: "".to_string(),
+        start_line,
+        end_line,
+    };
+}
+
+#[test]
+fn it_splits_around_the_selection() {
+    let prompt = FimPrompt::from_text(&context(2, Some(3)), CODE);
+
+    assert_eq!(prompt.language, "rust");
+    assert_eq!(prompt.prefix, "fn main() {\n");
+    assert_eq!(prompt.suffix, "    println!(\"{x}\");\n}\n");
+}
+
+#[test]
+fn it_splits_after_the_cursor_without_a_selection() {
+    let prompt = FimPrompt::from_text(&context(2, None), CODE);
+
+    assert_eq!(prompt.prefix, "fn main() {\n    let x = 1;\n");
+    assert_eq!(prompt.suffix, "    let y = 2;\n    println!(\"{x}\");\n}\n");
+}
+
+#[test]
+fn it_clamps_lines_past_the_end_of_the_file() {
+    let prompt = FimPrompt::from_text(&context(4, Some(20)), CODE);
+
+    assert_eq!(
+        prompt.prefix,
+        "fn main() {\n    let x = 1;\n    let y = 2;\n"
+    );
+    assert_eq!(prompt.suffix, "");
+}
+
+#[test]
+fn it_reads_from_the_editor_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("main.rs");
+    fs::write(&path, CODE)?;
+
+    let mut context = context(5, None);
+    context.file_path = path.to_string_lossy().to_string();
+    let prompt = FimPrompt::from_editor_context(&context)?;
+
+    assert_eq!(prompt.prefix, CODE);
+    assert_eq!(prompt.suffix, "");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_for_missing_files() {
+    let res = FimPrompt::from_editor_context(&context(1, None));
+
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .starts_with("Failed to read file main.rs")
+    );
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+#[path = "generation_params_test.rs"]
+mod tests;
+
+use anyhow::Result;
+use anyhow::bail;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
+/// Sampling parameters sent alongside each prompt. Unset values are left out of
+/// requests so each backend falls back to its own defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+    pub stop: Vec<String>,
+}
+
+fn parse_key(key: &str) -> Option<ConfigKey> {
+    return match key.replace('_', "-").as_str() {
+        "temperature" => Some(ConfigKey::Temperature),
+        "max-tokens" => Some(ConfigKey::MaxTokens),
+        "top-p" => Some(ConfigKey::TopP),
+        "stop" => Some(ConfigKey::Stop),
+        _ => None,
+    };
+}
+
+fn parse_float(key: ConfigKey, value: &str, max: f32) -> Result<Option<f32>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match value.parse::<f32>() {
+        Ok(val) if (0.0..=max).contains(&val) => return Ok(Some(val)),
+        _ => {
+            bail!(format!(
+                "Invalid value for {key}: {value}. Expected a number between 0 and {max}."
+            ))
+        }
+    }
+}
+
+fn parse_stop(value: &str) -> Vec<String> {
+    return value
+        .split(',')
+        .filter(|e| return !e.is_empty())
+        .map(|e| return e.to_string())
+        .collect();
+}
+
+impl GenerationParams {
+    /// Builds parameters from the current config, failing on values that can't
+    /// be parsed.
+    pub fn from_config() -> Result<GenerationParams> {
+        let mut params = GenerationParams {
+            temperature: parse_float(
+                ConfigKey::Temperature,
+                &Config::get(ConfigKey::Temperature),
+                2.0,
+            )?,
+            top_p: parse_float(ConfigKey::TopP, &Config::get(ConfigKey::TopP), 1.0)?,
+            stop: parse_stop(&Config::get(ConfigKey::Stop)),
+            ..GenerationParams::default()
+        };
+
+        let max_tokens = Config::get(ConfigKey::MaxTokens);
+        if !max_tokens.is_empty() {
+            match max_tokens.parse::<u32>() {
+                Ok(val) if val > 0 => params.max_tokens = Some(val),
+                _ => {
+                    bail!(format!(
+                        "Invalid value for {}: {max_tokens}. Expected a whole number above 0.",
+                        ConfigKey::MaxTokens
+                    ))
+                }
+            }
+        }
+
+        return Ok(params);
+    }
+
+    /// Validates and stores a parameter from the `/set` slash command. An empty
+    /// value resets the parameter back to the backend's default.
+    pub fn set_config(key: &str, value: &str) -> Result<()> {
+        let config_key = match parse_key(key) {
+            Some(config_key) => config_key,
+            None => {
+                bail!(format!(
+                    "Unknown parameter {key}. Possible values are: temperature, max-tokens, top-p, stop"
+                ))
+            }
+        };
+
+        let previous = Config::get(config_key);
+        Config::set(config_key, value);
+        if let Err(err) = GenerationParams::from_config() {
+            Config::set(config_key, &previous);
+            return Err(err);
+        }
+
+        return Ok(());
+    }
+
+    pub fn format(&self) -> String {
+        let unset = "default".to_string();
+        let temperature = self
+            .temperature
+            .map_or(unset.clone(), |e| return e.to_string());
+        let max_tokens = self
+            .max_tokens
+            .map_or(unset.clone(), |e| return e.to_string());
+        let top_p = self.top_p.map_or(unset.clone(), |e| return e.to_string());
+        let mut stop = self.stop.join(",");
+        if stop.is_empty() {
+            stop = unset;
+        }
+
+        return format!(
+            "- temperature: {temperature}\n- max-tokens: {max_tokens}\n- top-p: {top_p}\n- stop: {stop}"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use super::GenerationParams;
+
+#[test]
+fn it_formats_default_params() {
+    let params = GenerationParams::default();
+    insta::assert_snapshot!(params.format(), @r###"
+    - temperature: default
+    - max-tokens: default
+    - top-p: default
+    - stop: default
+    "###);
+}
+
+#[test]
+fn it_formats_params() {
+    let params = GenerationParams {
+        temperature: Some(0.7),
+        max_tokens: Some(256),
+        top_p: Some(0.9),
+        stop: vec!["END".to_string(), "###".to_string()],
+    };
+    insta::assert_snapshot!(params.format(), @r###"
+    - temperature: 0.7
+    - max-tokens: 256
+    - top-p: 0.9
+    - stop: END,###
+    "###);
+}
+
+#[test]
+fn it_parses_stop_sequences() {
+    assert_eq!(super::parse_stop("END,,###"), vec![
+        "END".to_string(),
+        "###".to_string()
+    ]);
+}
+
+#[test]
+fn it_fails_to_set_unknown_param() {
+    let res = GenerationParams::set_config("frequency", "1");
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Unknown parameter frequency. Possible values are: temperature, max-tokens, top-p, stop"
+    );
+}
+
+#[test]
+fn it_fails_to_set_out_of_range_top_p() {
+    let res = GenerationParams::set_config("top_p", "1.5");
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid value for top-p: 1.5. Expected a number between 0 and 1."
+    );
+}
+
+#[test]
+fn it_fails_to_set_invalid_max_tokens() {
+    let res = GenerationParams::set_config("max-tokens", "0");
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid value for max-tokens: 0. Expected a whole number above 0."
+    );
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+#[path = "image_test.rs"]
+mod tests;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use anyhow::bail;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as b64;
+use serde::Deserialize;
+use serde::Serialize;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_SIGNATURE: &[u8] = b"\xff\xd8\xff";
+
+/// An image attached to a prompt, stored base64 encoded so sessions can be
+/// reloaded without the original file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageAttachment {
+    pub name: String,
+    pub media_type: String,
+    pub data: String,
+}
+
+impl ImageAttachment {
+    pub fn from_path(path: &Path) -> Result<ImageAttachment> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => bail!(format!("Failed to read image {}: {err}", path.display())),
+        };
+        let name = path
+            .file_name()
+            .map(|e| return e.to_string_lossy().to_string())
+            .unwrap_or_else(|| return path.display().to_string());
+
+        return ImageAttachment::from_bytes(&name, &bytes);
+    }
+
+    pub fn from_bytes(name: &str, bytes: &[u8]) -> Result<ImageAttachment> {
+        let media_type = if bytes.starts_with(PNG_SIGNATURE) {
+            "image/png"
+        } else if bytes.starts_with(JPEG_SIGNATURE) {
+            "image/jpeg"
+        } else {
+            bail!(format!(
+                "Image {name} isn't supported, only PNG and JPEG images can be attached"
+            ));
+        };
+
+        return Ok(ImageAttachment {
+            name: name.to_string(),
+            media_type: media_type.to_string(),
+            data: b64.encode(bytes),
+        });
+    }
+
+    /// Returns the image as a `data:` URL.
+    pub fn data_url(&self) -> String {
+        return format!("data:{};base64,{}", self.media_type, self.data);
+    }
+}
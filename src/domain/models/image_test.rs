@@ -0,0 +1,65 @@
+use std::fs;
+
+use anyhow::Result;
+
+use super::ImageAttachment;
+
+const PNG_BYTES: &[u8] = b"\x89PNG\r\n\x1a\nrest";
+
+#[test]
+fn it_detects_png() -> Result<()> {
+    let image = ImageAttachment::from_bytes("diagram.png", PNG_BYTES)?;
+    assert_eq!(image.name, "diagram.png");
+    assert_eq!(image.media_type, "image/png");
+    assert_eq!(image.data, "iVBORw0KGgpyZXN0");
+
+    return Ok(());
+}
+
+#[test]
+fn it_detects_jpeg() -> Result<()> {
+    let image = ImageAttachment::from_bytes("photo.jpg", b"\xff\xd8\xff\xe0")?;
+    assert_eq!(image.media_type, "image/jpeg");
+
+    return Ok(());
+}
+
+#[test]
+fn it_rejects_other_files() {
+    let res = ImageAttachment::from_bytes("notes.txt", b"hello");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Image notes.txt isn't supported, only PNG and JPEG images can be attached"
+    );
+}
+
+#[test]
+fn it_reads_from_path() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("screenshot.png");
+    fs::write(&path, PNG_BYTES)?;
+
+    let image = ImageAttachment::from_path(&path)?;
+    assert_eq!(image.name, "screenshot.png");
+    assert_eq!(image.media_type, "image/png");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_read_missing_path() {
+    let res = ImageAttachment::from_path(std::path::Path::new("/does/not/exist.png"));
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .starts_with("Failed to read image /does/not/exist.png")
+    );
+}
+
+#[test]
+fn it_creates_data_url() -> Result<()> {
+    let image = ImageAttachment::from_bytes("diagram.png", PNG_BYTES)?;
+    assert_eq!(image.data_url(), "data:image/png;base64,iVBORw0KGgpyZXN0");
+
+    return Ok(());
+}
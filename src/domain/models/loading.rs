@@ -7,13 +7,34 @@ use ratatui::widgets::Borders;
 use ratatui::widgets::Padding;
 use ratatui::widgets::Paragraph;
 
-#[derive(Default)]
-pub struct Loading {}
+const DEFAULT_TEXT: &str = "Loading...";
+
+pub struct Loading {
+    text: String,
+}
+
+impl Default for Loading {
+    fn default() -> Loading {
+        return Loading {
+            text: DEFAULT_TEXT.to_string(),
+        };
+    }
+}
 
 impl Loading {
+    /// Replaces the loading text, such as to show which attempt of a retried
+    /// backend request is running.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+    }
+
+    pub fn reset(&mut self) {
+        self.text = DEFAULT_TEXT.to_string();
+    }
+
     pub fn render(&self, frame: &mut Frame, rect: Rect) {
         frame.render_widget(
-            Paragraph::new("Loading...")
+            Paragraph::new(self.text.as_str())
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
@@ -5,18 +5,30 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::Author;
+use super::ImageAttachment;
+use super::SlashCommand;
+use super::Usage;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     Normal,
     Error,
+    Warning,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Message {
     pub author: Author,
     pub text: String,
+    /// Reasoning the model streamed before its answer. It's kept apart from the
+    /// text so it never ends up in code blocks, copies or the transcript.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub thinking: String,
     mtype: MessageType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageAttachment>,
 }
 
 impl Message {
@@ -24,7 +36,10 @@ impl Message {
         return Message {
             author: author.clone(),
             text: text.to_string().replace('\t', "  "),
+            thinking: String::new(),
             mtype: MessageType::Normal,
+            usage: None,
+            images: vec![],
         };
     }
 
@@ -32,7 +47,10 @@ impl Message {
         return Message {
             author: author.clone(),
             text: text.to_string().replace('\t', "  "),
+            thinking: String::new(),
             mtype,
+            usage: None,
+            images: vec![],
         };
     }
 
@@ -40,10 +58,52 @@ impl Message {
         return self.mtype;
     }
 
+    /// Returns the conversation between the user and the model, skipping slash
+    /// commands, their replies, and messages only shown in the UI. Consecutive
+    /// messages from the same author are merged so turns always alternate.
+    pub fn chat_turns(messages: &[Message]) -> Vec<Message> {
+        let mut turns: Vec<Message> = vec![];
+        let mut skip_replies = true;
+
+        for message in messages {
+            if message.mtype == MessageType::Error || message.author == Author::Oatmeal {
+                continue;
+            }
+            if message.author == Author::User {
+                skip_replies = SlashCommand::parse(&message.text).is_some();
+                if skip_replies {
+                    continue;
+                }
+            }
+            // Model messages before the first prompt or after a slash command
+            // come from Oatmeal rather than the backend.
+            if message.author == Author::Model && skip_replies {
+                continue;
+            }
+
+            if turns
+                .last()
+                .is_some_and(|last| return last.author == message.author)
+            {
+                let last = turns.last_mut().unwrap();
+                last.text += &format!("\n\n{}", message.text);
+                last.images.extend(message.images.clone());
+                continue;
+            }
+            turns.push(message.clone());
+        }
+
+        return turns;
+    }
+
     pub fn append(&mut self, text: &str) {
         self.text += &text.replace('\t', "  ");
     }
 
+    pub fn append_thinking(&mut self, thinking: &str) {
+        self.thinking += &thinking.replace('\t', "  ");
+    }
+
     pub fn codeblocks(&self) -> Vec<String> {
         let mut codeblocks: Vec<String> = vec![];
         let mut current_codeblock: Vec<&str> = vec![];
@@ -60,6 +60,16 @@ fn it_executes_append_with_tabs() {
     assert_eq!(msg.text, "Hi there!  It's me!");
 }
 
+#[test]
+fn it_executes_append_thinking() {
+    let mut msg = Message::new(Author::Model, "");
+    msg.append_thinking("Hmm, ");
+    msg.append_thinking("```rust\nfn main() {}\n```");
+    assert_eq!(msg.thinking, "Hmm, ```rust\nfn main() {}\n```");
+    assert_eq!(msg.text, "");
+    assert!(msg.codeblocks().is_empty());
+}
+
 #[test]
 fn it_executes_codeblocks() {
     let msg = Message::new(Author::Oatmeal, codeblock_fixture());
@@ -94,3 +104,32 @@ fn it_executes_codeblocks() {
         print(i)
     "###);
 }
+
+#[test]
+fn it_executes_chat_turns() {
+    let messages = vec![
+        Message::new(Author::Model, "Hey there! What can I do for you?"),
+        Message::new(Author::User, "Hello"),
+        Message::new(Author::Model, "Hi!"),
+        Message::new(Author::User, "/model llama3"),
+        Message::new(Author::Model, "llama3 has entered the chat."),
+        Message::new(Author::User, "How are you?"),
+        Message::new_with_type(Author::Oatmeal, MessageType::Error, "It broke!"),
+        Message::new(Author::User, "Are you there?"),
+        Message::new(Author::Model, "I'm good."),
+        Message::new(Author::Oatmeal, "Copied chat log to clipboard."),
+    ];
+
+    let turns = Message::chat_turns(&messages);
+    let res = turns
+        .iter()
+        .map(|turn| return format!("{:?}: {}", turn.author, turn.text))
+        .collect::<Vec<String>>();
+
+    assert_eq!(res, vec![
+        "User: Hello",
+        "Model: Hi!",
+        "User: How are you?\n\nAre you there?",
+        "Model: I'm good.",
+    ]);
+}
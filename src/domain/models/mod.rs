@@ -1,21 +1,39 @@
 mod action;
 mod author;
 mod backend;
+mod comparison;
+mod context_window;
 mod editor;
 mod event;
+mod fim;
+mod generation_params;
+mod image;
 mod loading;
 mod message;
+mod model_info;
 mod session;
 mod slash_commands;
 mod textarea;
+mod tool;
+mod transcript;
+mod usage;
 
 pub use action::*;
 pub use author::*;
 pub use backend::*;
+pub use comparison::*;
+pub use context_window::*;
 pub use editor::*;
 pub use event::*;
+pub use fim::*;
+pub use generation_params::*;
+pub use image::*;
 pub use loading::*;
 pub use message::*;
+pub use model_info::*;
 pub use session::*;
 pub use slash_commands::*;
 pub use textarea::*;
+pub use tool::*;
+pub use transcript::*;
+pub use usage::*;
@@ -0,0 +1,123 @@
+#[cfg(test)]
+#[path = "model_info_test.rs"]
+mod tests;
+
+/// A model offered by a backend. Details are only set when the backend
+/// reports them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ModelInfo {
+    pub name: String,
+    /// Parameter count as reported by the backend, such as `8.0B`.
+    pub parameter_size: Option<String>,
+    pub quantization: Option<String>,
+    pub context_length: Option<u64>,
+    pub input_token_limit: Option<u64>,
+    pub output_token_limit: Option<u64>,
+    pub owned_by: Option<String>,
+}
+
+impl ModelInfo {
+    pub fn new(name: &str) -> ModelInfo {
+        return ModelInfo {
+            name: name.to_string(),
+            ..ModelInfo::default()
+        };
+    }
+
+    /// Returns the names of the models, in the same order.
+    pub fn names(models: &[ModelInfo]) -> Vec<String> {
+        return models
+            .iter()
+            .map(|model| return model.name.to_string())
+            .collect();
+    }
+
+    /// Formats the models as a table, numbered for use with `/model`. Columns
+    /// without a value for any model are left out.
+    pub fn format_table(models: &[ModelInfo]) -> String {
+        let optional_columns: Vec<(&str, Vec<Option<String>>)> = vec![
+            (
+                "Parameters",
+                models
+                    .iter()
+                    .map(|e| return e.parameter_size.clone())
+                    .collect(),
+            ),
+            (
+                "Quantization",
+                models
+                    .iter()
+                    .map(|e| return e.quantization.clone())
+                    .collect(),
+            ),
+            (
+                "Context",
+                models
+                    .iter()
+                    .map(|e| return e.context_length.map(|e| return e.to_string()))
+                    .collect(),
+            ),
+            (
+                "Input tokens",
+                models
+                    .iter()
+                    .map(|e| return e.input_token_limit.map(|e| return e.to_string()))
+                    .collect(),
+            ),
+            (
+                "Output tokens",
+                models
+                    .iter()
+                    .map(|e| return e.output_token_limit.map(|e| return e.to_string()))
+                    .collect(),
+            ),
+            (
+                "Owner",
+                models.iter().map(|e| return e.owned_by.clone()).collect(),
+            ),
+        ];
+
+        let mut columns: Vec<Vec<String>> = vec![
+            std::iter::once("#".to_string())
+                .chain((1..=models.len()).map(|e| return e.to_string()))
+                .collect(),
+            std::iter::once("Model".to_string())
+                .chain(models.iter().map(|e| return e.name.to_string()))
+                .collect(),
+        ];
+        for (header, values) in optional_columns {
+            if values.iter().all(|e| return e.is_none()) {
+                continue;
+            }
+            columns.push(
+                std::iter::once(header.to_string())
+                    .chain(values.into_iter().map(|e| return e.unwrap_or_default()))
+                    .collect(),
+            );
+        }
+
+        let widths = columns
+            .iter()
+            .map(|column| {
+                return column
+                    .iter()
+                    .map(|e| return e.chars().count())
+                    .max()
+                    .unwrap_or(0);
+            })
+            .collect::<Vec<usize>>();
+
+        return (0..=models.len())
+            .map(|row| {
+                let line = columns
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(column, width)| return format!("{:<width$}", column[row]))
+                    .collect::<Vec<String>>()
+                    .join("  ");
+                return line.trim_end().to_string();
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+    }
+}
@@ -0,0 +1,47 @@
+use super::ModelInfo;
+
+#[test]
+fn it_formats_names_only() {
+    let models = vec![ModelInfo::new("first"), ModelInfo::new("second")];
+
+    assert_eq!(
+        ModelInfo::format_table(&models),
+        "#  Model\n1  first\n2  second"
+    );
+}
+
+#[test]
+fn it_formats_reported_details() {
+    let models = vec![
+        ModelInfo {
+            parameter_size: Some("8.0B".to_string()),
+            quantization: Some("Q4_0".to_string()),
+            context_length: Some(8192),
+            ..ModelInfo::new("llama3:latest")
+        },
+        ModelInfo {
+            parameter_size: Some("7.2B".to_string()),
+            ..ModelInfo::new("mistral:latest")
+        },
+    ];
+
+    assert_eq!(
+        ModelInfo::format_table(&models),
+        [
+            "#  Model           Parameters  Quantization  Context",
+            "1  llama3:latest   8.0B        Q4_0          8192",
+            "2  mistral:latest  7.2B",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn it_returns_names() {
+    let models = vec![ModelInfo::new("first"), ModelInfo::new("second")];
+
+    assert_eq!(ModelInfo::names(&models), vec![
+        "first".to_string(),
+        "second".to_string()
+    ]);
+}
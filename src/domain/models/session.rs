@@ -1,15 +1,58 @@
+#[cfg(test)]
+#[path = "session_test.rs"]
+mod tests;
+
+use anyhow::Result;
+use anyhow::bail;
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::CompareColumn;
 use super::Message;
+use super::TRANSCRIPT_VERSION;
+use super::Transcript;
+
+/// Sessions saved before transcripts existed have none, which is marked with
+/// version 0 so it's rebuilt on load.
+fn legacy_transcript() -> Transcript {
+    return Transcript {
+        version: 0,
+        turns: vec![],
+    };
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct State {
     pub backend_name: String,
     pub backend_model: String,
-    pub backend_context: String,
+    #[serde(default = "legacy_transcript")]
+    pub transcript: Transcript,
+    #[serde(default)]
+    pub system_prompt: String,
     pub editor_language: String,
     pub messages: Vec<Message>,
+    /// The columns of a side-by-side comparison, when one is running.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comparison: Vec<CompareColumn>,
+}
+
+impl State {
+    /// Brings a session saved by an older version up to date. Older sessions
+    /// kept history in a format specific to each backend, so the transcript is
+    /// rebuilt from the chat messages instead.
+    pub fn migrate(&mut self) -> Result<()> {
+        if self.transcript.version > TRANSCRIPT_VERSION {
+            bail!(format!(
+                "The session was saved by a newer version of Oatmeal (transcript version {}). Please upgrade to open it.",
+                self.transcript.version
+            ));
+        }
+        if self.transcript.version == 0 {
+            self.transcript = Transcript::from_messages(&self.messages);
+        }
+
+        return Ok(());
+    }
 }
 
 #[derive(Serialize, Deserialize)]
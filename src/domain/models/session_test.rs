@@ -0,0 +1,115 @@
+use anyhow::Result;
+
+use super::Session;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+
+#[test]
+fn it_migrates_sessions_without_transcript() -> Result<()> {
+    let payload = r#"
+id: abc-123
+version: 0.13.0
+timestamp: 2024-01-01T00:00:00+00:00
+state:
+  backend_name: ollama
+  backend_model: llama2
+  backend_context: "[1, 2, 3]"
+  editor_language: ""
+  messages:
+    - author: Model
+      text: Hey there! What can I do for you?
+      mtype: Normal
+    - author: User
+      text: Hello
+      mtype: Normal
+    - author: Model
+      text: Hi!
+      mtype: Normal
+"#;
+
+    let mut session: Session = serde_yaml::from_str(payload)?;
+    session.state.migrate()?;
+
+    let transcript = session.state.transcript;
+    assert_eq!(transcript.version, TRANSCRIPT_VERSION);
+    assert_eq!(transcript.turns.len(), 2);
+    assert_eq!(transcript.turns[0].role, Role::User);
+    assert_eq!(transcript.turns[0].text, "Hello");
+    assert_eq!(transcript.turns[1].role, Role::Assistant);
+    assert_eq!(transcript.turns[1].text, "Hi!");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_migrate_newer_sessions() -> Result<()> {
+    let payload = r#"
+id: abc-123
+version: 9.0.0
+timestamp: 2024-01-01T00:00:00+00:00
+state:
+  backend_name: ollama
+  backend_model: llama2
+  transcript:
+    version: 99
+    turns: []
+  editor_language: ""
+  messages: []
+"#;
+
+    let mut session: Session = serde_yaml::from_str(payload)?;
+    let res = session.state.migrate();
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The session was saved by a newer version of Oatmeal (transcript version 99). Please upgrade to open it."
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_loads_sessions_with_comparison() -> Result<()> {
+    let payload = r#"
+id: abc-123
+version: 0.14.0
+timestamp: 2024-01-01T00:00:00+00:00
+state:
+  backend_name: ollama
+  backend_model: llama2
+  transcript:
+    version: 1
+    turns: []
+  editor_language: ""
+  messages: []
+  comparison:
+    - target:
+        backend: ollama
+        model: llama3
+      transcript:
+        version: 1
+        turns:
+          - role: user
+            text: Hello
+          - role: assistant
+            text: Hi!
+      messages:
+        - author: User
+          text: Hello
+          mtype: Normal
+        - author: Model
+          text: Hi!
+          mtype: Normal
+"#;
+
+    let session: Session = serde_yaml::from_str(payload)?;
+    let comparison = session.state.comparison;
+
+    assert_eq!(comparison.len(), 1);
+    assert_eq!(comparison[0].target.to_string(), "ollama:llama3");
+    assert_eq!(comparison[0].transcript.turns.len(), 2);
+    assert_eq!(comparison[0].messages.len(), 2);
+    assert!(!comparison[0].waiting);
+
+    return Ok(());
+}
@@ -24,12 +24,20 @@ impl SlashCommand {
         if cmd.is_quit()
             || cmd.is_model_list()
             || cmd.is_model_set()
+            || cmd.is_backend_set()
             || cmd.is_append_code_block()
             || cmd.is_replace_code_block()
             || cmd.is_copy_code_block()
             || cmd.is_copy_chat()
             || cmd.is_help()
             || cmd.is_load_file()
+            || cmd.is_set()
+            || cmd.is_system_prompt()
+            || cmd.is_image()
+            || cmd.is_compare()
+            || cmd.is_model_pull()
+            || cmd.is_model_delete()
+            || cmd.is_fill_in_middle()
         {
             return Some(cmd);
         }
@@ -49,6 +57,18 @@ impl SlashCommand {
         return ["/m", "/model"].contains(&self.command.as_str());
     }
 
+    pub fn is_model_pull(&self) -> bool {
+        return ["/pull"].contains(&self.command.as_str());
+    }
+
+    pub fn is_model_delete(&self) -> bool {
+        return ["/rmmodel"].contains(&self.command.as_str());
+    }
+
+    pub fn is_backend_set(&self) -> bool {
+        return ["/b", "/backend"].contains(&self.command.as_str());
+    }
+
     pub fn is_append_code_block(&self) -> bool {
         return ["/a", "/append"].contains(&self.command.as_str());
     }
@@ -72,4 +92,24 @@ impl SlashCommand {
     pub fn is_load_file(&self) -> bool {
         return ["/f", "/file"].contains(&self.command.as_str());
     }
+
+    pub fn is_set(&self) -> bool {
+        return ["/set"].contains(&self.command.as_str());
+    }
+
+    pub fn is_system_prompt(&self) -> bool {
+        return ["/system"].contains(&self.command.as_str());
+    }
+
+    pub fn is_image(&self) -> bool {
+        return ["/i", "/image"].contains(&self.command.as_str());
+    }
+
+    pub fn is_compare(&self) -> bool {
+        return ["/cmp", "/compare"].contains(&self.command.as_str());
+    }
+
+    pub fn is_fill_in_middle(&self) -> bool {
+        return ["/fim"].contains(&self.command.as_str());
+    }
 }
@@ -86,6 +86,18 @@ fn it_is_not_is_model_set() {
     assert!(!cmd.is_model_set());
 }
 
+#[test]
+fn it_is_short_backend_set() {
+    let cmd = SlashCommand::parse("/b").unwrap();
+    assert!(cmd.is_backend_set());
+}
+#[test]
+fn it_is_backend_set() {
+    let cmd = SlashCommand::parse("/backend claude").unwrap();
+    assert!(cmd.is_backend_set());
+    assert_eq!(cmd.args, vec!["claude"]);
+}
+
 #[test]
 fn it_is_short_append_code_block() {
     let cmd = SlashCommand::parse("/a").unwrap();
@@ -165,3 +177,80 @@ fn it_is_not_copy_code() {
     let cmd = SlashCommand::parse("/copy").unwrap();
     assert!(!cmd.is_copy_code_block());
 }
+
+#[test]
+fn it_is_set() {
+    let cmd = SlashCommand::parse("/set temperature 0.5").unwrap();
+    assert!(cmd.is_set());
+}
+#[test]
+fn it_is_not_set() {
+    let cmd = SlashCommand::parse("/model").unwrap();
+    assert!(!cmd.is_set());
+}
+
+#[test]
+fn it_is_system_prompt() {
+    let cmd = SlashCommand::parse("/system You are a pirate").unwrap();
+    assert!(cmd.is_system_prompt());
+}
+#[test]
+fn it_is_not_system_prompt() {
+    let cmd = SlashCommand::parse("/set").unwrap();
+    assert!(!cmd.is_system_prompt());
+}
+
+#[test]
+fn it_is_image() {
+    let cmd = SlashCommand::parse("/image ./screenshot.png").unwrap();
+    assert!(cmd.is_image());
+}
+#[test]
+fn it_is_not_image() {
+    let cmd = SlashCommand::parse("/model").unwrap();
+    assert!(!cmd.is_image());
+}
+
+#[test]
+fn it_is_compare() {
+    let cmd = SlashCommand::parse("/compare ollama:llama3 openai:gpt-4o").unwrap();
+    assert!(cmd.is_compare());
+}
+#[test]
+fn it_is_not_compare() {
+    let cmd = SlashCommand::parse("/model").unwrap();
+    assert!(!cmd.is_compare());
+}
+
+#[test]
+fn it_is_model_pull() {
+    let cmd = SlashCommand::parse("/pull llama3").unwrap();
+    assert!(cmd.is_model_pull());
+}
+#[test]
+fn it_is_not_model_pull() {
+    let cmd = SlashCommand::parse("/model llama3").unwrap();
+    assert!(!cmd.is_model_pull());
+}
+
+#[test]
+fn it_is_model_delete() {
+    let cmd = SlashCommand::parse("/rmmodel llama3").unwrap();
+    assert!(cmd.is_model_delete());
+}
+#[test]
+fn it_is_not_model_delete() {
+    let cmd = SlashCommand::parse("/pull llama3").unwrap();
+    assert!(!cmd.is_model_delete());
+}
+
+#[test]
+fn it_is_fill_in_middle() {
+    let cmd = SlashCommand::parse("/fim").unwrap();
+    assert!(cmd.is_fill_in_middle());
+}
+#[test]
+fn it_is_not_fill_in_middle() {
+    let cmd = SlashCommand::parse("/file").unwrap();
+    assert!(!cmd.is_fill_in_middle());
+}
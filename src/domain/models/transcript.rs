@@ -0,0 +1,120 @@
+#[cfg(test)]
+#[path = "transcript_test.rs"]
+mod tests;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::Author;
+use super::ImageAttachment;
+use super::Message;
+use super::ToolCall;
+use super::ToolResult;
+
+/// Bumped whenever the transcript format changes, so sessions saved by an
+/// older version can be migrated when they're loaded.
+pub const TRANSCRIPT_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// Reasoning the model returned before calling tools. Claude requires it to be
+/// sent back unchanged, along with its signature, with the tool results.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThinkingBlock {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signature: String,
+    /// Set instead of the text when the reasoning was redacted.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub redacted_data: String,
+}
+
+/// A single turn of the conversation. User turns answering the model's tool
+/// calls carry the tool results instead of text.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turn {
+    pub role: Role,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ImageAttachment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ThinkingBlock>,
+    /// Gemini's signature for the reasoning behind the turn, which it requires
+    /// back with the turn's function calls.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub thought_signature: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_results: Vec<ToolResult>,
+}
+
+impl Turn {
+    pub fn new(role: Role, text: &str) -> Turn {
+        return Turn {
+            role,
+            text: text.to_string(),
+            images: vec![],
+            tool_calls: vec![],
+            thinking: vec![],
+            thought_signature: String::new(),
+            tool_results: vec![],
+        };
+    }
+}
+
+/// The conversation history sent to backends. It's independent of any
+/// backend's request format, which each backend renders on every request, so
+/// a session can continue on another backend or model.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub version: u32,
+    pub turns: Vec<Turn>,
+}
+
+impl Default for Transcript {
+    fn default() -> Transcript {
+        return Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![],
+        };
+    }
+}
+
+impl Transcript {
+    /// Rebuilds a transcript from the chat messages, for sessions saved before
+    /// transcripts existed.
+    pub fn from_messages(messages: &[Message]) -> Transcript {
+        let turns = Message::chat_turns(messages)
+            .into_iter()
+            .map(|message| {
+                let mut role = Role::User;
+                if message.author == Author::Model {
+                    role = Role::Assistant;
+                }
+                let mut turn = Turn::new(role, &message.text);
+                turn.images = message.images;
+                return turn;
+            })
+            .collect();
+
+        return Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.turns.is_empty();
+    }
+
+    pub fn push(&mut self, turn: Turn) {
+        self.turns.push(turn);
+    }
+}
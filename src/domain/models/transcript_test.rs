@@ -0,0 +1,36 @@
+use super::Role;
+use super::TRANSCRIPT_VERSION;
+use super::Transcript;
+use super::Turn;
+use crate::domain::models::Author;
+use crate::domain::models::Message;
+
+#[test]
+fn it_creates_from_messages() {
+    let messages = vec![
+        Message::new(Author::Model, "Hey there! What can I do for you?"),
+        Message::new(Author::User, "Hello"),
+        Message::new(Author::Model, "Hi!"),
+    ];
+
+    let transcript = Transcript::from_messages(&messages);
+
+    assert_eq!(transcript.version, TRANSCRIPT_VERSION);
+    assert_eq!(transcript.turns, vec![
+        Turn::new(Role::User, "Hello"),
+        Turn::new(Role::Assistant, "Hi!"),
+    ]);
+}
+
+#[test]
+fn it_serializes_without_empty_fields() -> anyhow::Result<()> {
+    let mut transcript = Transcript::default();
+    transcript.push(Turn::new(Role::User, "Hello"));
+
+    assert_eq!(
+        serde_json::to_string(&transcript)?,
+        r#"{"version":1,"turns":[{"role":"user","text":"Hello"}]}"#
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+#[path = "usage_test.rs"]
+mod tests;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Token counts and timing for a single backend response. Every field is
+/// optional since not all backends report everything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_tokens: Option<u64>,
+    /// Time the backend reports it spent generating the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation_ms: Option<u64>,
+    /// Wall clock time from sending the request to receiving the final chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl Usage {
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let completion_tokens = self.completion_tokens?;
+        let ms = self.generation_ms.or(self.latency_ms)?;
+        if ms == 0 {
+            return None;
+        }
+
+        return Some(completion_tokens as f64 / (ms as f64 / 1000.0));
+    }
+
+    pub fn format(&self) -> String {
+        let mut parts = vec![];
+        if let Some(prompt_tokens) = self.prompt_tokens {
+            parts.push(format!("{prompt_tokens} in"));
+        }
+        if let Some(completion_tokens) = self.completion_tokens {
+            parts.push(format!("{completion_tokens} out"));
+        }
+        if let Some(latency_ms) = self.latency_ms {
+            parts.push(format!("{:.1}s", latency_ms as f64 / 1000.0));
+        }
+        if let Some(tokens_per_second) = self.tokens_per_second() {
+            parts.push(format!("{tokens_per_second:.1} tok/s"));
+        }
+
+        return parts.join(" · ");
+    }
+}
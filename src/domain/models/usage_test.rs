@@ -0,0 +1,40 @@
+use super::Usage;
+
+#[test]
+fn it_formats_empty_usage() {
+    assert_eq!(Usage::default().format(), "");
+}
+
+#[test]
+fn it_formats_usage() {
+    let usage = Usage {
+        prompt_tokens: Some(12),
+        completion_tokens: Some(40),
+        generation_ms: None,
+        latency_ms: Some(2000),
+    };
+    assert_eq!(usage.format(), "12 in · 40 out · 2.0s · 20.0 tok/s");
+}
+
+#[test]
+fn it_prefers_generation_time_for_tokens_per_second() {
+    let usage = Usage {
+        prompt_tokens: None,
+        completion_tokens: Some(40),
+        generation_ms: Some(1000),
+        latency_ms: Some(4000),
+    };
+    assert_eq!(usage.tokens_per_second(), Some(40.0));
+}
+
+#[test]
+fn it_skips_tokens_per_second_without_timing() {
+    let usage = Usage {
+        prompt_tokens: Some(12),
+        completion_tokens: Some(40),
+        generation_ms: None,
+        latency_ms: None,
+    };
+    assert_eq!(usage.tokens_per_second(), None);
+    assert_eq!(usage.format(), "12 in · 40 out");
+}
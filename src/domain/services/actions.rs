@@ -1,9 +1,19 @@
+#[cfg(test)]
+#[path = "actions_test.rs"]
+mod tests;
+
+use std::env;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
+use super::Tools;
 use super::clipboard::ClipboardService;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -12,19 +22,46 @@ use crate::domain::models::Action;
 use crate::domain::models::Author;
 use crate::domain::models::BackendBox;
 use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::CompareTarget;
+use crate::domain::models::ContextStrategy;
+use crate::domain::models::ContextWindow;
 use crate::domain::models::EditorContext;
 use crate::domain::models::EditorName;
 use crate::domain::models::Event;
+use crate::domain::models::FimPrompt;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
+use crate::domain::models::ModelInfo;
 use crate::domain::models::SlashCommand;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolResult;
+use crate::domain::models::estimate_prompt_tokens;
+use crate::domain::models::summary_prompt;
+use crate::domain::models::summary_turns;
+use crate::infrastructure::backends::BackendManager;
 use crate::infrastructure::editors::EditorManager;
 
+/// Caps how many times the model can call tools in response to one message.
+const MAX_TOOL_ROUNDS: usize = 10;
+
+/// Caps how much of a tool's output is shown in the chat. The model still
+/// receives the full output.
+const MAX_TOOL_DISPLAY_CHARS: usize = 2000;
+
 pub fn help_text() -> String {
     let text = r"
 COMMANDS:
 - /modellist (/ml) - Lists all available models from the backend.
 - /model (/model) [MODEL_NAME,MODEL_INDEX] - Sets the specified model as the active model. You can pass either the model name, or the index from `/modellist`.
+- /pull [MODEL_NAME] - Downloads a model, for backends that can manage their own models such as Ollama.
+- /rmmodel [MODEL_NAME] - Removes a downloaded model, for backends that can manage their own models such as Ollama.
+- /backend (/b) [BACKEND_NAME] - Switches to another backend, or a provider from your config file, keeping the conversation so far.
+- /compare (/cmp) [BACKEND:MODEL...] - Sends each message to two or more backend and model pairs at once, with their answers side by side. Use `/compare off` to go back to the regular chat.
+- /set [PARAMETER?] [VALUE?] - Sets a generation parameter (temperature, max-tokens, top-p, stop) for the rest of the session. Omit the value to reset a parameter, or omit both to list the current values.
+- /image (/i) [PATH] - Attaches a PNG or JPEG image to your next message, for models that support images.
+- /fim - Asks the model to fill in the code between what comes before and after the selection, or after the cursor, in your editor. The code is provided as a code block to use with `/replace` or `/append`. Only supported by backends with code models, such as Mistral.
+- /system [PROMPT?] - Sets the system prompt sent to the model for the rest of the session. Use `/system clear` to remove it, or omit the prompt to show the current one.
 - /append (/a) [CODE_BLOCK_NUMBER?] - Appends code blocks to an editor. See Code Actions for more details.
 - /replace (/r) [CODE_BLOCK_NUMBER?] - Replaces selections with code blocks in an editor. See Code Actions for more details.
 - /copy (/c) [CODE_BLOCK_NUMBER?] - Copies the entire chat history to your clipboard. When a `CODE_BLOCK_NUMBER` is used, only the specified copy blocks are copied to clipboard. See Code Actions for more details.
@@ -39,6 +76,7 @@ HOTKEYS:
 - CTRL+C - Interrupt waiting for prompt response if in progress, otherwise exit.
 - CTRL+O - Insert a line break at the cursor position.
 - CTRL+R - Resubmit your last message to the backend.
+- CTRL+T - Expand or collapse the reasoning of models that think before they answer.
 
 CODE ACTIONS:
 When working with models that provide code, and using an editor integration, Oatmeal has the capabilities to read selected code from an editor, and submit model provided code back in to an editor. Each code block provided by a model is indexed with a (NUMBER) at the beginning of the block to make it easily identifiable.
@@ -58,21 +96,28 @@ The `CODE_BLOCK_NUMBER` allows you to select several code blocks to send back to
 }
 
 async fn model_list(backend: &BackendBox, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
-    let mut models = backend.list_models().await?;
+    let backend_name = backend.name();
+    let mut models = match backend.list_models().await {
+        Ok(models) => models,
+        Err(err) => {
+            bail!(format!(
+                "Failed to list the models for backend {backend_name}. {err}"
+            ))
+        }
+    };
+    if models.is_empty() {
+        tx.send(Event::BackendMessage(Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Error,
+            &format!("Backend {backend_name} has no models available."),
+        )))?;
+        return Ok(());
+    }
     models.sort();
 
-    let res = models
-        .iter()
-        .enumerate()
-        .map(|(idx, model)| {
-            let n = idx + 1;
-            return format!("- ({n}) {model}");
-        })
-        .collect::<Vec<String>>();
-
     tx.send(Event::BackendMessage(Message::new(
         Author::Oatmeal,
-        res.join("\n").as_str(),
+        &ModelInfo::format_table(&models),
     )))?;
 
     return Ok(());
@@ -94,7 +139,29 @@ async fn model_set(
         return Ok(());
     }
 
-    let mut models = backend.list_models().await?;
+    // The model can still be used when the backend can't list its models,
+    // as long as it's picked by name.
+    let mut models = match backend.list_models().await {
+        Ok(models) => ModelInfo::names(&models),
+        Err(err) => {
+            let backend_name = backend.name();
+            if model_name.parse::<usize>().is_ok() {
+                bail!(format!(
+                    "Failed to list the models for backend {backend_name}, so {model_name} can't be used as an index. Set the model by name instead. {err}"
+                ));
+            }
+
+            Config::set(ConfigKey::Model, &model_name);
+            tx.send(Event::BackendMessage(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Warning,
+                &format!(
+                    "Failed to list the models for backend {backend_name}, so {model_name} was set without checking that it exists. {err}"
+                ),
+            )))?;
+            return Ok(());
+        }
+    };
     models.sort();
 
     if let Ok(idx) = model_name.parse::<usize>() {
@@ -112,15 +179,33 @@ async fn model_set(
 
     if !models.contains(&model_name) {
         let backend_name = Config::get(ConfigKey::Backend);
-        let msg = Message::new_with_type(
-            Author::Oatmeal,
-            MessageType::Error,
-            &format!(
-                "No model named {model_name} found in backend {backend_name}. Did you mistype it?"
+        if !backend.can_manage_models() {
+            let msg = Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                &format!(
+                    "No model named {model_name} found in backend {backend_name}. Did you mistype it?"
+                ),
+            );
+            tx.send(Event::BackendMessage(msg))?;
+            return Ok(());
+        }
+
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        tx.send(Event::BackendConfirm(
+            format!(
+                "No model named {model_name} found in backend {backend_name}. Would you like to pull it? (y/n)"
             ),
-        );
-        tx.send(Event::BackendMessage(msg))?;
-        return Ok(());
+            confirm_tx,
+        ))?;
+        if !confirm_rx.await.unwrap_or(false) {
+            tx.send(Event::BackendMessage(Message::new(
+                Author::Oatmeal,
+                &format!("Skipped pulling {model_name}."),
+            )))?;
+            return Ok(());
+        }
+        backend.pull_model(&model_name, tx).await?;
     }
 
     Config::set(ConfigKey::Model, &model_name);
@@ -133,6 +218,100 @@ async fn model_set(
     return Ok(());
 }
 
+async fn model_pull(
+    backend: &BackendBox,
+    tx: &mpsc::UnboundedSender<Event>,
+    model_name: &str,
+) -> Result<()> {
+    backend.pull_model(model_name, tx).await?;
+    tx.send(Event::BackendMessage(Message::new(
+        Author::Oatmeal,
+        &format!("Pulled {model_name}. Use `/model {model_name}` to chat with it."),
+    )))?;
+
+    return Ok(());
+}
+
+async fn model_delete(
+    backend: &BackendBox,
+    tx: &mpsc::UnboundedSender<Event>,
+    model_name: &str,
+) -> Result<()> {
+    backend.delete_model(model_name).await?;
+    tx.send(Event::BackendMessage(Message::new(
+        Author::Oatmeal,
+        &format!("Removed {model_name}."),
+    )))?;
+
+    return Ok(());
+}
+
+async fn fill_in_middle(
+    backend: &BackendBox,
+    prompt: FimPrompt,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<()> {
+    let language = prompt.language.to_string();
+    let code = backend.fill_in_middle(prompt, tx).await?;
+    tx.send(Event::BackendMessage(Message::new(
+        Author::Model,
+        &format!("```{language}\n{}\n```", code.trim_end_matches('\n')),
+    )))?;
+
+    return Ok(());
+}
+
+/// Creates the named backend to use for the rest of the session. The model is
+/// kept if the new backend has it, otherwise its first model is used. When the
+/// new backend can't list its models, the model is kept as is.
+async fn backend_set(
+    name: &str,
+    client: reqwest::Client,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<BackendBox> {
+    let backend = BackendManager::get(name, client)?;
+    if let Err(err) = backend.health_check().await {
+        bail!(format!("Backend {name} isn't reachable. {err}"));
+    }
+
+    let mut model_name = Config::get(ConfigKey::Model);
+    match backend.list_models().await {
+        Ok(models) => {
+            let models = ModelInfo::names(&models);
+            if !models.contains(&model_name) {
+                model_name = models
+                    .first()
+                    .ok_or_else(|| return anyhow!("Backend {name} has no models available"))?
+                    .to_string();
+            }
+        }
+        Err(err) => {
+            let mut warning = format!(
+                "Failed to list the models for backend {name}, so {model_name} is kept without checking that it exists. {err}"
+            );
+            if model_name.is_empty() {
+                warning = format!(
+                    "Failed to list the models for backend {name}, so no model is set. Set one with `/model MODEL_NAME`. {err}"
+                );
+            }
+            tx.send(Event::BackendMessage(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Warning,
+                &warning,
+            )))?;
+        }
+    }
+
+    Config::set(ConfigKey::Backend, name);
+    Config::set(ConfigKey::Model, &model_name);
+    tx.send(Event::BackendMessage(Message::new(
+        Author::Oatmeal,
+        &format!("Switched to backend {name} with model {model_name}."),
+    )))?;
+
+    return Ok(backend);
+}
+
 async fn accept_codeblock(
     context: Option<EditorContext>,
     codeblock: String,
@@ -200,29 +379,297 @@ fn copy_messages(messages: Vec<Message>, tx: &mpsc::UnboundedSender<Event>) -> R
     return Ok(());
 }
 
-fn worker_error(err: anyhow::Error, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
-    tx.send(Event::BackendMessage(Message::new_with_type(
+fn worker_error_message(err: anyhow::Error) -> Message {
+    return Message::new_with_type(
         Author::Oatmeal,
         MessageType::Error,
         &format!("The backend failed with the following error: {err:?}"),
-    )))?;
+    );
+}
+
+fn worker_error(err: anyhow::Error, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
+    tx.send(Event::BackendMessage(worker_error_message(err)))?;
 
     return Ok(());
 }
 
+fn tool_result_message(res: &ToolResult) -> Message {
+    let mut output = res
+        .content
+        .chars()
+        .take(MAX_TOOL_DISPLAY_CHARS)
+        .collect::<String>();
+    if output.len() < res.content.len() {
+        output += "\n...";
+    }
+
+    if res.is_error {
+        return Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Error,
+            &format!("Tool {} failed: {output}", res.name),
+        );
+    }
+
+    return Message::new(
+        Author::Oatmeal,
+        &format!("Tool {} returned:\n\n```\n{output}\n```", res.name),
+    );
+}
+
+/// Asks the user to confirm each tool call before running it. Denied calls are
+/// reported back to the model so it can carry on without them.
+async fn run_tools(
+    calls: &[ToolCall],
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<Vec<ToolResult>> {
+    let tools = Tools::new(env::current_dir()?);
+    let mut results = vec![];
+
+    for call in calls {
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        tx.send(Event::BackendToolCall(call.clone(), confirm_tx))?;
+
+        let res = if confirm_rx.await.unwrap_or(false) {
+            tools.run(call).await
+        } else {
+            ToolResult {
+                id: call.id.to_string(),
+                name: call.name.to_string(),
+                content: "The user denied this tool call.".to_string(),
+                is_error: true,
+            }
+        };
+
+        tx.send(Event::BackendToolResult(tool_result_message(&res)))?;
+        results.push(res);
+    }
+
+    return Ok(results);
+}
+
+/// Asks the model for a summary of the turns being cut from the history.
+async fn summarize(backend: &BackendBox, prompt: BackendPrompt) -> Result<String> {
+    let (summary_tx, mut summary_rx) = mpsc::unbounded_channel::<Event>();
+    backend.get_completion(prompt, &summary_tx).await?;
+    drop(summary_tx);
+
+    let mut summary = String::new();
+    while let Some(event) = summary_rx.recv().await {
+        if let Event::BackendPromptResponse(res) = event {
+            summary += &res.text;
+        }
+    }
+    if summary.trim().is_empty() {
+        bail!("The model returned an empty summary");
+    }
+
+    return Ok(summary.trim().to_string());
+}
+
+/// Keeps the history under the model's context limit by dropping or
+/// summarizing the oldest turns, with a warning when it does.
+async fn fit_context(
+    backend: &BackendBox,
+    prompt: BackendPrompt,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<BackendPrompt> {
+    let mut prompt = prompt;
+    let window = match ContextWindow::from_config(&prompt.model)? {
+        Some(window) => window,
+        None => return Ok(prompt),
+    };
+    if !window.is_near_limit(&prompt) {
+        return Ok(prompt);
+    }
+    // With nothing left to cut the prompt is sent as is, and the backend
+    // reports if it's too long.
+    let (cut_turns, mut kept_turns) = match window.split_oldest(&prompt) {
+        Some(split) => split,
+        None => return Ok(prompt),
+    };
+
+    let cut_len = cut_turns.len();
+    let mut warning = format!(
+        "This conversation is about {} tokens, close to the {} token limit for {}.",
+        estimate_prompt_tokens(&prompt),
+        window.limit,
+        prompt.model
+    );
+    if window.strategy == ContextStrategy::Summarize {
+        match summarize(backend, summary_prompt(&prompt, cut_turns)).await {
+            Ok(summary) => {
+                kept_turns.splice(0..0, summary_turns(&summary));
+                warning += &format!(" The oldest {cut_len} messages were replaced with a summary.");
+            }
+            Err(err) => {
+                warning += &format!(
+                    " Summarizing failed, so the oldest {cut_len} messages were dropped instead: {err}"
+                );
+            }
+        }
+    } else {
+        warning += &format!(" The oldest {cut_len} messages were dropped.");
+    }
+
+    prompt.transcript.turns = kept_turns;
+    tx.send(Event::BackendWarning(Message::new_with_type(
+        Author::Oatmeal,
+        MessageType::Warning,
+        &warning,
+    )))?;
+
+    return Ok(prompt);
+}
+
+/// Picks the backend's first model, for when no model was configured.
+async fn default_model(backend: &BackendBox) -> Result<String> {
+    let backend_name = backend.name();
+    let models = match backend.list_models().await {
+        Ok(models) => models,
+        Err(err) => {
+            bail!(format!(
+                "No model is set, and the models for backend {backend_name} couldn't be listed to pick one. Set a model with `/model MODEL_NAME`. {err}"
+            ))
+        }
+    };
+
+    return match models.first() {
+        Some(model) => Ok(model.name.to_string()),
+        None => {
+            bail!(format!(
+                "No model is set, and backend {backend_name} has no models available. Set a model with `/model MODEL_NAME`."
+            ))
+        }
+    };
+}
+
 async fn completions(
     backend: &BackendBox,
     prompt: BackendPrompt,
     tx: &mpsc::UnboundedSender<Event>,
 ) -> Result<()> {
-    if Config::get(ConfigKey::Model).is_empty() {
-        let models = backend.list_models().await?;
-        Config::set(ConfigKey::Model, &models[0]);
+    let mut prompt = prompt;
+    if prompt.model.is_empty() {
+        prompt.model = default_model(backend).await?;
+        Config::set(ConfigKey::Model, &prompt.model);
     }
 
-    backend.get_completion(prompt, tx).await?;
+    if Config::get(ConfigKey::Tools) == "true" {
+        prompt.tools = Tools::definitions();
+    }
 
-    return Ok(());
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let model = prompt.model.to_string();
+        let tools = prompt.tools.clone();
+        prompt = fit_context(backend, prompt, tx).await?;
+        let res = match completion_round(backend, prompt, tx).await? {
+            Some(res) => res,
+            None => return Ok(()),
+        };
+
+        let transcript = res
+            .transcript
+            .ok_or_else(|| return anyhow!("The backend didn't return a transcript"))?;
+        prompt = BackendPrompt::new(String::new(), transcript);
+        prompt.model = model;
+        prompt.tools = tools;
+        prompt.tool_results = run_tools(&res.tool_calls, tx).await?;
+    }
+
+    bail!(format!(
+        "The model called tools more than {MAX_TOOL_ROUNDS} times in a row, so the response was stopped."
+    ));
+}
+
+/// Requests a single completion. When the model asks to run tools, the final
+/// response is held back and returned so the turn can continue.
+async fn completion_round(
+    backend: &BackendBox,
+    prompt: BackendPrompt,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<Option<BackendResponse>> {
+    // Responses are forwarded through an intermediate channel so the request
+    // latency can be recorded on the final response for every backend.
+    let (backend_tx, mut backend_rx) = mpsc::unbounded_channel::<Event>();
+    let started = Instant::now();
+    let completion = async move {
+        return backend.get_completion(prompt, &backend_tx).await;
+    };
+    let forward = async {
+        let mut tool_calls_res = None;
+        while let Some(event) = backend_rx.recv().await {
+            if let Event::BackendPromptResponse(mut res) = event {
+                if res.done && !res.tool_calls.is_empty() {
+                    tx.send(Event::BackendPromptResponse(BackendResponse {
+                        author: res.author.clone(),
+                        text: res.text.to_string(),
+                        thinking: res.thinking.to_string(),
+                        done: false,
+                        transcript: None,
+                        usage: None,
+                        tool_calls: vec![],
+                    }))?;
+                    tool_calls_res = Some(res);
+                    continue;
+                }
+                if res.done {
+                    let mut usage = res.usage.unwrap_or_default();
+                    usage.latency_ms = Some(started.elapsed().as_millis().try_into()?);
+                    res.usage = Some(usage);
+                }
+                tx.send(Event::BackendPromptResponse(res))?;
+            } else {
+                tx.send(event)?;
+            }
+        }
+        return Ok::<Option<BackendResponse>, anyhow::Error>(tool_calls_res);
+    };
+
+    let (completion_res, forward_res) = tokio::join!(completion, forward);
+    let tool_calls_res = forward_res?;
+    completion_res?;
+
+    return Ok(tool_calls_res);
+}
+
+/// Requests a completion for one comparison column, tagging what the backend
+/// sends with the column's index. Tools aren't offered while comparing, since
+/// every column would ask to confirm its own calls.
+async fn compare_completion(
+    idx: usize,
+    target: CompareTarget,
+    prompt: BackendPrompt,
+    client: reqwest::Client,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<()> {
+    let (column_tx, mut column_rx) = mpsc::unbounded_channel::<Event>();
+    let completion = async move {
+        let backend = BackendManager::get(&target.backend, client)?;
+        completion_round(&backend, prompt, &column_tx).await?;
+        return Ok::<(), anyhow::Error>(());
+    };
+    let forward = async {
+        while let Some(event) = column_rx.recv().await {
+            match event {
+                Event::BackendPromptResponse(res) => {
+                    tx.send(Event::ComparePromptResponse(idx, res))?;
+                }
+                Event::BackendMessage(msg) => {
+                    tx.send(Event::CompareMessage(idx, msg))?;
+                }
+                event => {
+                    tx.send(event)?;
+                }
+            }
+        }
+        return Ok::<(), anyhow::Error>(());
+    };
+
+    let (completion_res, forward_res) = tokio::join!(completion, forward);
+    forward_res?;
+
+    return completion_res;
 }
 
 fn help(tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
@@ -239,15 +686,17 @@ pub struct ActionsService {}
 impl ActionsService {
     pub async fn start(
         backend: BackendBox,
+        client: reqwest::Client,
         tx: mpsc::UnboundedSender<Event>,
         rx: &mut mpsc::UnboundedReceiver<Action>,
     ) -> Result<()> {
-        let backend_arc = Arc::new(backend);
+        let mut backend_arc = Arc::new(backend);
 
         // Lazy default.
         let mut worker: JoinHandle<Result<()>> = tokio::spawn(async {
             return Ok(());
         });
+        let mut compare_workers: Vec<JoinHandle<Result<()>>> = vec![];
 
         loop {
             let event = rx.recv().await;
@@ -265,15 +714,102 @@ impl ActionsService {
                 }
                 Action::BackendAbort() => {
                     worker.abort();
+                    for compare_worker in compare_workers.drain(..) {
+                        compare_worker.abort();
+                    }
+                }
+                Action::BackendSet(name) => {
+                    match backend_set(&name, client.clone(), &tx).await {
+                        Ok(backend) => {
+                            backend_arc = Arc::new(backend);
+                        }
+                        Err(err) => {
+                            tx.send(Event::BackendMessage(Message::new_with_type(
+                                Author::Oatmeal,
+                                MessageType::Error,
+                                &format!("Failed to switch to backend {name}: {err}"),
+                            )))?;
+                        }
+                    }
+                }
+                Action::ModelPull(model_name) => {
+                    let backend_worker = backend_arc.clone();
+                    worker = tokio::spawn(async move {
+                        if let Err(err) = model_pull(&backend_worker, &worker_tx, &model_name).await
+                        {
+                            worker_error(err, &worker_tx)?;
+                        }
+                        return Ok(());
+                    });
+                }
+                Action::FillInMiddle(prompt) => {
+                    let backend_worker = backend_arc.clone();
+                    worker = tokio::spawn(async move {
+                        if let Err(err) = fill_in_middle(&backend_worker, prompt, &worker_tx).await
+                        {
+                            worker_error(err, &worker_tx)?;
+                        }
+                        return Ok(());
+                    });
+                }
+                Action::ModelDelete(model_name) => {
+                    let backend_worker = backend_arc.clone();
+                    worker = tokio::spawn(async move {
+                        if let Err(err) =
+                            model_delete(&backend_worker, &worker_tx, &model_name).await
+                        {
+                            worker_error(err, &worker_tx)?;
+                        }
+                        return Ok(());
+                    });
+                }
+                Action::CompareRequest(requests) => {
+                    compare_workers = requests
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, (target, prompt))| {
+                            let worker_tx = tx.clone();
+                            let worker_client = client.clone();
+                            return tokio::spawn(async move {
+                                let res = compare_completion(
+                                    idx,
+                                    target,
+                                    prompt,
+                                    worker_client,
+                                    &worker_tx,
+                                )
+                                .await;
+                                if let Err(err) = res {
+                                    worker_tx.send(Event::CompareMessage(
+                                        idx,
+                                        worker_error_message(err),
+                                    ))?;
+                                }
+                                return Ok(());
+                            });
+                        })
+                        .collect();
                 }
                 Action::BackendRequest(prompt) => {
                     if let Some(command) = SlashCommand::parse(&prompt.text) {
                         if command.is_model_list() {
-                            model_list(&backend_arc, &tx).await?;
+                            if let Err(err) = model_list(&backend_arc, &tx).await {
+                                worker_error(err, &tx)?;
+                            }
                             continue;
                         }
                         if command.is_model_set() {
-                            model_set(&backend_arc, &tx, &prompt.text).await?;
+                            // Runs as the worker so pulling a missing model can
+                            // be aborted.
+                            let backend_worker = backend_arc.clone();
+                            worker = tokio::spawn(async move {
+                                if let Err(err) =
+                                    model_set(&backend_worker, &worker_tx, &prompt.text).await
+                                {
+                                    worker_error(err, &worker_tx)?;
+                                }
+                                return Ok(());
+                            });
                             continue;
                         }
                         if command.is_help() {
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::backend_set;
+use super::completions;
+use super::model_list;
+use super::model_set;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::configuration::Provider;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendBox;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::Message;
+use crate::domain::models::MessageType;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+
+/// Lists the given models, or fails to list them when there are none.
+struct ListingBackend {
+    models: Option<Vec<ModelInfo>>,
+}
+
+#[async_trait]
+impl Backend for ListingBackend {
+    fn name(&self) -> BackendName {
+        return BackendName::Ollama;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        return match &self.models {
+            Some(models) => Ok(models.clone()),
+            None => bail!("401 Unauthorized"),
+        };
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let mut transcript = prompt.to_transcript();
+        transcript.push(Turn::new(Role::Assistant, &prompt.model));
+        tx.send(Event::BackendPromptResponse(BackendResponse {
+            author: Author::Model,
+            text: prompt.model.to_string(),
+            thinking: String::new(),
+            done: true,
+            transcript: Some(transcript),
+            usage: None,
+            tool_calls: vec![],
+        }))?;
+
+        return Ok(());
+    }
+}
+
+fn backend(models: Option<Vec<ModelInfo>>) -> BackendBox {
+    return Box::new(ListingBackend { models });
+}
+
+fn prompt(model: &str) -> BackendPrompt {
+    let mut prompt = BackendPrompt::new("Hello".to_string(), Transcript::default());
+    prompt.model = model.to_string();
+    return prompt;
+}
+
+fn to_message(event: Option<Event>) -> Result<Message> {
+    return match event {
+        Some(Event::BackendMessage(message)) => Ok(message),
+        _ => bail!("Wrong type from recv"),
+    };
+}
+
+#[tokio::test]
+async fn it_fails_completions_without_a_model_when_listing_fails() {
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let res = completions(&backend(None), prompt(""), &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "No model is set, and the models for backend ollama couldn't be listed to pick one. Set a model with `/model MODEL_NAME`. 401 Unauthorized"
+    );
+}
+
+#[tokio::test]
+async fn it_fails_completions_without_a_model_when_there_are_no_models() {
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let res = completions(&backend(Some(vec![])), prompt(""), &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "No model is set, and backend ollama has no models available. Set a model with `/model MODEL_NAME`."
+    );
+}
+
+#[tokio::test]
+async fn it_gets_completions_from_a_configured_model_when_listing_fails() -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    completions(&backend(None), prompt("llama3:latest"), &tx).await?;
+
+    match rx.recv().await {
+        Some(Event::BackendPromptResponse(res)) => {
+            assert!(res.done);
+            assert_eq!(res.text, "llama3:latest");
+        }
+        _ => bail!("Wrong type from recv"),
+    }
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_model_list_when_listing_fails() {
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let res = model_list(&backend(None), &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to list the models for backend ollama. 401 Unauthorized"
+    );
+}
+
+#[tokio::test]
+async fn it_explains_an_empty_model_list() -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    model_list(&backend(Some(vec![])), &tx).await?;
+
+    let message = to_message(rx.recv().await)?;
+    assert_eq!(message.message_type(), MessageType::Error);
+    assert_eq!(message.text, "Backend ollama has no models available.");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sets_a_model_by_name_when_listing_fails() -> Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    model_set(&backend(None), &tx, "/model model-1").await?;
+
+    let message = to_message(rx.recv().await)?;
+    assert_eq!(message.message_type(), MessageType::Warning);
+    assert_eq!(
+        message.text,
+        "Failed to list the models for backend ollama, so model-1 was set without checking that it exists. 401 Unauthorized"
+    );
+    assert_eq!(Config::get(ConfigKey::Model), "model-1");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_setting_a_model_by_index_when_listing_fails() {
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let res = model_set(&backend(None), &tx, "/model 1").await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to list the models for backend ollama, so 1 can't be used as an index. Set the model by name instead. 401 Unauthorized"
+    );
+}
+
+#[tokio::test]
+async fn it_switches_backend_when_listing_fails() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let health_mock = server
+        .mock("GET", "/")
+        .with_status(200)
+        .create_async()
+        .await;
+    let models_mock = server
+        .mock("GET", "/v1/models")
+        .with_status(401)
+        .with_body(r#"{"error":{"message":"Invalid API key"}}"#)
+        .create_async()
+        .await;
+
+    Config::set(ConfigKey::BackendHealthCheckTimeout, "500");
+    Config::set(ConfigKey::Model, "model-1");
+    Config::set_provider(Provider {
+        name: "listing-fails".to_string(),
+        url: server.url(),
+        token: "".to_string(),
+        headers: Default::default(),
+        models: vec![],
+    });
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    backend_set("listing-fails", reqwest::Client::new(), &tx).await?;
+
+    health_mock.assert();
+    models_mock.assert();
+
+    let warning = to_message(rx.recv().await)?;
+    assert_eq!(warning.message_type(), MessageType::Warning);
+    assert!(warning.text.starts_with(
+        "Failed to list the models for backend listing-fails, so model-1 is kept without checking that it exists."
+    ));
+    let message = to_message(rx.recv().await)?;
+    assert_eq!(
+        message.text,
+        "Switched to backend listing-fails with model model-1."
+    );
+    assert_eq!(Config::get(ConfigKey::Backend), "listing-fails");
+
+    return Ok(());
+}
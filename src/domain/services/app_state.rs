@@ -5,6 +5,7 @@ use anyhow::Result;
 use anyhow::anyhow;
 use ratatui::prelude::Rect;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 
 use super::BubbleList;
 use super::CodeBlocks;
@@ -19,11 +20,19 @@ use crate::domain::models::Author;
 use crate::domain::models::BackendBox;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::CompareColumn;
+use crate::domain::models::CompareTarget;
 use crate::domain::models::EditorBox;
 use crate::domain::models::EditorContext;
+use crate::domain::models::FimPrompt;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
+use crate::domain::models::ModelInfo;
 use crate::domain::models::SlashCommand;
+use crate::domain::models::ToolCall;
+use crate::domain::models::Transcript;
 
 #[cfg(test)]
 #[path = "app_state_test.rs"]
@@ -40,17 +49,23 @@ pub struct AppStateProps {
 }
 
 pub struct AppState<'a> {
-    pub backend_context: String,
     pub bubble_list: BubbleList<'a>,
     pub codeblocks: CodeBlocks,
+    pub compare_lists: Vec<BubbleList<'a>>,
+    pub comparison: Vec<CompareColumn>,
     pub editor_context: Option<EditorContext>,
     pub exit_warning: bool,
     pub last_known_height: usize,
     pub last_known_width: usize,
     pub messages: Vec<Message>,
+    pub pending_confirmation: Option<oneshot::Sender<bool>>,
+    pub pending_images: Vec<ImageAttachment>,
     pub scroll: Scroll,
     pub session_id: String,
     pub sessions_service: Sessions,
+    pub show_thinking: bool,
+    pub showing_progress: bool,
+    pub transcript: Transcript,
     pub waiting_for_backend: bool,
 }
 
@@ -68,17 +83,23 @@ impl<'a> AppState<'a> {
         let theme = Themes::get(&props.theme_name, &props.theme_file)?;
 
         let mut app_state = AppState {
-            backend_context: String::new(),
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            compare_lists: vec![],
+            comparison: vec![],
             editor_context: None,
             exit_warning: false,
             last_known_height: 0,
             last_known_width: 0,
             messages: vec![],
+            pending_confirmation: None,
+            pending_images: vec![],
             scroll: Scroll::default(),
             session_id: Sessions::create_id(),
             sessions_service: props.sessions_service,
+            show_thinking: false,
+            showing_progress: false,
+            transcript: Transcript::default(),
             waiting_for_backend: false,
         };
 
@@ -92,20 +113,7 @@ impl<'a> AppState<'a> {
                     &format!("Hey, it looks like backend {backend_name} isn't running, I can't connect to it. You should double check that before we start talking, otherwise I may crash.\n\nError: {err}"),
                 ));
         } else {
-            let models = props.backend.list_models().await?;
-            if model_name.is_empty() {
-                model_name = models[0].to_string();
-                // TODO refactor this out later.
-                Config::set(ConfigKey::Model, &model_name);
-            } else if !models.contains(&model_name.to_string()) {
-                app_state
-                .messages
-                .push(Message::new_with_type(
-                    Author::Oatmeal,
-                    MessageType::Error,
-                    format!("Model {model_name} doesn't exist for backend {backend_name}. You can use `/modellist` to view all available models, and `/model NAME` to switch models.").as_str(),
-                ));
-            }
+            model_name = app_state.check_model(&props.backend, &model_name).await;
         }
 
         // Fallback to the default intro message when there's no editor context.
@@ -119,29 +127,86 @@ impl<'a> AppState<'a> {
         return Ok(app_state);
     }
 
+    /// Checks the configured model against the backend's models, falling back
+    /// to the first one when none is configured. Problems are shown as messages
+    /// rather than errors, so the chat still starts. Returns the model to use,
+    /// which is empty when there isn't one.
+    async fn check_model(&mut self, backend: &BackendBox, model_name: &str) -> String {
+        let backend_name = backend.name();
+        let models = match backend.list_models().await {
+            Ok(models) => ModelInfo::names(&models),
+            Err(err) => {
+                if model_name.is_empty() {
+                    self.messages.push(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        &format!("I couldn't list the models for backend {backend_name}, so I don't know which model to use. You can set one with `/model NAME`, or with the `--model` flag.\n\nError: {err}"),
+                    ));
+                } else {
+                    self.messages.push(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Warning,
+                        &format!("I couldn't list the models for backend {backend_name}, so I can't check that model {model_name} exists. I'll still send your messages to it.\n\nError: {err}"),
+                    ));
+                }
+
+                return model_name.to_string();
+            }
+        };
+
+        if model_name.is_empty() {
+            if let Some(first) = models.first() {
+                // TODO refactor this out later.
+                Config::set(ConfigKey::Model, first);
+                return first.to_string();
+            }
+
+            self.messages.push(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                &format!("Backend {backend_name} doesn't have any models available. You can use `/pull NAME` to download one if the backend supports it, or `/model NAME` to set one."),
+            ));
+        } else if !models.contains(&model_name.to_string()) {
+            self.messages.push(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                &format!("Model {model_name} doesn't exist for backend {backend_name}. You can use `/modellist` to view all available models, and `/model NAME` to switch models."),
+            ));
+        }
+
+        return model_name.to_string();
+    }
+
     async fn from_session(props: AppStateProps) -> Result<AppState<'a>> {
         let session_id = props.session_id.clone().unwrap().to_string();
         let session = props.sessions_service.load(&session_id).await?;
         let theme = Themes::get(&props.theme_name, &props.theme_file)?;
 
         let mut app_state = AppState {
-            backend_context: session.state.backend_context,
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            compare_lists: vec![],
+            comparison: vec![],
             editor_context: None,
             exit_warning: false,
             last_known_height: 0,
             last_known_width: 0,
             messages: session.state.messages,
+            pending_confirmation: None,
+            pending_images: vec![],
             scroll: Scroll::default(),
             session_id,
             sessions_service: props.sessions_service,
+            show_thinking: false,
+            showing_progress: false,
+            transcript: session.state.transcript,
             waiting_for_backend: false,
         };
 
         app_state
             .codeblocks
             .replace_from_messages(&app_state.messages);
+        app_state.set_comparison(session.state.comparison);
 
         if props.editor.health_check().await.is_ok() {
             app_state.editor_context = props.editor.get_context().await?;
@@ -179,26 +244,36 @@ impl<'a> AppState<'a> {
     }
 
     pub fn handle_backend_response(&mut self, msg: BackendResponse) {
+        // Tool calls add messages in between, so the response only continues
+        // the last message when the model wrote it.
         let last_message = self.messages.last_mut().unwrap();
-        if last_message.author != Author::User {
+        if last_message.author == Author::Model {
             last_message.append(&msg.text);
         } else {
             self.messages.push(Message::new(msg.author, &msg.text));
         }
+        self.messages
+            .last_mut()
+            .unwrap()
+            .append_thinking(&msg.thinking);
+
+        if msg.usage.is_some() {
+            self.messages.last_mut().unwrap().usage = msg.usage;
+        }
 
         self.sync_dependants();
 
         if msg.done {
             self.waiting_for_backend = false;
-            if let Some(ctx) = msg.context {
-                self.backend_context = ctx;
+            if let Some(transcript) = msg.transcript {
+                self.transcript = transcript;
             }
 
-            if self.backend_context.is_empty() {
+            if self.transcript.is_empty() {
                 self.add_message(Message::new_with_type(
                     Author::Oatmeal,
                     MessageType::Error,
-                    "Error: No context was provided by the backend upon completion. Please report this bug on Github."
+                    "Error: No transcript was provided by the backend upon completion. Please report this bug on Github."
                 ));
                 self.sync_dependants();
             }
@@ -264,9 +339,159 @@ impl<'a> AppState<'a> {
                 self.waiting_for_backend = true;
             }
 
-            // Reset backend context on model switch.
-            if command.is_model_set() {
-                self.backend_context = String::new();
+            if command.is_backend_set() {
+                should_continue = true;
+                if command.args.is_empty() {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        "You must specify a backend name. Usage: /backend <name>",
+                    ));
+                    return Ok((should_break, should_continue));
+                }
+
+                tx.send(Action::BackendSet(command.args[0].to_string()))?;
+                self.waiting_for_backend = true;
+            }
+
+            if command.is_model_pull() || command.is_model_delete() {
+                should_continue = true;
+                if command.args.is_empty() {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        "You must specify a model name. Usage: /pull <model> or /rmmodel <model>",
+                    ));
+                    return Ok((should_break, should_continue));
+                }
+
+                let model_name = command.args[0].to_string();
+                if command.is_model_pull() {
+                    tx.send(Action::ModelPull(model_name))?;
+                } else {
+                    tx.send(Action::ModelDelete(model_name))?;
+                }
+                self.waiting_for_backend = true;
+            }
+
+            if command.is_fill_in_middle() {
+                should_continue = true;
+                let prompt = match &self.editor_context {
+                    Some(context) => FimPrompt::from_editor_context(context),
+                    None => {
+                        Err(anyhow!(
+                            "You must open Oatmeal from an editor with a file open to use /fim."
+                        ))
+                    }
+                };
+                match prompt {
+                    Ok(prompt) => {
+                        tx.send(Action::FillInMiddle(prompt))?;
+                        self.waiting_for_backend = true;
+                    }
+                    Err(err) => {
+                        self.add_message(Message::new_with_type(
+                            Author::Oatmeal,
+                            MessageType::Error,
+                            &err.to_string(),
+                        ));
+                    }
+                }
+            }
+
+            if command.is_compare() {
+                should_continue = true;
+                self.handle_compare_command(&command.args);
+            }
+
+            if command.is_set() {
+                should_continue = true;
+                if command.args.is_empty() {
+                    let params = GenerationParams::from_config().unwrap_or_default();
+                    self.add_message(Message::new(
+                        Author::Oatmeal,
+                        &format!("Generation parameters:\n{}", params.format()),
+                    ));
+                    return Ok((should_break, should_continue));
+                }
+
+                let key = &command.args[0];
+                let value = command.args[1..].join(" ");
+                match GenerationParams::set_config(key, &value) {
+                    Ok(_) => {
+                        let mut display_value = value.as_str();
+                        if display_value.is_empty() {
+                            display_value = "default";
+                        }
+                        self.add_message(Message::new(
+                            Author::Oatmeal,
+                            &format!("Set {key} to {display_value}."),
+                        ));
+                    }
+                    Err(err) => {
+                        self.add_message(Message::new_with_type(
+                            Author::Oatmeal,
+                            MessageType::Error,
+                            &err.to_string(),
+                        ));
+                    }
+                }
+            }
+
+            if command.is_system_prompt() {
+                should_continue = true;
+                if command.args.is_empty() {
+                    let mut system_prompt = Config::get(ConfigKey::SystemPrompt);
+                    if system_prompt.is_empty() {
+                        system_prompt = "No system prompt is set.".to_string();
+                    }
+                    self.add_message(Message::new(Author::Oatmeal, &system_prompt));
+                    return Ok((should_break, should_continue));
+                }
+
+                let mut system_prompt = command.args.join(" ");
+                if system_prompt == "clear" {
+                    system_prompt = String::new();
+                }
+                Config::set(ConfigKey::SystemPrompt, &system_prompt);
+
+                let mut msg = "System prompt cleared.";
+                if !system_prompt.is_empty() {
+                    msg = "System prompt set.";
+                }
+                self.add_message(Message::new(Author::Oatmeal, msg));
+            }
+
+            if command.is_image() {
+                should_continue = true;
+                if command.args.is_empty() {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        "You must specify a path. Usage: /image <path>",
+                    ));
+                    return Ok((should_break, should_continue));
+                }
+                let file_path = PathBuf::from(command.args.join(" "));
+                match ImageAttachment::from_path(&file_path) {
+                    Ok(image) => {
+                        self.add_message(Message::new(
+                            Author::Oatmeal,
+                            &format!(
+                                "Attached {}, it will be sent with your next message.",
+                                image.name
+                            ),
+                        ));
+                        self.pending_images.push(image);
+                    }
+                    Err(err) => {
+                        self.add_message(Message::new_with_type(
+                            Author::Oatmeal,
+                            MessageType::Error,
+                            &err.to_string(),
+                        ));
+                    }
+                }
             }
 
             if command.is_load_file() {
@@ -283,8 +508,8 @@ impl<'a> AppState<'a> {
                 match fs::read_to_string(&file_path) {
                     Ok(content) => {
                         self.waiting_for_backend = true;
-                        tx.send(Action::BackendRequest(BackendPrompt {
-                            text: format!(
+                        tx.send(Action::BackendRequest(BackendPrompt::new(
+                            format!(
                                 r#"I have loaded the file {}:
                                 ```
                                 {}
@@ -295,8 +520,8 @@ impl<'a> AppState<'a> {
                                 content,
                                 file_path.display(),
                             ),
-                            backend_context: self.backend_context.clone(),
-                        }))?;
+                            self.transcript.clone(),
+                        )))?;
                     }
                     Err(err) => {
                         self.add_message(Message::new_with_type(
@@ -312,25 +537,226 @@ impl<'a> AppState<'a> {
         return Ok((should_break, should_continue));
     }
 
+    fn handle_compare_command(&mut self, args: &[String]) {
+        if args.len() == 1 && args[0] == "off" {
+            self.set_comparison(vec![]);
+            self.add_message(Message::new(Author::Oatmeal, "Stopped comparing models."));
+            return;
+        }
+
+        let targets = args
+            .iter()
+            .filter(|arg| return !arg.is_empty())
+            .map(|arg| return CompareTarget::parse(arg))
+            .collect::<Result<Vec<CompareTarget>>>();
+        let err = match targets {
+            Ok(targets) if targets.len() >= 2 => {
+                let names = targets
+                    .iter()
+                    .map(|target| return target.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                self.set_comparison(targets.into_iter().map(CompareColumn::new).collect());
+                self.add_message(Message::new(
+                    Author::Oatmeal,
+                    &format!(
+                        "Comparing {names}. Your messages are sent to each of them until you run `/compare off`."
+                    ),
+                ));
+                return;
+            }
+            Ok(_) => "You must specify at least two targets. Usage: /compare <backend:model> <backend:model>".to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        self.add_message(Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Error,
+            &err,
+        ));
+    }
+
+    pub fn is_comparing(&self) -> bool {
+        return !self.comparison.is_empty();
+    }
+
+    fn set_comparison(&mut self, comparison: Vec<CompareColumn>) {
+        self.compare_lists = comparison
+            .iter()
+            .map(|column| {
+                let mut bubble_list = BubbleList::with_model_name(
+                    self.bubble_list.theme.clone(),
+                    &column.target.to_string(),
+                );
+                bubble_list.set_show_thinking(self.show_thinking);
+                return bubble_list;
+            })
+            .collect();
+        self.comparison = comparison;
+        self.sync_dependants();
+    }
+
+    /// Sends the user's message to every comparison column, each with its own
+    /// history.
+    pub fn send_compare_prompt(
+        &mut self,
+        input_str: &str,
+        tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        let images = std::mem::take(&mut self.pending_images);
+        let requests = self
+            .comparison
+            .iter_mut()
+            .map(|column| {
+                column.waiting = true;
+                if let Some(message) = column.messages.last_mut() {
+                    message.images = images.clone();
+                }
+
+                let mut prompt = column.prompt(input_str);
+                prompt.images = images.clone();
+                return (column.target.clone(), prompt);
+            })
+            .collect();
+
+        tx.send(Action::CompareRequest(requests))?;
+        self.waiting_for_backend = true;
+
+        return Ok(());
+    }
+
+    pub fn handle_compare_response(&mut self, idx: usize, msg: BackendResponse) {
+        if let Some(column) = self.comparison.get_mut(idx) {
+            column.handle_backend_response(msg);
+        }
+        self.sync_compare_waiting();
+    }
+
+    /// Shows a message in one comparison column. These are only sent when the
+    /// column's request failed, so it's no longer waiting on a response.
+    pub fn handle_compare_message(&mut self, idx: usize, msg: Message) {
+        if let Some(column) = self.comparison.get_mut(idx) {
+            column.messages.push(msg);
+            column.waiting = false;
+        }
+        self.sync_compare_waiting();
+    }
+
+    fn sync_compare_waiting(&mut self) {
+        self.waiting_for_backend = self.comparison.iter().any(|column| return column.waiting);
+        self.sync_dependants();
+    }
+
+    /// Asks the user to confirm a tool call the model wants to run.
+    pub fn handle_tool_call(&mut self, call: ToolCall, confirm_tx: oneshot::Sender<bool>) {
+        self.handle_confirm(
+            &format!(
+                "The model wants to run `{}`. Allow it? (y/n)",
+                call.describe()
+            ),
+            confirm_tx,
+        );
+    }
+
+    /// Asks the user a yes or no question, answered by their next message.
+    pub fn handle_confirm(&mut self, question: &str, confirm_tx: oneshot::Sender<bool>) {
+        self.add_message(Message::new(Author::Oatmeal, question));
+        self.pending_confirmation = Some(confirm_tx);
+        self.waiting_for_backend = false;
+    }
+
+    /// Answers a pending question with the user's input, where anything other
+    /// than yes is a no. Returns false when no question is waiting.
+    pub fn answer_confirmation(&mut self, input_str: &str) -> bool {
+        let confirm_tx = match self.pending_confirmation.take() {
+            Some(confirm_tx) => confirm_tx,
+            None => return false,
+        };
+
+        let allowed = matches!(input_str.trim().to_lowercase().as_str(), "y" | "yes");
+        // The request may have been aborted while waiting on the answer.
+        let _ = confirm_tx.send(allowed);
+        self.waiting_for_backend = true;
+
+        return true;
+    }
+
+    /// Moves images attached with `/image` onto the prompt, recording them on
+    /// the last message so they're kept in the session.
+    pub fn attach_pending_images(&mut self, prompt: &mut BackendPrompt) {
+        if self.pending_images.is_empty() {
+            return;
+        }
+
+        let images = std::mem::take(&mut self.pending_images);
+        if let Some(message) = self.messages.last_mut() {
+            message.images = images.clone();
+        }
+        prompt.images = images;
+    }
+
     pub fn set_rect(&mut self, rect: Rect) {
         self.last_known_width = rect.width.into();
         self.last_known_height = rect.height.into();
         self.sync_dependants();
     }
 
+    /// Expands or collapses the reasoning shown above the model's answers.
+    pub fn toggle_thinking(&mut self) {
+        self.show_thinking = !self.show_thinking;
+        self.bubble_list.set_show_thinking(self.show_thinking);
+        for bubble_list in self.compare_lists.iter_mut() {
+            bubble_list.set_show_thinking(self.show_thinking);
+        }
+        self.sync_dependants();
+    }
+
+    /// Shows the progress of a long running command, replacing the previous
+    /// progress message while it's still the latest one.
+    pub fn handle_progress(&mut self, text: &str) {
+        if self.showing_progress
+            && let Some(last_message) = self.messages.last_mut()
+        {
+            last_message.text = text.to_string();
+            self.sync_dependants();
+            return;
+        }
+
+        self.add_message(Message::new(Author::Oatmeal, text));
+        self.showing_progress = true;
+    }
+
+    /// Adds a message to the chat, or to every column while comparing.
     pub fn add_message(&mut self, message: Message) {
-        self.messages.push(message);
+        self.showing_progress = false;
+        if self.is_comparing() {
+            for column in self.comparison.iter_mut() {
+                column.messages.push(message.clone());
+            }
+        } else {
+            self.messages.push(message);
+            self.codeblocks.replace_from_messages(&self.messages);
+        }
         self.sync_dependants();
         self.scroll.last();
     }
 
     fn sync_dependants(&mut self) {
-        self.bubble_list
-            .set_messages(&self.messages, self.last_known_width);
+        let mut lines_len = 0;
+        if self.is_comparing() {
+            let column_width = self.last_known_width / self.comparison.len();
+            for (column, bubble_list) in self.comparison.iter().zip(self.compare_lists.iter_mut()) {
+                bubble_list.set_messages(&column.messages, column_width);
+                lines_len = lines_len.max(bubble_list.len());
+            }
+        } else {
+            self.bubble_list
+                .set_messages(&self.messages, self.last_known_width);
+            lines_len = self.bubble_list.len();
+        }
 
         let scrollbar_at_bottom = self.scroll.is_position_at_last();
-        self.scroll
-            .set_state(self.bubble_list.len(), self.last_known_height);
+        self.scroll.set_state(lines_len, self.last_known_height);
 
         if self.waiting_for_backend && scrollbar_at_bottom {
             self.scroll.last();
@@ -341,9 +767,10 @@ impl<'a> AppState<'a> {
         self.sessions_service
             .save(
                 &self.session_id,
-                &self.backend_context,
+                &self.transcript,
                 &self.editor_context,
                 &self.messages,
+                &self.comparison,
             )
             .await?;
 
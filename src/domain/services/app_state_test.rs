@@ -1,18 +1,31 @@
 use anyhow::Result;
 use anyhow::bail;
+use async_trait::async_trait;
 use test_utils::codeblock_fixture;
 use test_utils::insta_snapshot;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 
 use super::AppState;
 use crate::domain::models::AcceptType;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendBox;
 use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::EditorContext;
 use crate::domain::models::EditorName;
+use crate::domain::models::Event;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ToolCall;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
 use crate::domain::services::AppStateProps;
 use crate::domain::services::BubbleList;
 use crate::domain::services::CodeBlocks;
@@ -26,17 +39,23 @@ impl Default for AppState<'static> {
     fn default() -> AppState<'static> {
         let theme = Themes::get("base16-onedark", "").unwrap();
         return AppState {
-            backend_context: "".to_string(),
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            compare_lists: vec![],
+            comparison: vec![],
             editor_context: None,
             exit_warning: false,
             last_known_height: 300,
             last_known_width: 100,
             messages: vec![],
+            pending_confirmation: None,
+            pending_images: vec![],
             session_id: "test".to_string(),
             scroll: Scroll::default(),
             sessions_service: Sessions::default(),
+            show_thinking: false,
+            showing_progress: false,
+            transcript: Transcript::default(),
             waiting_for_backend: false,
         };
     }
@@ -202,7 +221,197 @@ mod handle_slash_commands {
         assert!(!should_break);
         assert!(!should_continue);
         assert!(!app_state.waiting_for_backend);
-        assert!(app_state.backend_context.is_empty());
+        assert!(app_state.transcript.is_empty());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_handles_backend_set() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.add_message(Message::new(Author::User, "Hello world"));
+
+        let (should_break, should_continue) =
+            app_state.handle_slash_commands("/backend claude", &tx)?;
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        let event = rx.blocking_recv().unwrap();
+        match event {
+            Action::BackendSet(name) => {
+                assert_eq!(name, "claude");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_handles_model_pull() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/pull llama3", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::ModelPull(name) => {
+                assert_eq!(name, "llama3");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_handles_model_delete() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/rmmodel llama3", &tx)?;
+
+        assert!(should_continue);
+        match rx.blocking_recv().unwrap() {
+            Action::ModelDelete(name) => {
+                assert_eq!(name, "llama3");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_missing_backend_name() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/backend", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        assert_eq!(
+            last_message.text,
+            "You must specify a backend name. Usage: /backend <name>"
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_invalid_set() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (should_break, should_continue) =
+            app_state.handle_slash_commands("/set temperature 5", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(last_message.author, Author::Oatmeal);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @"Invalid value for temperature: 5. Expected a number between 0 and 2.");
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_attaches_image_to_next_prompt() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("screenshot.png");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n")?;
+
+        let (should_break, should_continue) =
+            app_state.handle_slash_commands(&format!("/image {}", path.display()), &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert_eq!(last_message.message_type(), MessageType::Normal);
+        insta::assert_snapshot!(last_message.text, @"Attached screenshot.png, it will be sent with your next message.");
+        assert_eq!(app_state.pending_images.len(), 1);
+
+        app_state.add_message(Message::new(Author::User, "What is this?"));
+        let mut prompt = BackendPrompt::new("What is this?".to_string(), Transcript::default());
+        app_state.attach_pending_images(&mut prompt);
+
+        assert_eq!(prompt.images.len(), 1);
+        assert_eq!(app_state.messages.last().unwrap().images, prompt.images);
+        assert!(app_state.pending_images.is_empty());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_invalid_image() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/image", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(should_continue);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @"You must specify a path. Usage: /image <path>");
+        assert!(app_state.pending_images.is_empty());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_handles_fill_in_middle() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("main.py");
+        std::fs::write(&path, "def add(a, b):\n    pass\nprint(add(1, 2))\n")?;
+        app_state.editor_context = Some(EditorContext {
+            file_path: path.display().to_string(),
+            language: "python".to_string(),
+            code: "    pass".to_string(),
+            start_line: 2,
+            end_line: Some(2),
+        });
+
+        let (_, should_continue) = app_state.handle_slash_commands("/fim", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::FillInMiddle(prompt) => {
+                assert_eq!(prompt.language, "python");
+                assert_eq!(prompt.prefix, "def add(a, b):\n");
+                assert_eq!(prompt.suffix, "print(add(1, 2))\n");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_fill_in_middle_without_editor() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/fim", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @"You must open Oatmeal from an editor with a file open to use /fim.");
 
         return Ok(());
     }
@@ -220,8 +429,17 @@ mod handle_backend_response {
         let backend_response = BackendResponse {
             author: Author::Model,
             text: "All done!".to_string(),
+            thinking: "".to_string(),
             done: true,
-            context: Some("icanrememberthingsnow".to_string()),
+            transcript: Some(Transcript {
+                version: TRANSCRIPT_VERSION,
+                turns: vec![
+                    Turn::new(Role::User, "Do something for me!"),
+                    Turn::new(Role::Assistant, "All done!"),
+                ],
+            }),
+            usage: None,
+            tool_calls: vec![],
         };
         app_state.handle_backend_response(backend_response);
 
@@ -237,8 +455,11 @@ mod handle_backend_response {
         let backend_response = BackendResponse {
             author: Author::Model,
             text: "All done!".to_string(),
+            thinking: "".to_string(),
             done: true,
-            context: Some("".to_string()),
+            transcript: Some(Transcript::default()),
+            usage: None,
+            tool_calls: vec![],
         };
         app_state.handle_backend_response(backend_response);
 
@@ -248,15 +469,311 @@ mod handle_backend_response {
             MessageType::Error
         );
     }
+
+    #[test]
+    fn it_starts_a_new_message_after_tool_results() {
+        let mut app_state = AppState::default();
+        app_state
+            .messages
+            .push(Message::new(Author::Oatmeal, "Tool read_file returned:"));
+        let backend_response = BackendResponse {
+            author: Author::Model,
+            text: "The readme says hello.".to_string(),
+            thinking: "".to_string(),
+            done: false,
+            transcript: None,
+            usage: None,
+            tool_calls: vec![],
+        };
+        app_state.handle_backend_response(backend_response);
+
+        assert_eq!(app_state.messages.len(), 2);
+        assert_eq!(app_state.messages[1].author, Author::Model);
+        assert_eq!(app_state.messages[1].text, "The readme says hello.");
+    }
+}
+
+mod answer_confirmation {
+    use super::*;
+
+    fn call() -> ToolCall {
+        return ToolCall {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+            arguments: serde_json::json!({"path": "README.md"}),
+        };
+    }
+
+    #[test]
+    fn it_allows_tool_call() -> Result<()> {
+        let mut app_state = AppState::default();
+        let (confirm_tx, mut confirm_rx) = oneshot::channel();
+        app_state.handle_tool_call(call(), confirm_tx);
+
+        assert_eq!(
+            app_state.messages.last().unwrap().text,
+            r#"The model wants to run `read_file({"path":"README.md"})`. Allow it? (y/n)"#
+        );
+        assert!(!app_state.waiting_for_backend);
+
+        assert!(app_state.answer_confirmation("Yes"));
+        assert!(confirm_rx.try_recv()?);
+        assert!(app_state.waiting_for_backend);
+        assert!(app_state.pending_confirmation.is_none());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_denies_tool_call() -> Result<()> {
+        let mut app_state = AppState::default();
+        let (confirm_tx, mut confirm_rx) = oneshot::channel();
+        app_state.handle_tool_call(call(), confirm_tx);
+
+        assert!(app_state.answer_confirmation("no thanks"));
+        assert!(!confirm_rx.try_recv()?);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_ignores_input_without_pending_confirmation() {
+        let mut app_state = AppState::default();
+        assert!(!app_state.answer_confirmation("y"));
+        assert!(!app_state.waiting_for_backend);
+    }
+}
+
+mod handle_progress {
+    use super::*;
+
+    #[test]
+    fn it_updates_the_progress_message() {
+        let mut app_state = AppState::default();
+        app_state.handle_progress("Pulling llama3: 10%");
+        app_state.handle_progress("Pulling llama3: 20%");
+
+        assert_eq!(app_state.messages.len(), 1);
+        assert_eq!(app_state.messages[0].text, "Pulling llama3: 20%");
+
+        app_state.add_message(Message::new(Author::Oatmeal, "Pulled llama3."));
+        app_state.handle_progress("Pulling mistral: 10%");
+
+        assert_eq!(app_state.messages.len(), 3);
+        assert_eq!(app_state.messages[2].text, "Pulling mistral: 10%");
+    }
+}
+
+mod compare {
+    use super::*;
+
+    #[test]
+    fn it_starts_comparing() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) =
+            app_state.handle_slash_commands("/compare ollama:llama3 openai:gpt-4o", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.is_comparing());
+        assert_eq!(app_state.comparison.len(), 2);
+        assert_eq!(app_state.compare_lists.len(), 2);
+        assert_eq!(app_state.comparison[1].target.to_string(), "openai:gpt-4o");
+        assert_eq!(
+            app_state.comparison[0].messages[0].text,
+            "Comparing ollama:llama3, openai:gpt-4o. Your messages are sent to each of them until you run `/compare off`."
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_single_target() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        app_state.handle_slash_commands("/compare ollama:llama3", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(!app_state.is_comparing());
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        assert_eq!(
+            last_message.text,
+            "You must specify at least two targets. Usage: /compare <backend:model> <backend:model>"
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_sends_prompts_to_each_target() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.handle_slash_commands("/compare ollama:llama3 openai:gpt-4o", &tx)?;
+
+        app_state.add_message(Message::new(Author::User, "Hello"));
+        app_state.send_compare_prompt("Hello", &tx)?;
+
+        assert!(app_state.waiting_for_backend);
+        assert!(app_state.messages.is_empty());
+        match rx.blocking_recv().unwrap() {
+            Action::CompareRequest(requests) => {
+                assert_eq!(requests.len(), 2);
+                assert_eq!(requests[0].0.backend, "ollama");
+                assert_eq!(requests[0].1.model, "llama3");
+                assert_eq!(requests[1].1.model, "gpt-4o");
+                assert_eq!(requests[1].1.text, "Hello");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_waits_for_every_target() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.handle_slash_commands("/compare ollama:llama3 openai:gpt-4o", &tx)?;
+        app_state.send_compare_prompt("Hello", &tx)?;
+
+        app_state.handle_compare_response(1, BackendResponse {
+            author: Author::Model,
+            text: "Hi!".to_string(),
+            thinking: "".to_string(),
+            done: true,
+            transcript: Some(Transcript {
+                version: TRANSCRIPT_VERSION,
+                turns: vec![
+                    Turn::new(Role::User, "Hello"),
+                    Turn::new(Role::Assistant, "Hi!"),
+                ],
+            }),
+            usage: None,
+            tool_calls: vec![],
+        });
+        assert!(app_state.waiting_for_backend);
+        assert_eq!(app_state.comparison[1].messages.last().unwrap().text, "Hi!");
+
+        app_state.handle_compare_message(
+            0,
+            Message::new_with_type(Author::Oatmeal, MessageType::Error, "Failed"),
+        );
+        assert!(!app_state.waiting_for_backend);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_stops_comparing() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.handle_slash_commands("/compare ollama:llama3 openai:gpt-4o", &tx)?;
+
+        app_state.handle_slash_commands("/compare off", &tx)?;
+
+        assert!(!app_state.is_comparing());
+        assert!(app_state.compare_lists.is_empty());
+        assert_eq!(
+            app_state.messages.last().unwrap().text,
+            "Stopped comparing models."
+        );
+
+        return Ok(());
+    }
 }
 
 mod init {
 
     use super::*;
 
+    /// Lists the given models, or fails to list them when there are none.
+    struct ListingBackend {
+        models: Option<Vec<ModelInfo>>,
+    }
+
+    #[async_trait]
+    impl Backend for ListingBackend {
+        fn name(&self) -> BackendName {
+            return BackendName::Ollama;
+        }
+
+        #[allow(clippy::implicit_return)]
+        async fn health_check(&self) -> Result<()> {
+            return Ok(());
+        }
+
+        #[allow(clippy::implicit_return)]
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+            return match &self.models {
+                Some(models) => Ok(models.clone()),
+                None => bail!("401 Unauthorized"),
+            };
+        }
+
+        #[allow(clippy::implicit_return)]
+        async fn get_completion<'a>(
+            &self,
+            _prompt: BackendPrompt,
+            _tx: &'a mpsc::UnboundedSender<Event>,
+        ) -> Result<()> {
+            return Ok(());
+        }
+    }
+
+    fn backend(models: Option<Vec<ModelInfo>>) -> BackendBox {
+        return Box::new(ListingBackend { models });
+    }
+
+    #[tokio::test]
+    async fn it_keeps_a_configured_model_when_listing_fails() {
+        let mut app_state = AppState::default();
+
+        let model_name = app_state.check_model(&backend(None), "llama3:latest").await;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert_eq!(model_name, "llama3:latest");
+        assert_eq!(last_message.message_type(), MessageType::Warning);
+        insta::assert_snapshot!(last_message.text, @r"
+        I couldn't list the models for backend ollama, so I can't check that model llama3:latest exists. I'll still send your messages to it.
+
+        Error: 401 Unauthorized
+        ");
+    }
+
+    #[tokio::test]
+    async fn it_returns_error_message_without_a_model_when_listing_fails() {
+        let mut app_state = AppState::default();
+
+        let model_name = app_state.check_model(&backend(None), "").await;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert_eq!(model_name, "");
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @r"
+        I couldn't list the models for backend ollama, so I don't know which model to use. You can set one with `/model NAME`, or with the `--model` flag.
+
+        Error: 401 Unauthorized
+        ");
+    }
+
+    #[tokio::test]
+    async fn it_returns_error_message_without_models() {
+        let mut app_state = AppState::default();
+
+        let model_name = app_state.check_model(&backend(Some(vec![])), "").await;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert_eq!(model_name, "");
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @"Backend ollama doesn't have any models available. You can use `/pull NAME` to download one if the backend supports it, or `/model NAME` to set one.");
+    }
+
     #[tokio::test]
     async fn it_inits_and_reloads_from_session() -> Result<()> {
-        let backend = BackendManager::get(BackendName::Ollama)?;
+        let backend =
+            BackendManager::get(&BackendName::Ollama.to_string(), reqwest::Client::new())?;
         let editor = EditorManager::get(EditorName::None)?;
         let sessions_dir = tempfile::tempdir()?.into_path();
 
@@ -273,7 +790,8 @@ mod init {
         app_state.save_session().await?;
 
         let session_id = app_state.session_id;
-        let backend = BackendManager::get(BackendName::Ollama)?;
+        let backend =
+            BackendManager::get(&BackendName::Ollama.to_string(), reqwest::Client::new())?;
         let editor = EditorManager::get(EditorName::None)?;
 
         AppState::new(AppStateProps {
@@ -3,6 +3,7 @@
 mod tests;
 
 use ratatui::style::Color;
+use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
@@ -23,7 +24,9 @@ pub enum BubbleAlignment {
 
 pub struct Bubble<'a> {
     alignment: BubbleAlignment,
+    author_name: String,
     message: &'a Message,
+    show_thinking: bool,
     window_max_width: usize,
     codeblock_counter: usize,
 }
@@ -61,12 +64,30 @@ impl<'a> Bubble<'_> {
     ) -> Bubble<'a> {
         return Bubble {
             alignment,
+            author_name: message.author.to_string(),
             message,
+            show_thinking: false,
             window_max_width,
             codeblock_counter,
         };
     }
 
+    /// Labels the bubble with a different name when the model wrote it.
+    pub fn with_model_name(mut self, model_name: &str) -> Self {
+        if self.message.author == Author::Model {
+            self.author_name = model_name.to_string();
+        }
+
+        return self;
+    }
+
+    /// Expands the model's reasoning rather than showing a collapsed summary.
+    pub fn with_thinking_shown(mut self, show_thinking: bool) -> Self {
+        self.show_thinking = show_thinking;
+
+        return self;
+    }
+
     pub fn style_config() -> BubbleConfig {
         return BubbleConfig {
             // Unicode character border + padding.
@@ -82,9 +103,8 @@ impl<'a> Bubble<'_> {
         // Lazy default
         let mut highlight = HighlightLines::new(Syntaxes::get("text"), theme);
         let mut in_codeblock = false;
-        let mut lines: Vec<Line> = vec![];
-
         let max_line_length = self.get_max_line_length();
+        let mut lines: Vec<Line> = self.thinking_lines(max_line_length);
 
         for line in self.message.text.lines() {
             let mut spans = vec![];
@@ -135,39 +155,85 @@ impl<'a> Bubble<'_> {
                 spans = vec![Span::styled(line.to_owned(), Style::default())];
             }
 
-            let mut split_spans = vec![];
-            let mut line_char_count = 0;
+            lines.extend(self.wrap_spans(spans, max_line_length));
+        }
 
-            for span in spans {
-                if span.content.len() + line_char_count <= max_line_length {
-                    line_char_count += span.content.len();
-                    split_spans.push(span);
-                    continue;
-                }
+        return self.wrap_lines_in_buddle(lines, max_line_length);
+    }
 
-                let mut word_set: Vec<&str> = vec![];
+    /// Renders the model's reasoning as a dimmed section above the answer,
+    /// collapsed to a single line unless it's expanded.
+    fn thinking_lines(&self, max_line_length: usize) -> Vec<Line<'a>> {
+        if self.message.thinking.is_empty() {
+            return vec![];
+        }
 
-                for word in span.content.split(' ') {
-                    if word.len() + line_char_count > max_line_length {
-                        split_spans.push(Span::styled(word_set.join(" "), span.style));
-                        lines.push(self.spans_to_line(split_spans, max_line_length));
+        let style = Style {
+            fg: Some(Color::DarkGray),
+            add_modifier: Modifier::DIM,
+            ..Style::default()
+        };
+        let mut lines = self.wrap_spans(
+            vec![Span::styled(self.thinking_header(), style)],
+            max_line_length,
+        );
+        if !self.show_thinking {
+            return lines;
+        }
 
-                        split_spans = vec![];
-                        word_set = vec![];
-                        line_char_count = 0;
-                    }
+        for line in self.message.thinking.trim().lines() {
+            lines.extend(
+                self.wrap_spans(vec![Span::styled(line.to_owned(), style)], max_line_length),
+            );
+        }
+        if !self.message.text.is_empty() {
+            lines.push(self.spans_to_line(vec![], max_line_length));
+        }
 
-                    word_set.push(word);
-                    line_char_count += word.len() + 1;
+        return lines;
+    }
+
+    fn thinking_header(&self) -> String {
+        if self.show_thinking {
+            return "[-] Thinking (CTRL+T to collapse)".to_string();
+        }
+        return "[+] Thinking (CTRL+T to expand)".to_string();
+    }
+
+    fn wrap_spans(&self, spans: Vec<Span<'a>>, max_line_length: usize) -> Vec<Line<'a>> {
+        let mut lines = vec![];
+        let mut split_spans = vec![];
+        let mut line_char_count = 0;
+
+        for span in spans {
+            if span.content.len() + line_char_count <= max_line_length {
+                line_char_count += span.content.len();
+                split_spans.push(span);
+                continue;
+            }
+
+            let mut word_set: Vec<&str> = vec![];
+
+            for word in span.content.split(' ') {
+                if word.len() + line_char_count > max_line_length {
+                    split_spans.push(Span::styled(word_set.join(" "), span.style));
+                    lines.push(self.spans_to_line(split_spans, max_line_length));
+
+                    split_spans = vec![];
+                    word_set = vec![];
+                    line_char_count = 0;
                 }
 
-                split_spans.push(Span::styled(word_set.join(" "), span.style));
+                word_set.push(word);
+                line_char_count += word.len() + 1;
             }
 
-            lines.push(self.spans_to_line(split_spans, max_line_length));
+            split_spans.push(Span::styled(word_set.join(" "), span.style));
         }
 
-        return self.wrap_lines_in_buddle(lines, max_line_length);
+        lines.push(self.spans_to_line(split_spans, max_line_length));
+
+        return lines;
     }
 
     fn spans_to_line(&self, mut spans: Vec<Span<'a>>, max_line_length: usize) -> Line<'a> {
@@ -212,17 +278,38 @@ impl<'a> Bubble<'_> {
                 return line.len();
             })
             .max()
-            .unwrap();
+            .unwrap_or(0);
+
+        if !self.message.thinking.is_empty() {
+            max_line_length = max_line_length.max(self.thinking_header().len());
+            if self.show_thinking {
+                let thinking_len = self
+                    .message
+                    .thinking
+                    .lines()
+                    .map(|line| return line.len())
+                    .max()
+                    .unwrap_or(0);
+                max_line_length = max_line_length.max(thinking_len);
+            }
+        }
 
         if max_line_length > (self.window_max_width - line_border_width) {
             max_line_length = self.window_max_width - line_border_width;
         }
 
-        let username = &self.message.author.to_string();
+        let username = &self.author_name;
         if max_line_length < username.len() {
             max_line_length = username.len();
         }
 
+        // Widen the bubble to fit usage in the bottom border where the window
+        // allows it.
+        let usage_len = self.usage_text().chars().count();
+        if max_line_length < usage_len && usage_len <= (self.window_max_width - line_border_width) {
+            max_line_length = usage_len;
+        }
+
         return max_line_length;
     }
 
@@ -231,14 +318,21 @@ impl<'a> Bubble<'_> {
         let inner_bar = ["─"].repeat(max_line_length + 2).join("");
         let top_left_border = "╭";
         let mut top_bar = format!("{top_left_border}{inner_bar}╮");
-        let bottom_bar = format!("╰{inner_bar}╯");
+        let mut bottom_bar = format!("╰{inner_bar}╯");
         let bar_bubble_padding = repeat_from_subtractions(" ", vec![
             self.window_max_width,
             max_line_length,
             Bubble::style_config().bubble_padding,
         ]);
 
-        let username = &self.message.author.to_string();
+        let username = &self.author_name;
+
+        let usage = self.usage_text();
+        let usage_len = usage.chars().count();
+        if usage_len > 0 && usage_len <= max_line_length + 2 {
+            let bottom_fill = ["─"].repeat(max_line_length + 2 - usage_len).join("");
+            bottom_bar = format!("╰{bottom_fill}{usage}╯");
+        }
 
         if self.alignment == BubbleAlignment::Left {
             let top_replace = ["─"].repeat(username.len()).join("");
@@ -265,12 +359,25 @@ impl<'a> Bubble<'_> {
         }
     }
 
+    fn usage_text(&self) -> String {
+        return self
+            .message
+            .usage
+            .map(|usage| return usage.format())
+            .unwrap_or_default();
+    }
+
     fn highlight_span(&self, text: String) -> Span<'a> {
         if self.message.message_type() == MessageType::Error {
             return Span::styled(text, Style {
                 fg: Some(Color::Red),
                 ..Style::default()
             });
+        } else if self.message.message_type() == MessageType::Warning {
+            return Span::styled(text, Style {
+                fg: Some(Color::Yellow),
+                ..Style::default()
+            });
         } else if self.message.author == Author::Oatmeal {
             return Span::styled(text, Style {
                 fg: Some(Color::Rgb(138, 85, 63)), // Brown
@@ -19,7 +19,9 @@ mod tests;
 
 struct BubbleCacheEntry<'a> {
     codeblocks_count: usize,
-    text_len: usize,
+    text: String,
+    thinking_len: usize,
+    has_usage: bool,
     lines: Vec<Line<'a>>,
 }
 
@@ -27,6 +29,8 @@ pub struct BubbleList<'a> {
     cache: BTreeMap<usize, BubbleCacheEntry<'a>>,
     pub line_width: usize,
     pub lines_len: usize,
+    model_name: Option<String>,
+    show_thinking: bool,
     pub theme: Theme,
 }
 
@@ -74,10 +78,21 @@ impl<'a> BubbleList<'a> {
             cache: BTreeMap::new(),
             line_width: 0,
             lines_len: 0,
+            model_name: None,
+            show_thinking: false,
             theme,
         };
     }
 
+    /// Creates a list that labels the model's messages with the given name
+    /// instead of the configured model, as comparison columns do.
+    pub fn with_model_name(theme: Theme, model_name: &str) -> BubbleList<'a> {
+        let mut bubble_list = BubbleList::new(theme);
+        bubble_list.model_name = Some(model_name.to_string());
+
+        return bubble_list;
+    }
+
     pub fn set_messages(&mut self, messages: &[Message], line_width: usize) {
         if self.line_width != line_width {
             self.line_width = line_width;
@@ -90,7 +105,11 @@ impl<'a> BubbleList<'a> {
             .map(|(idx, message)| {
                 if self.cache.contains_key(&idx) {
                     let cache_entry = self.cache.get(&idx).unwrap();
-                    if idx < (messages.len() - 1) || message.text.len() == cache_entry.text_len {
+                    if idx < (messages.len() - 1)
+                        || (message.text == cache_entry.text
+                            && message.thinking.len() == cache_entry.thinking_len
+                            && message.usage.is_some() == cache_entry.has_usage)
+                    {
                         total_codeblock_counter += cache_entry.codeblocks_count;
                         return cache_entry.lines.len();
                     }
@@ -101,8 +120,12 @@ impl<'a> BubbleList<'a> {
                     align = BubbleAlignment::Right;
                 }
 
-                let bubble_lines = Bubble::new(message, align, line_width, total_codeblock_counter)
-                    .as_lines(&self.theme);
+                let mut bubble = Bubble::new(message, align, line_width, total_codeblock_counter)
+                    .with_thinking_shown(self.show_thinking);
+                if let Some(model_name) = &self.model_name {
+                    bubble = bubble.with_model_name(model_name);
+                }
+                let bubble_lines = bubble.as_lines(&self.theme);
                 let bubble_line_len = bubble_lines.len();
 
                 let codeblocks_count = message.codeblocks().len();
@@ -110,7 +133,9 @@ impl<'a> BubbleList<'a> {
 
                 self.cache.insert(idx, BubbleCacheEntry {
                     codeblocks_count,
-                    text_len: message.text.len(),
+                    text: message.text.to_string(),
+                    thinking_len: message.thinking.len(),
+                    has_usage: message.usage.is_some(),
                     lines: bubble_lines,
                 });
 
@@ -119,6 +144,15 @@ impl<'a> BubbleList<'a> {
             .sum();
     }
 
+    /// Expands or collapses the model's reasoning in every bubble. The cache is
+    /// cleared so the next update renders them again.
+    pub fn set_show_thinking(&mut self, show_thinking: bool) {
+        if self.show_thinking != show_thinking {
+            self.show_thinking = show_thinking;
+            self.cache.clear();
+        }
+    }
+
     pub fn len(&self) -> usize {
         return self.lines_len;
     }
@@ -142,7 +176,7 @@ impl<'a> BubbleList<'a> {
                     break;
                 }
 
-                buf.set_line(0, line_idx - scroll_index, line, rect.width);
+                buf.set_line(rect.x, rect.y + line_idx - scroll_index, line, rect.width);
                 line_idx += 1;
             }
 
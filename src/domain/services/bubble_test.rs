@@ -8,6 +8,7 @@ use crate::configuration::Config;
 use crate::configuration::ConfigKey;
 use crate::domain::models::Author;
 use crate::domain::models::Message;
+use crate::domain::models::Usage;
 use crate::domain::services::Themes;
 
 fn create_lines(
@@ -15,13 +16,30 @@ fn create_lines(
     alignment: BubbleAlignment,
     codeblock_count: usize,
     text: &str,
+) -> Result<String> {
+    return create_message_lines(&Message::new(author, text), alignment, codeblock_count);
+}
+
+fn create_message_lines(
+    message: &Message,
+    alignment: BubbleAlignment,
+    codeblock_count: usize,
+) -> Result<String> {
+    return create_bubble_lines(message, |message| {
+        return Bubble::new(message, alignment, 50, codeblock_count);
+    });
+}
+
+fn create_bubble_lines(
+    message: &Message,
+    new_bubble: impl FnOnce(&Message) -> Bubble,
 ) -> Result<String> {
     Config::set(ConfigKey::Username, "testuser");
     Config::set(ConfigKey::Model, "model-1");
 
-    let message = Message::new(author, text);
+    let mut bubble = new_bubble(message);
     let theme = Themes::get("base16-seti", "")?;
-    let lines = Bubble::new(&message, alignment, 50, codeblock_count).as_lines(&theme);
+    let lines = bubble.as_lines(&theme);
     let lines_str = lines
         .iter()
         .map(|line| {
@@ -135,3 +153,56 @@ fn it_creates_author_user_text() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_creates_author_model_text_with_usage() -> Result<()> {
+    let mut message = Message::new(Author::Model, "Hi there!");
+    message.usage = Some(Usage {
+        prompt_tokens: Some(12),
+        completion_tokens: Some(4),
+        generation_ms: None,
+        latency_ms: Some(500),
+    });
+    let lines_str = create_message_lines(&message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1───────────────────────────╮          
+    │ Hi there!                        │          
+    ╰──12 in · 4 out · 0.5s · 8.0 tok/s╯
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_author_model_text_with_collapsed_thinking() -> Result<()> {
+    let mut message = Message::new(Author::Model, "Hi there!");
+    message.thinking = "The user said hello.".to_string();
+    let lines_str = create_message_lines(&message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1──────────────────────────╮           
+    │ [+] Thinking (CTRL+T to expand) │           
+    │ Hi there!                       │           
+    ╰─────────────────────────────────╯           
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_author_model_text_with_thinking() -> Result<()> {
+    let mut message = Message::new(Author::Model, "Hi there!");
+    message.thinking = "\nThe user said hello.\n".to_string();
+    let lines_str = create_bubble_lines(&message, |message| {
+        return Bubble::new(message, BubbleAlignment::Left, 50, 0).with_thinking_shown(true);
+    })?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1────────────────────────────╮         
+    │ [-] Thinking (CTRL+T to collapse) │         
+    │ The user said hello.              │         
+    │                                   │         
+    │ Hi there!                         │         
+    ╰───────────────────────────────────╯         
+    "###);
+
+    return Ok(());
+}
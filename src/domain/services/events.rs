@@ -141,6 +141,13 @@ impl EventsService {
                     } => {
                         return Some(Event::KeyboardCTRLR());
                     }
+                    Input {
+                        key: Key::Char('t'),
+                        ctrl: true,
+                        ..
+                    } => {
+                        return Some(Event::KeyboardCTRLT());
+                    }
                     Input {
                         key: Key::Enter, ..
                     } => {
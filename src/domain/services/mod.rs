@@ -9,6 +9,7 @@ mod scroll;
 mod sessions;
 mod syntaxes;
 mod themes;
+mod tools;
 
 pub use app_state::*;
 pub use bubble::*;
@@ -18,3 +19,4 @@ pub use scroll::*;
 pub use sessions::*;
 pub use syntaxes::*;
 pub use themes::*;
+pub use tools::*;
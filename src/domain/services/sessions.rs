@@ -12,10 +12,12 @@ use uuid::Uuid;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
 use crate::domain::models::Author;
+use crate::domain::models::CompareColumn;
 use crate::domain::models::EditorContext;
 use crate::domain::models::Message;
 use crate::domain::models::Session;
 use crate::domain::models::State;
+use crate::domain::models::Transcript;
 
 pub struct Sessions {
     pub cache_dir: path::PathBuf,
@@ -53,8 +55,8 @@ impl Sessions {
         return self.cache_dir.join(format!("{id}.yaml"));
     }
 
-    /// Returns a list of sessions, but with only the first author message and
-    /// context removed to save on memory.
+    /// Returns a list of sessions, but with only the first author message, and
+    /// the transcript and comparison removed to save on memory.
     pub async fn list(&self) -> Result<Vec<Session>> {
         let mut sessions: Vec<Session> = vec![];
         if !self.cache_dir.exists() {
@@ -77,7 +79,8 @@ impl Sessions {
                 session.state.messages = vec![];
             }
 
-            session.state.backend_context = String::new();
+            session.state.transcript = Transcript::default();
+            session.state.comparison = vec![];
             sessions.push(session);
         }
 
@@ -95,7 +98,8 @@ impl Sessions {
         }
 
         let payload = fs::read_to_string(file_path).await?;
-        let session: Session = serde_yaml::from_str(&payload)?;
+        let mut session: Session = serde_yaml::from_str(&payload)?;
+        session.state.migrate()?;
 
         return Ok(session);
     }
@@ -103,17 +107,20 @@ impl Sessions {
     pub async fn save(
         &self,
         id: &str,
-        backend_context: &str,
+        transcript: &Transcript,
         editor_context: &Option<EditorContext>,
         messages: &[Message],
+        comparison: &[CompareColumn],
     ) -> Result<()> {
         let mut state = State {
             // TODO drop pulling this in from config.
             backend_name: Config::get(ConfigKey::Backend),
             backend_model: Config::get(ConfigKey::Model),
-            backend_context: backend_context.to_string(),
+            transcript: transcript.clone(),
+            system_prompt: Config::get(ConfigKey::SystemPrompt),
             editor_language: String::new(),
             messages: messages.to_vec(),
+            comparison: comparison.to_vec(),
         };
 
         if let Some(context) = editor_context {
@@ -0,0 +1,395 @@
+#[cfg(test)]
+#[path = "tools_test.rs"]
+mod tests;
+
+use std::fs;
+use std::path;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use regex::Regex;
+use serde_json::json;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::ToolResult;
+
+/// Tool output is cut off past this many bytes so a large file doesn't blow
+/// through the model's context window.
+const MAX_OUTPUT_BYTES: usize = 32 * 1024;
+const MAX_GREP_MATCHES: usize = 200;
+/// Larger files are skipped by grep, as they're rarely source code.
+const MAX_GREP_FILE_BYTES: u64 = 1024 * 1024;
+/// Build output and dependencies, which grep doesn't search.
+const GREP_SKIPPED_DIRS: [&str; 2] = ["node_modules", "target"];
+
+fn truncate(mut text: String) -> String {
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text;
+    }
+
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text += "\n[output truncated]";
+
+    return text;
+}
+
+fn arg_str<'a>(call: &'a ToolCall, key: &str) -> Option<&'a str> {
+    return call
+        .arguments
+        .get(key)
+        .and_then(|e| return e.as_str())
+        .filter(|e| return !e.is_empty());
+}
+
+/// Read-only tools the model can call, restricted to files inside a root
+/// directory.
+pub struct Tools {
+    root: PathBuf,
+}
+
+impl Tools {
+    pub fn new(root: PathBuf) -> Tools {
+        return Tools { root };
+    }
+
+    pub fn definitions() -> Vec<ToolDefinition> {
+        return vec![
+            ToolDefinition {
+                name: "read_file".to_string(),
+                description: "Reads a text file from the current working directory.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {"type": "string", "description": "Path relative to the working directory."}
+                    },
+                    "required": ["path"]
+                }),
+            },
+            ToolDefinition {
+                name: "list_dir".to_string(),
+                description: "Lists the entries of a directory in the current working directory. Directories end with a slash.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {"type": "string", "description": "Path relative to the working directory. Defaults to the working directory."}
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "grep".to_string(),
+                description: "Searches files in the current working directory for lines matching a regular expression. Hidden files, binary files, files over 1 MiB and `target` or `node_modules` directories are skipped.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {"type": "string", "description": "Regular expression to search for."},
+                        "path": {"type": "string", "description": "File or directory to search, relative to the working directory. Defaults to the working directory."}
+                    },
+                    "required": ["pattern"]
+                }),
+            },
+            ToolDefinition {
+                name: "git_diff".to_string(),
+                description: "Shows uncommitted git changes in the current working directory.".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {"type": "string", "description": "Limits the diff to a path relative to the working directory."},
+                        "staged": {"type": "boolean", "description": "Shows staged changes instead of unstaged changes."}
+                    }
+                }),
+            },
+        ];
+    }
+
+    /// Runs a tool call. Failures are returned to the model as an error result
+    /// rather than ending the conversation.
+    pub async fn run(&self, call: &ToolCall) -> ToolResult {
+        let res = match call.name.as_str() {
+            "read_file" => self.read_file(call).await,
+            "list_dir" => self.list_dir(call),
+            "grep" => self.grep(call).await,
+            "git_diff" => self.git_diff(call).await,
+            _ => Err(anyhow!("Unknown tool {}", call.name)),
+        };
+
+        let (content, is_error) = match res {
+            Ok(content) => (truncate(content), false),
+            Err(err) => (err.to_string(), true),
+        };
+
+        return ToolResult {
+            id: call.id.to_string(),
+            name: call.name.to_string(),
+            content,
+            is_error,
+        };
+    }
+
+    /// Resolves a path from the model, refusing anything outside of the root
+    /// directory including through symlinks.
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let root = self.root.canonicalize()?;
+        let resolved = root
+            .join(path)
+            .canonicalize()
+            .map_err(|err| return anyhow!("Failed to find {path}: {err}"))?;
+        if !resolved.starts_with(&root) {
+            bail!(format!("{path} is outside of the working directory"));
+        }
+
+        return Ok(resolved);
+    }
+
+    /// Resolves a path from the model without touching the filesystem, so it
+    /// can name files that git knows about but that have been deleted.
+    fn resolve_lexically(&self, path: &str) -> Result<PathBuf> {
+        let root = normalize(&path::absolute(&self.root)?);
+        let resolved = normalize(&root.join(path));
+        if !resolved.starts_with(&root) {
+            bail!(format!("{path} is outside of the working directory"));
+        }
+
+        return Ok(resolved.strip_prefix(&root)?.to_path_buf());
+    }
+
+    /// Reads no more of the file than can be returned. A few bytes past the
+    /// limit are read so the output is still marked as truncated when a
+    /// character cut in half at the end is dropped.
+    async fn read_file(&self, call: &ToolCall) -> Result<String> {
+        let path = arg_str(call, "path").ok_or_else(|| return anyhow!("Missing path"))?;
+        let file = tokio::fs::File::open(self.resolve(path)?).await?;
+        let mut bytes = vec![];
+        file.take((MAX_OUTPUT_BYTES + 4) as u64)
+            .read_to_end(&mut bytes)
+            .await?;
+
+        return match String::from_utf8(bytes) {
+            Ok(text) => Ok(text),
+            Err(err) if err.utf8_error().error_len().is_none() => {
+                let valid_len = err.utf8_error().valid_up_to();
+                let mut bytes = err.into_bytes();
+                bytes.truncate(valid_len);
+                Ok(String::from_utf8(bytes)?)
+            }
+            Err(_) => bail!(format!("{path} isn't a text file")),
+        };
+    }
+
+    fn list_dir(&self, call: &ToolCall) -> Result<String> {
+        let path = self.resolve(arg_str(call, "path").unwrap_or("."))?;
+
+        let mut entries = fs::read_dir(path)?
+            .filter_map(|entry| return entry.ok())
+            .map(|entry| {
+                let mut name = entry.file_name().to_string_lossy().to_string();
+                if entry.path().is_dir() {
+                    name += "/";
+                }
+                return name;
+            })
+            .collect::<Vec<String>>();
+        entries.sort();
+
+        return Ok(entries.join("\n"));
+    }
+
+    /// Searches on a blocking thread, as the files are read synchronously.
+    async fn grep(&self, call: &ToolCall) -> Result<String> {
+        let pattern = arg_str(call, "pattern").ok_or_else(|| return anyhow!("Missing pattern"))?;
+        let regex = Regex::new(pattern)?;
+        let path = self.resolve(arg_str(call, "path").unwrap_or("."))?;
+        let root = self.root.canonicalize()?;
+
+        let matches = tokio::task::spawn_blocking(move || {
+            let mut matches = vec![];
+            search(&root, &path, &regex, &mut matches);
+            return matches;
+        })
+        .await?;
+
+        if matches.is_empty() {
+            return Ok("No matches found.".to_string());
+        }
+
+        return Ok(matches.join("\n"));
+    }
+
+    /// Runs `git diff` without the external diff, textconv, fsmonitor and
+    /// filter commands a repository's config can set, so the tool stays
+    /// read-only.
+    async fn git_diff(&self, call: &ToolCall) -> Result<String> {
+        let mut args = self.filter_overrides().await?;
+        args.extend([
+            "-c".to_string(),
+            "core.fsmonitor=".to_string(),
+            "--literal-pathspecs".to_string(),
+            "diff".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--no-textconv".to_string(),
+        ]);
+        if call
+            .arguments
+            .get("staged")
+            .and_then(|e| return e.as_bool())
+            == Some(true)
+        {
+            args.push("--cached".to_string());
+        }
+        if let Some(path) = arg_str(call, "path") {
+            args.push("--".to_string());
+            let path = self.resolve_lexically(path)?;
+            if path.as_os_str().is_empty() {
+                args.push(".".to_string());
+            } else {
+                args.push(path.display().to_string());
+            }
+        }
+
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .await?;
+        if !output.status.success() {
+            bail!(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+
+        let diff = String::from_utf8_lossy(&output.stdout).to_string();
+        if diff.is_empty() {
+            return Ok("No changes.".to_string());
+        }
+
+        return Ok(diff);
+    }
+
+    /// Turns off every configured filter driver. Git runs the clean and
+    /// process filters `.gitattributes` assigns to a file while diffing it.
+    async fn filter_overrides(&self) -> Result<Vec<String>> {
+        let output = Command::new("git")
+            .args([
+                "config",
+                "--name-only",
+                "--get-regexp",
+                r"^filter\..+\.(clean|process)$",
+            ])
+            .current_dir(&self.root)
+            .output()
+            .await?;
+
+        let mut args = vec![];
+        for key in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((driver, _)) = key.rsplit_once('.') else {
+                continue;
+            };
+            args.extend([
+                "-c".to_string(),
+                format!("{key}="),
+                "-c".to_string(),
+                format!("{driver}.required=false"),
+            ]);
+        }
+
+        return Ok(args);
+    }
+}
+
+/// Removes `.` and `..` from a path without following symlinks.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    return normalized;
+}
+
+/// Searches the files under a path in order, and returns true once it's
+/// stopped after finding too many matches.
+fn search(root: &Path, path: &Path, regex: &Regex, matches: &mut Vec<String>) -> bool {
+    if path.is_file() {
+        return search_file(root, path, regex, matches);
+    }
+
+    let mut entries = match fs::read_dir(path) {
+        Ok(entries) => {
+            entries
+                .filter_map(|entry| return entry.ok())
+                .collect::<Vec<fs::DirEntry>>()
+        }
+        Err(_) => return false,
+    };
+    entries.sort_by_key(|entry| return entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        // Symlinks are skipped so a search can't escape the root directory.
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        let mut stopped = false;
+        if file_type.is_dir() && !GREP_SKIPPED_DIRS.contains(&name.as_str()) {
+            stopped = search(root, &entry.path(), regex, matches);
+        } else if file_type.is_file() {
+            stopped = search_file(root, &entry.path(), regex, matches);
+        }
+        if stopped {
+            return true;
+        }
+    }
+
+    return false;
+}
+
+/// Binary, unreadable and large files are skipped.
+fn search_file(root: &Path, file: &Path, regex: &Regex, matches: &mut Vec<String>) -> bool {
+    let size = match fs::metadata(file) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return false,
+    };
+    if size > MAX_GREP_FILE_BYTES {
+        return false;
+    }
+    let content = match fs::read_to_string(file) {
+        Ok(content) if !content.contains('\0') => content,
+        _ => return false,
+    };
+
+    let display_path = file
+        .strip_prefix(root)
+        .unwrap_or(file)
+        .display()
+        .to_string();
+    for (idx, line) in content.lines().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+        if matches.len() >= MAX_GREP_MATCHES {
+            matches.push(format!("[stopped after {MAX_GREP_MATCHES} matches]"));
+            return true;
+        }
+        matches.push(format!("{display_path}:{}: {line}", idx + 1));
+    }
+
+    return false;
+}
@@ -0,0 +1,322 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde_json::json;
+use tempfile::TempDir;
+
+use super::MAX_GREP_FILE_BYTES;
+use super::MAX_GREP_MATCHES;
+use super::MAX_OUTPUT_BYTES;
+use super::Tools;
+use super::truncate;
+use crate::domain::models::ToolCall;
+
+fn fixture() -> Result<(TempDir, Tools)> {
+    // The tools are rooted in a subdirectory so there's room to test paths
+    // outside of it.
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("repo");
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join(".git"))?;
+    fs::write(root.join("README.md"), "# Hello\nWorld\n")?;
+    fs::write(root.join("src/main.rs"), "fn main() {\n    hello();\n}\n")?;
+    fs::write(root.join(".git/config"), "hello\n")?;
+    fs::write(dir.path().join("outside.txt"), "secret")?;
+
+    let tools = Tools::new(root);
+    return Ok((dir, tools));
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    return ToolCall {
+        id: "call_1".to_string(),
+        name: name.to_string(),
+        arguments,
+    };
+}
+
+#[tokio::test]
+async fn it_reads_files() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools
+        .run(&call("read_file", json!({"path": "README.md"})))
+        .await;
+
+    assert!(!res.is_error);
+    assert_eq!(res.id, "call_1");
+    assert_eq!(res.name, "read_file");
+    assert_eq!(res.content, "# Hello\nWorld\n");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_refuses_paths_outside_of_root() -> Result<()> {
+    let (dir, tools) = fixture()?;
+    let outside = dir.path().join("outside.txt");
+
+    let relative = tools
+        .run(&call("read_file", json!({"path": "../outside.txt"})))
+        .await;
+    let absolute = tools
+        .run(&call(
+            "read_file",
+            json!({"path": outside.display().to_string()}),
+        ))
+        .await;
+
+    assert!(relative.is_error);
+    assert_eq!(
+        relative.content,
+        "../outside.txt is outside of the working directory"
+    );
+    assert!(absolute.is_error);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_directories() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools.run(&call("list_dir", json!({}))).await;
+
+    assert!(!res.is_error);
+    assert_eq!(res.content, ".git/\nREADME.md\nsrc/");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_greps_files() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools
+        .run(&call("grep", json!({"pattern": "(?i)hello"})))
+        .await;
+
+    assert!(!res.is_error);
+    assert_eq!(
+        res.content,
+        "README.md:1: # Hello\nsrc/main.rs:2:     hello();"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_greps_without_matches() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools
+        .run(&call("grep", json!({"pattern": "goodbye", "path": "src"})))
+        .await;
+
+    assert!(!res.is_error);
+    assert_eq!(res.content, "No matches found.");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_greps_without_searching_build_output_binary_or_large_files() -> Result<()> {
+    let (dir, tools) = fixture()?;
+    let root = dir.path().join("repo");
+    fs::create_dir_all(root.join("target/debug"))?;
+    fs::write(root.join("target/debug/build.log"), "hello\n")?;
+    fs::write(root.join("image.png"), b"hello\n\0\xff")?;
+    fs::write(
+        root.join("large.txt"),
+        "hello\n".repeat(MAX_GREP_FILE_BYTES as usize),
+    )?;
+
+    let res = tools.run(&call("grep", json!({"pattern": "hello"}))).await;
+
+    assert!(!res.is_error);
+    assert_eq!(res.content, "src/main.rs:2:     hello();");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_grepping_after_too_many_matches() -> Result<()> {
+    let (dir, tools) = fixture()?;
+    fs::write(
+        dir.path().join("repo/a.txt"),
+        "match\n".repeat(MAX_GREP_MATCHES + 1),
+    )?;
+
+    let res = tools.run(&call("grep", json!({"pattern": "match"}))).await;
+
+    assert!(!res.is_error);
+    assert_eq!(res.content.lines().count(), MAX_GREP_MATCHES + 1);
+    assert!(res.content.starts_with("a.txt:1: match\n"));
+    assert!(res.content.ends_with(&format!(
+        "a.txt:{MAX_GREP_MATCHES}: match\n[stopped after {MAX_GREP_MATCHES} matches]"
+    )));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_errors_for_missing_arguments() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools.run(&call("grep", json!({}))).await;
+
+    assert!(res.is_error);
+    assert_eq!(res.content, "Missing pattern");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_errors_for_unknown_tools() -> Result<()> {
+    let (_dir, tools) = fixture()?;
+    let res = tools.run(&call("write_file", json!({}))).await;
+
+    assert!(res.is_error);
+    assert_eq!(res.content, "Unknown tool write_file");
+
+    return Ok(());
+}
+
+#[test]
+fn it_truncates_output() {
+    let text = "a".repeat(MAX_OUTPUT_BYTES + 10);
+    let res = truncate(text);
+
+    assert_eq!(res.len(), MAX_OUTPUT_BYTES + "\n[output truncated]".len());
+    assert!(res.ends_with("\n[output truncated]"));
+}
+
+#[tokio::test]
+async fn it_reads_only_the_start_of_large_files() -> Result<()> {
+    let (dir, tools) = fixture()?;
+    // Multibyte characters make sure the read stops on a character boundary.
+    fs::write(
+        dir.path().join("repo/large.txt"),
+        "é".repeat(MAX_OUTPUT_BYTES),
+    )?;
+
+    let res = tools
+        .run(&call("read_file", json!({"path": "large.txt"})))
+        .await;
+
+    assert!(!res.is_error);
+    assert!(res.content.starts_with("éé"));
+    assert!(res.content.ends_with("é\n[output truncated]"));
+
+    return Ok(());
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<()> {
+    std::process::Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(root)
+        .output()?;
+
+    return Ok(());
+}
+
+/// Creates a git repository with a committed README.
+fn git_fixture() -> Result<(TempDir, PathBuf)> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("repo");
+    fs::create_dir_all(&root)?;
+    fs::write(root.join("README.md"), "# Hello\n")?;
+    git(&root, &["init", "-q"])?;
+    git(&root, &["add", "README.md"])?;
+    git(&root, &["commit", "-q", "-m", "init"])?;
+
+    return Ok((dir, root));
+}
+
+#[tokio::test]
+async fn it_diffs_without_running_commands_from_the_repo_config() -> Result<()> {
+    let (dir, root) = git_fixture()?;
+    let marker = dir.path().join("external-diff-ran");
+    git(&root, &[
+        "config",
+        "diff.external",
+        &format!("touch {}", marker.display()),
+    ])?;
+    fs::write(root.join("README.md"), "# Hello\nWorld\n")?;
+
+    let res = Tools::new(root).run(&call("git_diff", json!({}))).await;
+
+    assert!(!res.is_error);
+    assert!(res.content.contains("+World"));
+    assert!(!marker.exists());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_diffs_without_running_filters_from_the_repo_attributes() -> Result<()> {
+    let (dir, root) = git_fixture()?;
+    let clean_marker = dir.path().join("clean-filter-ran");
+    let process_marker = dir.path().join("process-filter-ran");
+    fs::write(
+        root.join(".gitattributes"),
+        "README.md filter=clean\n*.txt filter=process\n",
+    )?;
+    fs::write(root.join("notes.txt"), "Notes\n")?;
+    git(&root, &["add", ".gitattributes", "notes.txt"])?;
+    git(&root, &["commit", "-q", "-m", "attributes"])?;
+    git(&root, &[
+        "config",
+        "filter.clean.clean",
+        &format!("touch {} && cat", clean_marker.display()),
+    ])?;
+    git(&root, &["config", "filter.clean.required", "true"])?;
+    git(&root, &[
+        "config",
+        "filter.process.process",
+        &format!("touch {}", process_marker.display()),
+    ])?;
+    fs::write(root.join("README.md"), "# Hello\nWorld\n")?;
+    fs::write(root.join("notes.txt"), "Notes\nMore\n")?;
+
+    let res = Tools::new(root).run(&call("git_diff", json!({}))).await;
+
+    assert!(!res.is_error, "{}", res.content);
+    assert!(res.content.contains("+World"));
+    assert!(res.content.contains("+More"));
+    assert!(!clean_marker.exists());
+    assert!(!process_marker.exists());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_diffs_deleted_files() -> Result<()> {
+    let (_dir, root) = git_fixture()?;
+    fs::remove_file(root.join("README.md"))?;
+
+    let res = Tools::new(root)
+        .run(&call("git_diff", json!({"path": "./src/../README.md"})))
+        .await;
+
+    assert!(!res.is_error, "{}", res.content);
+    assert!(res.content.contains("deleted file mode"));
+    assert!(res.content.contains("-# Hello"));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_refuses_diffs_outside_of_root() -> Result<()> {
+    let (_dir, root) = git_fixture()?;
+
+    let res = Tools::new(root)
+        .run(&call("git_diff", json!({"path": "../outside.txt"})))
+        .await;
+
+    assert!(res.is_error);
+    assert_eq!(
+        res.content,
+        "../outside.txt is outside of the working directory"
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,256 @@
+#[cfg(test)]
+#[path = "aws_test.rs"]
+mod tests;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::bail;
+use chrono::DateTime;
+use chrono::Utc;
+use ring::digest;
+use ring::hmac;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// AWS access keys, read the same way the AWS CLI reads them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl Credentials {
+    /// Loads credentials from the `AWS_ACCESS_KEY_ID` and
+    /// `AWS_SECRET_ACCESS_KEY` environment variables, falling back to the
+    /// profile in the shared credentials file.
+    pub fn load(profile: &str) -> Result<Credentials> {
+        if let Some(credentials) = Credentials::from_env() {
+            return Ok(credentials);
+        }
+
+        let path = match env::var("AWS_SHARED_CREDENTIALS_FILE") {
+            Ok(path) if !path.is_empty() => PathBuf::from(path),
+            _ => {
+                dirs::home_dir()
+                    .unwrap_or_default()
+                    .join(".aws")
+                    .join("credentials")
+            }
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                bail!(format!(
+                    "AWS credentials were not found in the environment or in {}",
+                    path.display()
+                ))
+            }
+        };
+
+        return match Credentials::from_ini(&text, profile) {
+            Some(credentials) => Ok(credentials),
+            None => {
+                bail!(format!(
+                    "AWS profile {profile} has no credentials in {}",
+                    path.display()
+                ))
+            }
+        };
+    }
+
+    fn from_env() -> Option<Credentials> {
+        let access_key_id = env::var("AWS_ACCESS_KEY_ID").unwrap_or_default();
+        let secret_access_key = env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default();
+        if access_key_id.is_empty() || secret_access_key.is_empty() {
+            return None;
+        }
+
+        return Some(Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: env::var("AWS_SESSION_TOKEN")
+                .ok()
+                .filter(|e| return !e.is_empty()),
+        });
+    }
+
+    /// Reads a profile from the INI formatted shared credentials file.
+    fn from_ini(text: &str, profile: &str) -> Option<Credentials> {
+        let mut section = String::new();
+        let mut credentials = Credentials::default();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|e| return e.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                continue;
+            }
+            if section != profile {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim().to_string()),
+                None => continue,
+            };
+            match key {
+                "aws_access_key_id" => credentials.access_key_id = value,
+                "aws_secret_access_key" => credentials.secret_access_key = value,
+                "aws_session_token" => credentials.session_token = Some(value),
+                _ => {}
+            }
+        }
+
+        if credentials.access_key_id.is_empty() || credentials.secret_access_key.is_empty() {
+            return None;
+        }
+
+        return Some(credentials);
+    }
+}
+
+/// Percent encodes everything but unreserved characters, as SigV4 expects.
+pub fn uri_encode(text: &str) -> String {
+    return text
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                return (byte as char).to_string();
+            }
+            return format!("%{byte:02X}");
+        })
+        .collect();
+}
+
+fn hex(bytes: &[u8]) -> String {
+    return bytes
+        .iter()
+        .map(|byte| return format!("{byte:02x}"))
+        .collect();
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    return hex(digest::digest(&digest::SHA256, bytes).as_ref());
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    return hmac::sign(&key, data.as_bytes()).as_ref().to_vec();
+}
+
+/// The `Host` header reqwest sends for the URL, which has to be signed.
+fn host_header(url: &reqwest::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    return match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+}
+
+/// Signs requests with AWS Signature Version 4.
+pub struct Signer<'a> {
+    pub credentials: &'a Credentials,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+impl Signer<'_> {
+    /// Returns the headers to add to the request, including `authorization`.
+    /// The URL path must already be percent encoded, as it's sent.
+    pub fn sign(
+        &self,
+        method: &reqwest::Method,
+        url: &reqwest::Url,
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> Vec<(String, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let mut headers = vec![
+            ("host".to_string(), host_header(url)),
+            ("x-amz-date".to_string(), amz_date.to_string()),
+        ];
+        if let Some(session_token) = &self.credentials.session_token {
+            headers.push((
+                "x-amz-security-token".to_string(),
+                session_token.to_string(),
+            ));
+        }
+
+        let canonical_headers = headers
+            .iter()
+            .map(|(key, value)| return format!("{key}:{}\n", value.trim()))
+            .collect::<String>();
+        let signed_headers = headers
+            .iter()
+            .map(|(key, _)| return key.to_string())
+            .collect::<Vec<String>>()
+            .join(";");
+
+        // Services other than S3 sign the path encoded a second time.
+        let canonical_uri = url
+            .path()
+            .split('/')
+            .map(uri_encode)
+            .collect::<Vec<String>>()
+            .join("/");
+        let mut query = url
+            .query_pairs()
+            .map(|(key, value)| return format!("{}={}", uri_encode(&key), uri_encode(&value)))
+            .collect::<Vec<String>>();
+        query.sort();
+
+        let canonical_request = [
+            method.as_str(),
+            &canonical_uri,
+            &query.join("&"),
+            &canonical_headers,
+            &signed_headers,
+            &sha256_hex(body),
+        ]
+        .join("\n");
+
+        let scope = format!(
+            "{date}/{region}/{service}/aws4_request",
+            region = self.region,
+            service = self.service
+        );
+        let string_to_sign = [
+            ALGORITHM,
+            &amz_date,
+            &scope,
+            &sha256_hex(canonical_request.as_bytes()),
+        ]
+        .join("\n");
+
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let mut key = hmac_sha256(secret.as_bytes(), &date);
+        for part in [self.region, self.service, "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+        // Host is set by reqwest from the URL.
+        headers.remove(0);
+        headers.push((
+            "authorization".to_string(),
+            format!(
+                "{ALGORITHM} Credential={access_key_id}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                access_key_id = self.credentials.access_key_id
+            ),
+        ));
+
+        return headers;
+    }
+}
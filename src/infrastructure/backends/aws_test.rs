@@ -0,0 +1,132 @@
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Utc;
+
+use super::Credentials;
+use super::Signer;
+use super::uri_encode;
+
+// From the AWS Signature Version 4 test suite.
+fn example_credentials() -> Credentials {
+    return Credentials {
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        session_token: None,
+    };
+}
+
+fn example_date() -> Result<DateTime<Utc>> {
+    return Ok(DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")?.to_utc());
+}
+
+#[test]
+fn it_encodes_uri_components() {
+    assert_eq!(
+        uri_encode("anthropic.claude-3-haiku-20240307-v1:0"),
+        "anthropic.claude-3-haiku-20240307-v1%3A0"
+    );
+    assert_eq!(uri_encode("a b/c~"), "a%20b%2Fc~");
+}
+
+#[test]
+fn it_signs_requests() -> Result<()> {
+    let credentials = example_credentials();
+    let signer = Signer {
+        credentials: &credentials,
+        region: "us-east-1",
+        service: "service",
+    };
+    let url = reqwest::Url::parse("https://example.amazonaws.com/")?;
+
+    let headers = signer.sign(&reqwest::Method::GET, &url, b"", example_date()?);
+
+    assert_eq!(headers, vec![
+        ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        (
+            "authorization".to_string(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31".to_string()
+        ),
+    ]);
+
+    return Ok(());
+}
+
+#[test]
+fn it_signs_sorted_query_parameters() -> Result<()> {
+    let credentials = example_credentials();
+    let signer = Signer {
+        credentials: &credentials,
+        region: "us-east-1",
+        service: "service",
+    };
+    let url = reqwest::Url::parse("https://example.amazonaws.com/?Param2=value2&Param1=value1")?;
+
+    let headers = signer.sign(&reqwest::Method::GET, &url, b"", example_date()?);
+
+    assert!(
+        headers[1].1.ends_with(
+            "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        )
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_signs_session_tokens() -> Result<()> {
+    let mut credentials = example_credentials();
+    credentials.session_token = Some("token".to_string());
+    let signer = Signer {
+        credentials: &credentials,
+        region: "us-east-1",
+        service: "service",
+    };
+    let url = reqwest::Url::parse("https://example.amazonaws.com/")?;
+
+    let headers = signer.sign(&reqwest::Method::GET, &url, b"", example_date()?);
+
+    assert_eq!(
+        headers[1],
+        ("x-amz-security-token".to_string(), "token".to_string())
+    );
+    assert!(
+        headers[2]
+            .1
+            .contains("SignedHeaders=host;x-amz-date;x-amz-security-token,")
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_reads_profiles_from_the_credentials_file() {
+    let text = r"
+[default]
+aws_access_key_id = AKIDDEFAULT
+aws_secret_access_key = default-secret
+
+# Work account.
+[work]
+aws_access_key_id=AKIDWORK
+aws_secret_access_key=work-secret
+aws_session_token=work-token
+";
+
+    assert_eq!(
+        Credentials::from_ini(text, "default"),
+        Some(Credentials {
+            access_key_id: "AKIDDEFAULT".to_string(),
+            secret_access_key: "default-secret".to_string(),
+            session_token: None,
+        })
+    );
+    assert_eq!(
+        Credentials::from_ini(text, "work"),
+        Some(Credentials {
+            access_key_id: "AKIDWORK".to_string(),
+            secret_access_key: "work-secret".to_string(),
+            session_token: Some("work-token".to_string()),
+        })
+    );
+    assert_eq!(Credentials::from_ini(text, "missing"), None);
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+#[path = "azure_test.rs"]
+mod tests;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+use crate::domain::models::ModelInfo;
+use crate::infrastructure::backends::openai;
+
+/// Azure OpenAI, which serves OpenAI models through deployments created on an
+/// Azure resource. Each deployment is offered as a model, and requests are
+/// addressed to the deployment rather than naming the model in the body.
+pub struct Azure {
+    url: String,
+    token: String,
+    api_version: String,
+    deployments: Vec<String>,
+    timeout: String,
+    client: reqwest::Client,
+}
+
+impl Azure {
+    pub fn new(client: reqwest::Client) -> Azure {
+        let deployments = Config::get(ConfigKey::AzureDeployments)
+            .split(',')
+            .map(|e| return e.trim().to_string())
+            .filter(|e| return !e.is_empty())
+            .collect();
+
+        return Azure {
+            url: Config::get(ConfigKey::AzureEndpoint)
+                .trim_end_matches('/')
+                .to_string(),
+            token: Config::get(ConfigKey::AzureToken),
+            api_version: Config::get(ConfigKey::AzureApiVersion),
+            deployments,
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+        };
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        return self
+            .client
+            .request(method, format!("{url}/openai/{path}", url = self.url))
+            .query(&[("api-version", &self.api_version)])
+            .header("api-key", &self.token);
+    }
+}
+
+#[async_trait]
+impl Backend for Azure {
+    fn name(&self) -> BackendName {
+        return BackendName::Azure;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.url.is_empty() {
+            bail!("Azure OpenAI endpoint is not defined");
+        }
+        if self.token.is_empty() {
+            bail!("Azure OpenAI token is not defined");
+        }
+        if self.deployments.is_empty() {
+            bail!("Azure OpenAI deployments are not defined");
+        }
+
+        let res = match self
+            .request(reqwest::Method::GET, "models")
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!(error = ?err, "Azure OpenAI is not reachable");
+                bail!("Azure OpenAI is not reachable");
+            }
+        };
+
+        let status = res.status().as_u16();
+        if status >= 400 {
+            tracing::error!(status = status, "Azure OpenAI health check failed");
+            bail!("Azure OpenAI health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut models = self
+            .deployments
+            .iter()
+            .map(|deployment| return ModelInfo::new(deployment))
+            .collect::<Vec<ModelInfo>>();
+
+        models.sort();
+
+        return Ok(models);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let req_builder = self.request(
+            reqwest::Method::POST,
+            &format!("deployments/{model}/chat/completions", model = prompt.model),
+        );
+
+        return openai::send_completion("Azure OpenAI", req_builder, prompt, true, tx).await;
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use anyhow::bail;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use super::Azure;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+
+impl Azure {
+    fn with_url(url: String) -> Azure {
+        return Azure {
+            url,
+            token: "abc".to_string(),
+            api_version: "2024-10-21".to_string(),
+            deployments: vec!["gpt-4o-mini".to_string(), "gpt-4o".to_string()],
+            timeout: "200".to_string(),
+            client: reqwest::Client::new(),
+        };
+    }
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/openai/models")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "api-version".to_string(),
+            "2024-10-21".to_string(),
+        ))
+        .match_header("api-key", "abc")
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let backend = Azure::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/openai/models")
+        .match_query(mockito::Matcher::Any)
+        .with_status(401)
+        .create_async()
+        .await;
+
+    let backend = Azure::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Azure OpenAI health check failed"
+    );
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_deployments() {
+    let mut backend = Azure::with_url("http://localhost".to_string());
+    backend.deployments = vec![];
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Azure OpenAI deployments are not defined"
+    );
+}
+
+#[tokio::test]
+async fn it_lists_deployments_as_models() -> Result<()> {
+    let backend = Azure::with_url("http://localhost".to_string());
+    let res = backend.list_models().await?;
+
+    assert_eq!(res, vec![
+        ModelInfo::new("gpt-4o"),
+        ModelInfo::new("gpt-4o-mini")
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let body = [
+        json!({"choices": [{"delta": {"content": "Hello "}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {"content": "World"}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {}, "finish_reason": "stop"}]}),
+        json!({"choices": [], "usage": {"prompt_tokens": 12, "completion_tokens": 2}}),
+    ]
+    .iter()
+    .map(|line| return format!("data: {line}\n\n"))
+    .collect::<String>()
+        + "data: [DONE]\n\n";
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "gpt-4o".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/openai/deployments/gpt-4o/chat/completions")
+        .match_query(mockito::Matcher::UrlEncoded(
+            "api-version".to_string(),
+            "2024-10-21".to_string(),
+        ))
+        .match_header("api-key", "abc")
+        .match_header("Authorization", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Azure::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert!(third_recv.done);
+    assert_eq!(
+        third_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(12),
+            completion_tokens: Some(2),
+            ..Usage::default()
+        })
+    );
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::Assistant, "How may I help you?"),
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "gpt-4o".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/openai/deployments/gpt-4o/chat/completions")
+        .match_query(mockito::Matcher::Any)
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Azure::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Azure OpenAI"
+    );
+
+    return Ok(());
+}
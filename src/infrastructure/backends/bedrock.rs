@@ -0,0 +1,605 @@
+#[cfg(test)]
+#[path = "bedrock_test.rs"]
+mod tests;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::aws::Credentials;
+use crate::infrastructure::backends::aws::Signer;
+use crate::infrastructure::backends::aws::uri_encode;
+use crate::infrastructure::backends::event_stream::EventStreamDecoder;
+use crate::infrastructure::backends::retry;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelSummary {
+    model_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider_name: Option<String>,
+    #[serde(default)]
+    response_streaming_supported: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelListResponse {
+    model_summaries: Vec<ModelSummary>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ImageSource {
+    bytes: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ImageBlock {
+    format: String,
+    source: ImageSource,
+}
+
+impl ImageBlock {
+    fn new(image: &ImageAttachment) -> ImageBlock {
+        return ImageBlock {
+            format: image.media_type.trim_start_matches("image/").to_string(),
+            source: ImageSource {
+                bytes: image.data.to_string(),
+            },
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolUseBlock {
+    tool_use_id: String,
+    name: String,
+    input: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolResultContent {
+    text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolResultBlock {
+    tool_use_id: String,
+    content: Vec<ToolResultContent>,
+    status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ContentBlock {
+    Text(String),
+    Image(ImageBlock),
+    ToolUse(ToolUseBlock),
+    ToolResult(ToolResultBlock),
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageRequest {
+    role: String,
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SystemBlock {
+    text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InferenceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct InputSchema {
+    json: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolSpec {
+    name: String,
+    description: String,
+    input_schema: InputSchema,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolRequest {
+    tool_spec: ToolSpec,
+}
+
+impl ToolRequest {
+    fn new(tool: ToolDefinition) -> ToolRequest {
+        return ToolRequest {
+            tool_spec: ToolSpec {
+                name: tool.name,
+                description: tool.description,
+                input_schema: InputSchema {
+                    json: tool.parameters,
+                },
+            },
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolConfig {
+    tools: Vec<ToolRequest>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionRequest {
+    messages: Vec<MessageRequest>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    system: Vec<SystemBlock>,
+    inference_config: InferenceConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<ToolConfig>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolUseStartResponse {
+    tool_use_id: String,
+    name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentBlockStartInnerResponse {
+    #[serde(default)]
+    tool_use: Option<ToolUseStartResponse>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ContentBlockStartResponse {
+    start: ContentBlockStartInnerResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ReasoningDeltaResponse {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolUseDeltaResponse {
+    #[serde(default)]
+    input: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeltaResponse {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    reasoning_content: Option<ReasoningDeltaResponse>,
+    #[serde(default)]
+    tool_use: Option<ToolUseDeltaResponse>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ContentBlockDeltaResponse {
+    delta: DeltaResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageResponse {
+    #[serde(default)]
+    input_tokens: Option<u64>,
+    #[serde(default)]
+    output_tokens: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetricsResponse {
+    #[serde(default)]
+    latency_ms: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MetadataResponse {
+    #[serde(default)]
+    usage: UsageResponse,
+    #[serde(default)]
+    metrics: MetricsResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ExceptionResponse {
+    #[serde(default)]
+    message: String,
+}
+
+fn messages_from_transcript(transcript: &Transcript) -> Vec<MessageRequest> {
+    return transcript
+        .turns
+        .iter()
+        .map(|turn| {
+            // Bedrock rejects empty text blocks, which a turn that only calls
+            // tools or only returns tool results would otherwise have.
+            let mut content = vec![];
+            if !turn.text.is_empty() {
+                content.push(ContentBlock::Text(turn.text.to_string()));
+            }
+            content.extend(
+                turn.images
+                    .iter()
+                    .map(|image| return ContentBlock::Image(ImageBlock::new(image))),
+            );
+            content.extend(turn.tool_calls.iter().map(|tool_call| {
+                return ContentBlock::ToolUse(ToolUseBlock {
+                    tool_use_id: tool_call.id.to_string(),
+                    name: tool_call.name.to_string(),
+                    input: tool_call.arguments.clone(),
+                });
+            }));
+            content.extend(turn.tool_results.iter().map(|tool_result| {
+                let mut status = "success";
+                if tool_result.is_error {
+                    status = "error";
+                }
+                return ContentBlock::ToolResult(ToolResultBlock {
+                    tool_use_id: tool_result.id.to_string(),
+                    content: vec![ToolResultContent {
+                        text: tool_result.content.to_string(),
+                    }],
+                    status: status.to_string(),
+                });
+            }));
+
+            let mut role = "user";
+            if turn.role == Role::Assistant {
+                role = "assistant";
+            }
+
+            return MessageRequest {
+                role: role.to_string(),
+                content,
+            };
+        })
+        .collect();
+}
+
+/// Amazon Bedrock, which serves models from several providers behind one
+/// API. Requests are signed with AWS Signature Version 4 and completions are
+/// streamed from the Converse API in AWS's binary event stream format.
+pub struct Bedrock {
+    region: String,
+    runtime_url: String,
+    control_url: String,
+    credentials: Result<Credentials>,
+    timeout: String,
+    client: reqwest::Client,
+    /// The time requests are signed for, which tests can fix.
+    now: fn() -> DateTime<Utc>,
+}
+
+impl Bedrock {
+    pub fn new(client: reqwest::Client) -> Bedrock {
+        let region = Config::get(ConfigKey::BedrockRegion);
+
+        return Bedrock {
+            runtime_url: format!("https://bedrock-runtime.{region}.amazonaws.com"),
+            control_url: format!("https://bedrock.{region}.amazonaws.com"),
+            region,
+            credentials: Credentials::load(&Config::get(ConfigKey::BedrockProfile)),
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+            now: Utc::now,
+        };
+    }
+
+    /// Builds a request signed for the current time. The URL's path must
+    /// already be percent encoded.
+    fn request(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let credentials = match &self.credentials {
+            Ok(credentials) => credentials,
+            Err(err) => bail!(err.to_string()),
+        };
+        let url = reqwest::Url::parse(url)?;
+        let signer = Signer {
+            credentials,
+            region: &self.region,
+            service: "bedrock",
+        };
+
+        let mut req = self.client.request(method.clone(), url.clone());
+        for (key, value) in signer.sign(&method, &url, &body, (self.now)()) {
+            req = req.header(key, value);
+        }
+        if !body.is_empty() {
+            req = req.header("content-type", "application/json").body(body);
+        }
+
+        return Ok(req);
+    }
+
+    fn models_url(&self) -> String {
+        return format!(
+            "{url}/foundation-models?byOutputModality=TEXT&byInferenceType=ON_DEMAND",
+            url = self.control_url
+        );
+    }
+}
+
+#[async_trait]
+impl Backend for Bedrock {
+    fn name(&self) -> BackendName {
+        return BackendName::Bedrock;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.region.is_empty() {
+            bail!("Bedrock region is not defined");
+        }
+        if let Err(err) = &self.credentials {
+            bail!(err.to_string());
+        }
+
+        let res = match self
+            .request(reqwest::Method::GET, &self.models_url(), vec![])?
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!(error = ?err, "Bedrock is not reachable");
+                bail!("Bedrock is not reachable");
+            }
+        };
+
+        let status = res.status().as_u16();
+        if status >= 400 {
+            tracing::error!(status = status, "Bedrock health check failed");
+            bail!("Bedrock health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .request(reqwest::Method::GET, &self.models_url(), vec![])?
+            .send()
+            .await?
+            .json::<ModelListResponse>()
+            .await?;
+
+        let mut models = res
+            .model_summaries
+            .iter()
+            .filter(|model| return model.response_streaming_supported)
+            .map(|model| {
+                return ModelInfo {
+                    owned_by: model.provider_name.clone(),
+                    ..ModelInfo::new(&model.model_id)
+                };
+            })
+            .collect::<Vec<ModelInfo>>();
+
+        models.sort();
+
+        return Ok(models);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let mut transcript = prompt.to_transcript();
+
+        let mut system = vec![];
+        if !prompt.system_prompt.is_empty() {
+            system.push(SystemBlock {
+                text: prompt.system_prompt.to_string(),
+            });
+        }
+        let mut tool_config = None;
+        if !prompt.tools.is_empty() {
+            tool_config = Some(ToolConfig {
+                tools: prompt.tools.into_iter().map(ToolRequest::new).collect(),
+            });
+        }
+        let req = CompletionRequest {
+            messages: messages_from_transcript(&transcript),
+            system,
+            inference_config: InferenceConfig {
+                max_tokens: prompt.params.max_tokens,
+                temperature: prompt.params.temperature,
+                top_p: prompt.params.top_p,
+                stop_sequences: prompt.params.stop,
+            },
+            tool_config,
+        };
+
+        let url = format!(
+            "{url}/model/{model}/converse-stream",
+            url = self.runtime_url,
+            model = uri_encode(&prompt.model)
+        );
+        let req_builder = self.request(reqwest::Method::POST, &url, serde_json::to_vec(&req)?)?;
+        let res = retry::send(req_builder, tx).await?;
+
+        if !res.status().is_success() {
+            let status = res.status().as_u16();
+            let message = res
+                .json::<ExceptionResponse>()
+                .await
+                .unwrap_or_default()
+                .message;
+            tracing::error!(
+                status = status,
+                message = message,
+                "Failed to make completion request to Bedrock"
+            );
+            if message.is_empty() {
+                bail!("Failed to make completion request to Bedrock");
+            }
+            bail!(format!(
+                "Failed to make completion request to Bedrock. {message}"
+            ));
+        }
+
+        let mut decoder = EventStreamDecoder::new(res);
+
+        let mut last_message = String::new();
+        let mut usage = Usage::default();
+        let mut tool_calls: Vec<ToolCall> = vec![];
+        let mut tool_inputs: Vec<String> = vec![];
+        while let Some(message) = decoder.next().await? {
+            // Errors that happen once the stream started are sent as
+            // exception messages, named by their type.
+            if message.header(":message-type") == "exception" {
+                let exception = serde_json::from_slice::<ExceptionResponse>(&message.payload)
+                    .unwrap_or_default();
+                bail!(format!(
+                    "Bedrock returned {}: {}",
+                    message.header(":exception-type"),
+                    exception.message
+                ));
+            }
+            if message.header(":message-type") == "error" {
+                bail!(format!(
+                    "Bedrock returned {}: {}",
+                    message.header(":error-code"),
+                    message.header(":error-message")
+                ));
+            }
+
+            let event_type = message.header(":event-type");
+            if event_type == "contentBlockStart" {
+                let ores: ContentBlockStartResponse = serde_json::from_slice(&message.payload)?;
+                if let Some(tool_use) = ores.start.tool_use {
+                    tool_calls.push(ToolCall {
+                        id: tool_use.tool_use_id,
+                        name: tool_use.name,
+                        arguments: serde_json::json!({}),
+                    });
+                    tool_inputs.push(String::new());
+                }
+                continue;
+            }
+            // Usage arrives in the metadata event after the message stops.
+            if event_type == "metadata" {
+                let ores: MetadataResponse = serde_json::from_slice(&message.payload)?;
+                usage.prompt_tokens = ores.usage.input_tokens;
+                usage.completion_tokens = ores.usage.output_tokens;
+                usage.generation_ms = ores.metrics.latency_ms;
+                continue;
+            }
+            if event_type != "contentBlockDelta" {
+                continue;
+            }
+
+            let ores: ContentBlockDeltaResponse = serde_json::from_slice(&message.payload)?;
+            tracing::debug!(body = ?ores, "Completion response");
+
+            if let Some(tool_use) = ores.delta.tool_use {
+                if let Some(input) = tool_inputs.last_mut() {
+                    *input += &tool_use.input;
+                }
+                continue;
+            }
+
+            let text = ores.delta.text;
+            let thinking = ores
+                .delta
+                .reasoning_content
+                .map(|reasoning| return reasoning.text)
+                .unwrap_or_default();
+            if text.is_empty() && thinking.is_empty() {
+                continue;
+            }
+
+            last_message += &text;
+            let msg = BackendResponse {
+                author: Author::Model,
+                text,
+                thinking,
+                done: false,
+                transcript: None,
+                usage: None,
+                tool_calls: vec![],
+            };
+
+            tx.send(Event::BackendPromptResponse(msg))?;
+        }
+
+        for (tool_call, input) in tool_calls.iter_mut().zip(tool_inputs) {
+            if let Ok(arguments) = serde_json::from_str(&input) {
+                tool_call.arguments = arguments;
+            }
+        }
+
+        let mut turn = Turn::new(Role::Assistant, &last_message);
+        turn.tool_calls = tool_calls.clone();
+        transcript.push(turn);
+
+        let msg = BackendResponse {
+            author: Author::Model,
+            text: String::new(),
+            thinking: String::new(),
+            done: true,
+            transcript: Some(transcript),
+            usage: Some(usage),
+            tool_calls,
+        };
+        tx.send(Event::BackendPromptResponse(msg))?;
+        decoder.finish()?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use serde_json::json;
+use test_utils::event_stream_message;
+use tokio::sync::mpsc;
+
+use super::Bedrock;
+use super::ModelListResponse;
+use super::ModelSummary;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::aws::Credentials;
+
+const MODEL: &str = "anthropic.claude-3-haiku-20240307-v1:0";
+const CONVERSE_PATH: &str = "/model/anthropic.claude-3-haiku-20240307-v1%3A0/converse-stream";
+/// Matches a signature from the test credentials, for any request.
+const SIGNED: &str = r"^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/us-east-1/bedrock/aws4_request, SignedHeaders=host;x-amz-date;x-amz-security-token, Signature=[0-9a-f]{64}$";
+
+fn credentials() -> Credentials {
+    return Credentials {
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        session_token: Some("session".to_string()),
+    };
+}
+
+impl Bedrock {
+    fn with_url(url: String) -> Bedrock {
+        return Bedrock {
+            region: "us-east-1".to_string(),
+            runtime_url: url.to_string(),
+            control_url: url,
+            credentials: Ok(credentials()),
+            timeout: "200".to_string(),
+            client: reqwest::Client::new(),
+            now: signing_time,
+        };
+    }
+}
+
+fn signing_time() -> DateTime<Utc> {
+    return Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+}
+
+fn event(event_type: &str, payload: serde_json::Value) -> Vec<u8> {
+    return event_stream_message(
+        &[
+            (":message-type", "event"),
+            (":event-type", event_type),
+            (":content-type", "application/json"),
+        ],
+        &payload.to_string(),
+    );
+}
+
+fn prompt() -> BackendPrompt {
+    return BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: MODEL.to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "Be brief.".to_string(),
+        params: GenerationParams {
+            max_tokens: Some(100),
+            ..GenerationParams::default()
+        },
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/foundation-models")
+        .match_query(mockito::Matcher::Any)
+        .match_header("authorization", mockito::Matcher::Regex(SIGNED.to_string()))
+        .match_header("x-amz-security-token", "session")
+        .with_status(200)
+        .with_body(json!({"modelSummaries": []}).to_string())
+        .create_async()
+        .await;
+
+    let backend = Bedrock::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/foundation-models")
+        .match_query(mockito::Matcher::Any)
+        .with_status(403)
+        .create_async()
+        .await;
+
+    let backend = Bedrock::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert_eq!(res.unwrap_err().to_string(), "Bedrock health check failed");
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_credentials() {
+    let mut backend = Bedrock::with_url("http://localhost".to_string());
+    backend.credentials = Err(anyhow!(
+        "AWS credentials were not found in the environment or in /home/user/.aws/credentials"
+    ));
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "AWS credentials were not found in the environment or in /home/user/.aws/credentials"
+    );
+}
+
+#[tokio::test]
+async fn it_lists_models() -> Result<()> {
+    let body = serde_json::to_string(&ModelListResponse {
+        model_summaries: vec![
+            ModelSummary {
+                model_id: MODEL.to_string(),
+                provider_name: Some("Anthropic".to_string()),
+                response_streaming_supported: true,
+            },
+            ModelSummary {
+                model_id: "meta.llama3-8b-instruct-v1:0".to_string(),
+                provider_name: Some("Meta".to_string()),
+                response_streaming_supported: true,
+            },
+            ModelSummary {
+                model_id: "amazon.titan-embed-text-v2:0".to_string(),
+                provider_name: Some("Amazon".to_string()),
+                response_streaming_supported: false,
+            },
+        ],
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/foundation-models")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("byOutputModality".to_string(), "TEXT".to_string()),
+            mockito::Matcher::UrlEncoded("byInferenceType".to_string(), "ON_DEMAND".to_string()),
+        ]))
+        .match_header("authorization", mockito::Matcher::Regex(SIGNED.to_string()))
+        .match_header("x-amz-security-token", "session")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let backend = Bedrock::with_url(server.url());
+    let res = backend.list_models().await?;
+
+    assert_eq!(res, vec![
+        ModelInfo {
+            owned_by: Some("Anthropic".to_string()),
+            ..ModelInfo::new(MODEL)
+        },
+        ModelInfo {
+            owned_by: Some("Meta".to_string()),
+            ..ModelInfo::new("meta.llama3-8b-instruct-v1:0")
+        },
+    ]);
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let body = [
+        event("messageStart", json!({"role": "assistant"})),
+        event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"text": "Hello "}}),
+        ),
+        event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"text": "World"}}),
+        ),
+        event("contentBlockStop", json!({"contentBlockIndex": 0})),
+        event("messageStop", json!({"stopReason": "end_turn"})),
+        event(
+            "metadata",
+            json!({"usage": {"inputTokens": 12, "outputTokens": 2, "totalTokens": 14}, "metrics": {"latencyMs": 250}}),
+        ),
+    ]
+    .concat();
+
+    // The signature covers the host, so the server's port has to be fixed. The
+    // model's `:` is encoded once in the path and signed encoded twice.
+    let mut server = mockito::Server::new_with_opts_async(mockito::ServerOpts {
+        port: 38124,
+        ..mockito::ServerOpts::default()
+    })
+    .await;
+    let mock = server
+        .mock("POST", CONVERSE_PATH)
+        .match_header("host", "127.0.0.1:38124")
+        .match_header("x-amz-date", "20240501T120000Z")
+        .match_header("x-amz-security-token", "session")
+        .match_header(
+            "authorization",
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/us-east-1/bedrock/aws4_request, SignedHeaders=host;x-amz-date;x-amz-security-token, Signature=a003e30430389f6c93e13fabb255856b6fa886e70ac7a5f27bae6110df9a2bdb",
+        )
+        .match_body(mockito::Matcher::Json(json!({
+            "messages": [
+                {"role": "assistant", "content": [{"text": "How may I help you?"}]},
+                {"role": "user", "content": [{"text": "Say hi to the world"}]},
+            ],
+            "system": [{"text": "Be brief."}],
+            "inferenceConfig": {"maxTokens": 100},
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/vnd.amazon.eventstream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Bedrock::with_url(server.url());
+    backend.get_completion(prompt(), &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert!(third_recv.done);
+    assert_eq!(
+        third_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(12),
+            completion_tokens: Some(2),
+            generation_ms: Some(250),
+            ..Usage::default()
+        })
+    );
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::Assistant, "How may I help you?"),
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_tool_calls() -> Result<()> {
+    let body = [
+        event("messageStart", json!({"role": "assistant"})),
+        event(
+            "contentBlockStart",
+            json!({"contentBlockIndex": 0, "start": {"toolUse": {"toolUseId": "tooluse_1", "name": "read_file"}}}),
+        ),
+        event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"toolUse": {"input": "{\"path\":"}}}),
+        ),
+        event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"toolUse": {"input": "\"README.md\"}"}}}),
+        ),
+        event("contentBlockStop", json!({"contentBlockIndex": 0})),
+        event("messageStop", json!({"stopReason": "tool_use"})),
+    ]
+    .concat();
+
+    let mut prompt = prompt();
+    prompt.tools = vec![ToolDefinition {
+        name: "read_file".to_string(),
+        description: "Reads a file.".to_string(),
+        parameters: json!({"type": "object"}),
+    }];
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", CONVERSE_PATH)
+        .match_body(mockito::Matcher::PartialJson(json!({
+            "toolConfig": {"tools": [{"toolSpec": {
+                "name": "read_file",
+                "description": "Reads a file.",
+                "inputSchema": {"json": {"type": "object"}},
+            }}]},
+        })))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Bedrock::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    let tool_call = ToolCall {
+        id: "tooluse_1".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    };
+    assert!(recv.done);
+    assert_eq!(recv.tool_calls, vec![tool_call.clone()]);
+    assert_eq!(
+        recv.transcript.unwrap().turns.last().unwrap().tool_calls,
+        vec![tool_call]
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_on_stream_exceptions() -> Result<()> {
+    let body = [
+        event(
+            "contentBlockDelta",
+            json!({"contentBlockIndex": 0, "delta": {"text": "Hello "}}),
+        ),
+        event_stream_message(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            &json!({"message": "Too many requests, please wait before trying again."}).to_string(),
+        ),
+    ]
+    .concat();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", CONVERSE_PATH)
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Bedrock::with_url(server.url());
+    let res = backend.get_completion(prompt(), &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Bedrock returned throttlingException: Too many requests, please wait before trying again."
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", CONVERSE_PATH)
+        .with_status(403)
+        .with_body(
+            json!({"message": "The security token included in the request is invalid."})
+                .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Bedrock::with_url(server.url());
+    let res = backend.get_completion(prompt(), &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Bedrock. The security token included in the request is invalid."
+    );
+
+    return Ok(());
+}
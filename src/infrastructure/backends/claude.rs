@@ -7,14 +7,9 @@ use std::time::Duration;
 use anyhow::Result;
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::stream::TryStreamExt;
-use itertools::Itertools;
-use regex::Regex;
 use serde::Deserialize;
 use serde::Serialize;
-use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
-use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -24,11 +19,18 @@ use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
-
-fn convert_err(err: reqwest::Error) -> std::io::Error {
-    let err_msg = err.to_string();
-    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
-}
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ThinkingBlock;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::StreamDecoder;
+use crate::infrastructure::backends::stream::request_error;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Model {
@@ -38,20 +40,152 @@ struct Model {
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct ModelListResponse {
     data: Vec<Model>,
+    has_more: bool,
+    last_id: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ImageSource {
+    #[serde(rename = "type")]
+    _type: String,
+    media_type: String,
+    data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: ImageSource,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+    },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
+}
+
+impl ContentBlock {
+    fn from_thinking(block: &ThinkingBlock) -> ContentBlock {
+        if !block.redacted_data.is_empty() {
+            return ContentBlock::RedactedThinking {
+                data: block.redacted_data.to_string(),
+            };
+        }
+
+        return ContentBlock::Thinking {
+            thinking: block.text.to_string(),
+            signature: block.signature.to_string(),
+        };
+    }
+}
+
+/// Message content is plain text, or a list of blocks when images are attached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+impl Default for MessageContent {
+    fn default() -> MessageContent {
+        return MessageContent::Text(String::new());
+    }
+}
+
+impl MessageContent {
+    fn new(text: String, images: &[ImageAttachment]) -> MessageContent {
+        if images.is_empty() {
+            return MessageContent::Text(text);
+        }
+
+        // Claude recommends placing images before the text that refers to them.
+        let mut blocks = images
+            .iter()
+            .map(|image| {
+                return ContentBlock::Image {
+                    source: ImageSource {
+                        _type: "base64".to_string(),
+                        media_type: image.media_type.to_string(),
+                        data: image.data.to_string(),
+                    },
+                };
+            })
+            .collect::<Vec<ContentBlock>>();
+        // Claude rejects empty text blocks.
+        if !text.is_empty() {
+            blocks.push(ContentBlock::Text { text });
+        }
+
+        return MessageContent::Blocks(blocks);
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct MessageRequest {
     role: String,
-    content: String,
+    content: MessageContent,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolRequest {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+impl ToolRequest {
+    fn new(tool: ToolDefinition) -> ToolRequest {
+        return ToolRequest {
+            name: tool.name,
+            description: tool.description,
+            input_schema: tool.parameters,
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ThinkingRequest {
+    #[serde(rename = "type")]
+    _type: String,
+    budget_tokens: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct CompletionRequest {
     model: String,
     max_tokens: u32,
     messages: Vec<MessageRequest>,
     stream: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    system: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ThinkingRequest>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -63,7 +197,14 @@ struct Healthcheck {
 struct CompletionDeltaResponse {
     #[serde(rename = "type")]
     _type: String,
+    #[serde(default)]
     text: String,
+    /// Set on `thinking_delta` events when extended thinking is enabled.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    thinking: String,
+    /// Set on `signature_delta` events, once a thinking block is complete.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    signature: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,20 +214,181 @@ struct CompletionResponse {
     delta: CompletionDeltaResponse,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct UsageResponse {
+    #[serde(default)]
+    input_tokens: Option<u64>,
+    #[serde(default)]
+    output_tokens: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageStartMessageResponse {
+    usage: UsageResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageStartResponse {
+    message: MessageStartMessageResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageDeltaResponse {
+    usage: UsageResponse,
+}
+
+/// Renders the transcript as Claude messages. Tool calls and their results are
+/// sent as content blocks. Turns left with nothing to send, like an aborted
+/// reply, are skipped since Claude rejects empty content.
+fn messages_from_transcript(transcript: &Transcript) -> Vec<MessageRequest> {
+    return transcript
+        .turns
+        .iter()
+        .filter(|turn| {
+            return !turn.text.is_empty()
+                || !turn.images.is_empty()
+                || !turn.tool_calls.is_empty()
+                || !turn.tool_results.is_empty();
+        })
+        .map(|turn| {
+            if turn.role == Role::Assistant {
+                let mut content = MessageContent::Text(turn.text.to_string());
+                if !turn.tool_calls.is_empty() {
+                    // Thinking has to come first, unchanged, for Claude to
+                    // accept the tool results that follow.
+                    let mut blocks = turn
+                        .thinking
+                        .iter()
+                        .map(ContentBlock::from_thinking)
+                        .collect::<Vec<ContentBlock>>();
+                    if !turn.text.is_empty() {
+                        blocks.push(ContentBlock::Text {
+                            text: turn.text.to_string(),
+                        });
+                    }
+                    blocks.extend(turn.tool_calls.iter().map(|tool_call| {
+                        return ContentBlock::ToolUse {
+                            id: tool_call.id.to_string(),
+                            name: tool_call.name.to_string(),
+                            input: tool_call.arguments.clone(),
+                        };
+                    }));
+                    content = MessageContent::Blocks(blocks);
+                }
+
+                return MessageRequest {
+                    role: "assistant".to_string(),
+                    content,
+                };
+            }
+
+            let mut content = MessageContent::new(turn.text.to_string(), &turn.images);
+            if !turn.tool_results.is_empty() {
+                let blocks = turn
+                    .tool_results
+                    .iter()
+                    .map(|tool_result| {
+                        return ContentBlock::ToolResult {
+                            tool_use_id: tool_result.id.to_string(),
+                            content: tool_result.content.to_string(),
+                            is_error: tool_result.is_error,
+                        };
+                    })
+                    .collect();
+                content = MessageContent::Blocks(blocks);
+            }
+
+            return MessageRequest {
+                role: "user".to_string(),
+                content,
+            };
+        })
+        .collect();
+}
+
 pub struct Claude {
     url: String,
     token: String,
+    thinking_budget: String,
     timeout: String,
+    client: reqwest::Client,
 }
 
-impl Default for Claude {
-    fn default() -> Claude {
+impl Claude {
+    pub fn new(client: reqwest::Client) -> Claude {
         return Claude {
             url: "https://api.anthropic.com".to_string(),
             token: Config::get(ConfigKey::ClaudeToken),
+            thinking_budget: Config::get(ConfigKey::ClaudeThinkingBudget),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+        };
+    }
+}
+
+impl Claude {
+    /// Returns the thinking request when a budget is configured.
+    fn thinking(&self) -> Result<Option<ThinkingRequest>> {
+        if self.thinking_budget.is_empty() {
+            return Ok(None);
+        }
+
+        return match self.thinking_budget.parse::<u32>() {
+            Ok(budget_tokens) if budget_tokens >= 1024 => {
+                Ok(Some(ThinkingRequest {
+                    _type: "enabled".to_string(),
+                    budget_tokens,
+                }))
+            }
+            _ => {
+                bail!(format!(
+                    "Invalid value for {}: {}. Expected a whole number of at least 1024.",
+                    ConfigKey::ClaudeThinkingBudget,
+                    self.thinking_budget
+                ))
+            }
         };
     }
+
+    /// Pages through Anthropic's model listing endpoint.
+    async fn fetch_models(&self) -> Result<Vec<String>> {
+        let mut models: Vec<String> = vec![];
+        let mut after_id: Option<String> = None;
+
+        loop {
+            let mut req = self
+                .client
+                .get(format!("{url}/v1/models", url = self.url))
+                .query(&[("limit", "1000")])
+                .header("x-api-key", &self.token)
+                .header("anthropic-version", "2023-06-01");
+            if let Some(id) = &after_id {
+                req = req.query(&[("after_id", id)]);
+            }
+
+            let res = req.send().await?;
+            if !res.status().is_success() {
+                bail!(format!(
+                    "Failed to list Claude models, {}",
+                    res.status().as_u16()
+                ));
+            }
+
+            let page = res.json::<ModelListResponse>().await?;
+            models.extend(page.data.into_iter().map(|model| return model.id));
+
+            if !page.has_more || page.last_id.is_none() {
+                break;
+            }
+            after_id = page.last_id;
+        }
+
+        if models.is_empty() {
+            bail!("Claude returned no models");
+        }
+
+        return Ok(models);
+    }
 }
 
 #[async_trait]
@@ -104,7 +406,8 @@ impl Backend for Claude {
             bail!("Claude token is not defined");
         }
 
-        let res = reqwest::Client::new()
+        let res = self
+            .client
             .get(format!("{url}/healthcheck", url = self.url))
             .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
             .send()
@@ -131,38 +434,26 @@ impl Backend for Claude {
     }
 
     #[allow(clippy::implicit_return)]
-    async fn list_models(&self) -> Result<Vec<String>> {
-        let backup = vec![
-            "claude-3-haiku-20240307".to_string(),
-            "claude-3-sonnet-20240229".to_string(),
-            "claude-3-opus-20240229".to_string(),
-            "claude-2.1".to_string(),
-            "claude-2.0".to_string(),
-        ];
-        let res = reqwest::Client::new()
-            .get("https://raw.githubusercontent.com/anthropics/anthropic-sdk-typescript/main/src/resources/messages/messages.ts")
-            .send()
-            .await;
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let models = self.fetch_models().await?;
 
-        let body = match res {
-            Ok(html) => html.text().await,
-            Err(_) => return Ok(backup),
-        };
+        return Ok(models
+            .iter()
+            .map(|model| return ModelInfo::new(model))
+            .collect());
+    }
 
-        match body {
-            Ok(html) => {
-                let re = Regex::new(r#"['"](claude-.*)['"]"#).unwrap();
-                let mut results: Vec<String> = vec![];
-                let cleaned_regex = Regex::new(r"[^a-zA-Z0-9-\.]").unwrap();
-                for (_, [model]) in re.captures_iter(&html).map(|c| c.extract()) {
-                    let m = model.to_string();
-                    let cleaned = cleaned_regex.replace_all(&m, "").to_string();
-                    results.push(cleaned);
-                }
-                return Ok(results.into_iter().unique().collect());
-            }
-            Err(_) => return Ok(backup),
-        };
+    fn fallback_models(&self) -> Vec<ModelInfo> {
+        return [
+            "claude-3-haiku-20240307",
+            "claude-3-sonnet-20240229",
+            "claude-3-opus-20240229",
+            "claude-2.1",
+            "claude-2.0",
+        ]
+        .iter()
+        .map(|model| return ModelInfo::new(model))
+        .collect();
     }
 
     #[allow(clippy::implicit_return)]
@@ -171,69 +462,125 @@ impl Backend for Claude {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
-        let mut messages: Vec<MessageRequest> = vec![];
-        if !prompt.backend_context.is_empty() {
-            messages = serde_json::from_str(&prompt.backend_context)?;
+        let mut transcript = prompt.to_transcript();
+
+        // Claude requires max_tokens to always be set.
+        let mut max_tokens = prompt.params.max_tokens.unwrap_or(1024);
+        let mut temperature = prompt.params.temperature;
+        let thinking = self.thinking()?;
+        if let Some(thinking) = &thinking {
+            // The thinking budget counts towards max_tokens, so the answer keeps
+            // its own room on top of it. Claude rejects a temperature while
+            // thinking.
+            max_tokens += thinking.budget_tokens;
+            temperature = None;
         }
-        messages.push(MessageRequest {
-            role: "user".to_string(),
-            content: prompt.text,
-        });
 
         let req = CompletionRequest {
-            model: Config::get(ConfigKey::Model),
-            max_tokens: 1024,
-            messages: messages.clone(),
+            model: prompt.model,
+            max_tokens,
+            messages: messages_from_transcript(&transcript),
             stream: true,
+            system: prompt.system_prompt,
+            temperature,
+            top_p: prompt.params.top_p,
+            stop_sequences: prompt.params.stop,
+            tools: prompt.tools.into_iter().map(ToolRequest::new).collect(),
+            thinking,
         };
 
-        let res = reqwest::Client::new()
+        let req_builder = self
+            .client
             .post(format!("{url}/v1/messages", url = self.url))
             .header("x-api-key", &self.token)
             .header("content-type", "application/json")
             .header("anthropic-version", "2023-06-01")
             .header("anthropic-beta", "messages-2023-12-15")
-            .json(&req)
-            .send()
-            .await?;
+            .json(&req);
+        let res = retry::send(req_builder, tx).await?;
 
         if !res.status().is_success() {
-            tracing::error!(
-                status = res.status().as_u16(),
-                "Failed to make completion request to Claude"
-            );
-            bail!("Failed to make completion request to Claude");
+            return Err(request_error("Claude", res).await);
         }
 
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+        let mut decoder = StreamDecoder::new(res);
 
         let mut last_message = String::new();
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
+        let mut usage = Usage::default();
+        let mut tool_calls: Vec<ToolCall> = vec![];
+        let mut tool_inputs: Vec<String> = vec![];
+        let mut thinking_blocks: Vec<ThinkingBlock> = vec![];
+        while let Some(event) = decoder.next::<serde_json::Value>().await? {
+            let event_type = event["type"].as_str().unwrap_or_default().to_string();
+
+            // Thinking blocks are kept whole, with their signature, so they can
+            // be sent back alongside tool results.
+            if event_type == "content_block_start" && event["content_block"]["type"] == "thinking" {
+                thinking_blocks.push(ThinkingBlock::default());
+                continue;
+            }
+            if event_type == "content_block_start"
+                && event["content_block"]["type"] == "redacted_thinking"
+            {
+                thinking_blocks.push(ThinkingBlock {
+                    redacted_data: event["content_block"]["data"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    ..ThinkingBlock::default()
+                });
+                continue;
             }
 
-            let mut cleaned_line = line.unwrap().trim().to_string();
-            if cleaned_line.starts_with("data:") {
-                cleaned_line = cleaned_line.split_off(5).trim().to_string();
+            // Tool calls arrive as their own content block, with the input JSON
+            // streamed in pieces through the deltas that follow.
+            if event_type == "content_block_start" && event["content_block"]["type"] == "tool_use" {
+                let block = &event["content_block"];
+                tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_string(),
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: serde_json::json!({}),
+                });
+                tool_inputs.push(String::new());
+                continue;
             }
-            if cleaned_line.is_empty() || cleaned_line.contains("event:") {
+            if event_type == "content_block_delta" && event["delta"]["type"] == "input_json_delta" {
+                if let Some(input) = tool_inputs.last_mut() {
+                    *input += event["delta"]["partial_json"].as_str().unwrap_or_default();
+                }
                 continue;
             }
 
-            if cleaned_line.contains("content_block_stop") {
+            // Input tokens are reported when the message starts, and the
+            // cumulative output tokens in message_delta events once it ends.
+            if event_type == "message_start" {
+                let ores: MessageStartResponse = serde_json::from_value(event)?;
+                usage.prompt_tokens = ores.message.usage.input_tokens;
+                continue;
+            }
+            if event_type == "message_delta" {
+                let ores: MessageDeltaResponse = serde_json::from_value(event)?;
+                usage.completion_tokens = ores.usage.output_tokens;
+                continue;
+            }
+            if event_type == "message_stop" {
                 break;
             }
-            if !cleaned_line.contains("content_block_delta") {
+            if event_type != "content_block_delta" {
                 continue;
             }
 
-            let ores: CompletionResponse = serde_json::from_str(&cleaned_line).unwrap();
+            let ores: CompletionResponse = serde_json::from_value(event)?;
             tracing::debug!(body = ?ores, "Completion response");
 
+            if let Some(block) = thinking_blocks.last_mut() {
+                block.text += &ores.delta.thinking;
+                block.signature += &ores.delta.signature;
+            }
+
             let text = ores.delta.text.clone().to_string();
-            if text.is_empty() {
+            let thinking = ores.delta.thinking.clone().to_string();
+            if text.is_empty() && thinking.is_empty() {
                 continue;
             }
 
@@ -241,25 +588,40 @@ impl Backend for Claude {
             let msg = BackendResponse {
                 author: Author::Model,
                 text,
+                thinking,
                 done: false,
-                context: None,
+                transcript: None,
+                usage: None,
+                tool_calls: vec![],
             };
 
             tx.send(Event::BackendPromptResponse(msg))?;
         }
 
-        messages.push(MessageRequest {
-            role: "assistant".to_string(),
-            content: last_message.to_string(),
-        });
+        for (tool_call, input) in tool_calls.iter_mut().zip(tool_inputs) {
+            if let Ok(arguments) = serde_json::from_str(&input) {
+                tool_call.arguments = arguments;
+            }
+        }
+
+        let mut turn = Turn::new(Role::Assistant, &last_message);
+        turn.tool_calls = tool_calls.clone();
+        if !tool_calls.is_empty() {
+            turn.thinking = thinking_blocks;
+        }
+        transcript.push(turn);
 
         let msg = BackendResponse {
             author: Author::Model,
             text: String::new(),
+            thinking: String::new(),
             done: true,
-            context: Some(serde_json::to_string(&messages)?),
+            transcript: Some(transcript),
+            usage: Some(usage),
+            tool_calls,
         };
         tx.send(Event::BackendPromptResponse(msg))?;
+        decoder.finish()?;
 
         return Ok(());
     }
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use anyhow::bail;
+use serde_json::json;
 use test_utils::insta_snapshot;
 use tokio::sync::mpsc;
 
@@ -7,19 +8,34 @@ use super::Claude;
 use super::CompletionDeltaResponse;
 use super::CompletionResponse;
 use super::Healthcheck;
-use super::MessageRequest;
+use super::Model;
+use super::ModelListResponse;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ThinkingBlock;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::ToolResult;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
 
 impl Claude {
     fn with_url(url: String) -> Claude {
         return Claude {
             url,
             token: "abc".to_string(),
+            thinking_budget: "".to_string(),
             timeout: "500".to_string(),
+            client: reqwest::Client::new(),
         };
     }
 }
@@ -81,10 +97,80 @@ async fn it_fails_health_checks() {
 
 #[tokio::test]
 async fn it_lists_models() -> Result<()> {
-    let backend = Claude::with_url("https://api.anthropic.com".to_string());
+    let first_page = serde_json::to_string(&ModelListResponse {
+        data: vec![Model {
+            id: "claude-first".to_string(),
+        }],
+        has_more: true,
+        last_id: Some("claude-first".to_string()),
+    })?;
+    let second_page = serde_json::to_string(&ModelListResponse {
+        data: vec![Model {
+            id: "claude-second".to_string(),
+        }],
+        has_more: false,
+        last_id: Some("claude-second".to_string()),
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let first_mock = server
+        .mock("GET", "/v1/models")
+        .match_query(mockito::Matcher::Exact("limit=1000".to_string()))
+        .match_header("x-api-key", "abc")
+        .match_header("anthropic-version", "2023-06-01")
+        .with_status(200)
+        .with_body(first_page)
+        .create_async()
+        .await;
+    let second_mock = server
+        .mock("GET", "/v1/models")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("limit".to_string(), "1000".to_string()),
+            mockito::Matcher::UrlEncoded("after_id".to_string(), "claude-first".to_string()),
+        ]))
+        .match_header("x-api-key", "abc")
+        .with_status(200)
+        .with_body(second_page)
+        .create_async()
+        .await;
+
+    let backend = Claude::with_url(server.url());
     let res = backend.list_models().await?;
 
-    assert!(!res.is_empty());
+    second_mock.assert();
+    first_mock.assert();
+    assert_eq!(res, vec![
+        ModelInfo::new("claude-first"),
+        ModelInfo::new("claude-second")
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_listing_models_with_a_fallback_list() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_query(mockito::Matcher::Any)
+        .with_status(401)
+        .create_async()
+        .await;
+
+    let backend = Claude::with_url(server.url());
+    let res = backend.list_models().await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to list Claude models, 401"
+    );
+    assert!(
+        backend
+            .fallback_models()
+            .contains(&ModelInfo::new("claude-3-opus-20240229"))
+    );
+
     return Ok(());
 }
 
@@ -95,6 +181,8 @@ async fn it_gets_completions() -> Result<()> {
         delta: CompletionDeltaResponse {
             _type: "text".to_string(),
             text: "Hello ".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
         },
     })?;
 
@@ -103,6 +191,8 @@ async fn it_gets_completions() -> Result<()> {
         delta: CompletionDeltaResponse {
             _type: "text".to_string(),
             text: "World".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
         },
     })?;
 
@@ -111,16 +201,24 @@ async fn it_gets_completions() -> Result<()> {
         delta: CompletionDeltaResponse {
             _type: "end".to_string(),
             text: "".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
         },
     })?;
 
     let body = [first_line, second_line, third_line].join("\n");
     let prompt = BackendPrompt {
         text: "Say hi to the world".to_string(),
-        backend_context: serde_json::to_string(&vec![MessageRequest {
-            role: "assistant".to_string(),
-            content: "How may I help you?".to_string(),
-        }])?,
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
     };
 
     let mut server = mockito::Server::new_async().await;
@@ -149,19 +247,564 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(first_recv.author, Author::Model);
     assert_eq!(first_recv.text, "Hello ".to_string());
     assert!(!first_recv.done);
-    assert_eq!(first_recv.context, None);
+    assert_eq!(first_recv.transcript, None);
 
     assert_eq!(second_recv.author, Author::Model);
     assert_eq!(second_recv.text, "World".to_string());
     assert!(!second_recv.done);
-    assert_eq!(second_recv.context, None);
+    assert_eq!(second_recv.transcript, None);
 
     assert_eq!(third_recv.author, Author::Model);
     assert!(third_recv.text.is_empty());
     assert!(third_recv.done);
     insta_snapshot(|| {
-        insta::assert_toml_snapshot!(third_recv.context);
+        insta::assert_toml_snapshot!(third_recv.transcript);
     });
 
     return Ok(());
 }
+
+#[tokio::test]
+async fn it_gets_completions_with_thinking() -> Result<()> {
+    let first_line = serde_json::to_string(&CompletionResponse {
+        _type: "content_block_delta".to_string(),
+        delta: CompletionDeltaResponse {
+            _type: "thinking_delta".to_string(),
+            text: "".to_string(),
+            thinking: "Hmm, a greeting".to_string(),
+            signature: "".to_string(),
+        },
+    })?;
+
+    let second_line = serde_json::to_string(&CompletionResponse {
+        _type: "content_block_delta".to_string(),
+        delta: CompletionDeltaResponse {
+            _type: "text_delta".to_string(),
+            text: "Hello".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
+        },
+    })?;
+
+    let body = [first_line, second_line].join("\n");
+    let prompt = BackendPrompt {
+        text: "Say hi".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "".to_string());
+    assert_eq!(first_recv.thinking, "Hmm, a greeting".to_string());
+
+    assert_eq!(second_recv.text, "Hello".to_string());
+    assert_eq!(second_recv.thinking, "".to_string());
+
+    assert!(third_recv.done);
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi"),
+        Turn::new(Role::Assistant, "Hello"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_system_prompt() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        _type: "content_block_stop".to_string(),
+        delta: CompletionDeltaResponse {
+            _type: "end".to_string(),
+            text: "".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
+        },
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "You are a pirate".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"system":"You are a pirate","messages":[{"role":"user","content":"Say hi to the world"}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_images() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        _type: "content_block_stop".to_string(),
+        delta: CompletionDeltaResponse {
+            _type: "end".to_string(),
+            text: "".to_string(),
+            thinking: "".to_string(),
+            signature: "".to_string(),
+        },
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "What is this?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![ImageAttachment {
+            name: "diagram.png".to_string(),
+            media_type: "image/png".to_string(),
+            data: "aGVsbG8=".to_string(),
+        }],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"user","content":[{"type":"image","source":{"type":"base64","media_type":"image/png","data":"aGVsbG8="}},{"type":"text","text":"What is this?"}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_usage() -> Result<()> {
+    let body = [
+        "event: message_start",
+        r#"data: {"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","content":[],"usage":{"input_tokens":25,"output_tokens":1}}}"#,
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#,
+        "event: content_block_stop",
+        r#"data: {"type":"content_block_stop","index":0}"#,
+        "event: message_delta",
+        r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":15}}"#,
+        "event: message_stop",
+        r#"data: {"type":"message_stop"}"#,
+    ]
+    .join("\n");
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello".to_string());
+    assert!(second_recv.done);
+    assert_eq!(
+        second_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(25),
+            completion_tokens: Some(15),
+            ..Usage::default()
+        })
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_calls() -> Result<()> {
+    let body = [
+        r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Let me check."}}"#,
+        r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"read_file","input":{}}}"#,
+        r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"path\":"}}"#,
+        r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"\"README.md\"}"}}"#,
+        r#"{"type":"message_stop"}"#,
+    ]
+    .join("\n");
+
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Reads a file.".to_string(),
+            parameters: json!({"type": "object"}),
+        }],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"tools":[{"name":"read_file","description":"Reads a file.","input_schema":{"type":"object"}}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    assert_eq!(first_recv.text, "Let me check.");
+
+    let second_recv = to_res(rx.recv().await)?;
+    assert!(second_recv.done);
+    assert_eq!(second_recv.tool_calls, vec![ToolCall {
+        id: "toolu_1".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }]);
+    let transcript = second_recv.transcript.unwrap();
+    assert_eq!(transcript.turns[1].text, "Let me check.");
+    assert_eq!(transcript.turns[1].tool_calls, second_recv.tool_calls);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_results() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "toolu_1".to_string(),
+            name: "read_file".to_string(),
+            content: "Failed to find README.md".to_string(),
+            is_error: true,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"Failed to find README.md","is_error":true}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(r#"{"type":"message_stop"}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_a_thinking_budget() -> Result<()> {
+    let body = [
+        r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+        r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"I should read it."}}"#,
+        r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig-1"}}"#,
+        r#"{"type":"content_block_start","index":1,"content_block":{"type":"redacted_thinking","data":"redacted-1"}}"#,
+        r#"{"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"toolu_1","name":"read_file","input":{}}}"#,
+        r#"{"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"path\":\"README.md\"}"}}"#,
+        r#"{"type":"message_stop"}"#,
+    ]
+    .join("\n");
+
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams {
+            temperature: Some(0.5),
+            ..GenerationParams::default()
+        },
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::Json(json!({
+            "model": "model-1",
+            "max_tokens": 3072,
+            "messages": [{"role": "user", "content": "What's in the readme?"}],
+            "stream": true,
+            "thinking": {"type": "enabled", "budget_tokens": 2048},
+        })))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut backend = Claude::with_url(server.url());
+    backend.thinking_budget = "2048".to_string();
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    assert_eq!(first_recv.thinking, "I should read it.");
+
+    let second_recv = to_res(rx.recv().await)?;
+    assert!(second_recv.done);
+    let transcript = second_recv.transcript.unwrap();
+    assert_eq!(transcript.turns[1].thinking, vec![
+        ThinkingBlock {
+            text: "I should read it.".to_string(),
+            signature: "sig-1".to_string(),
+            redacted_data: "".to_string(),
+        },
+        ThinkingBlock {
+            redacted_data: "redacted-1".to_string(),
+            ..ThinkingBlock::default()
+        },
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_thinking_back_with_tool_results() -> Result<()> {
+    let mut tool_turn = Turn::new(Role::Assistant, "");
+    tool_turn.tool_calls = vec![ToolCall {
+        id: "toolu_1".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }];
+    tool_turn.thinking = vec![ThinkingBlock {
+        text: "I should read it.".to_string(),
+        signature: "sig-1".to_string(),
+        redacted_data: "".to_string(),
+    }];
+
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::User, "What's in the readme?"), tool_turn],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "toolu_1".to_string(),
+            name: "read_file".to_string(),
+            content: "# Oatmeal".to_string(),
+            is_error: false,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJson(json!({
+            "messages": [
+                {"role": "user", "content": "What's in the readme?"},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "I should read it.", "signature": "sig-1"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "read_file", "input": {"path": "README.md"}},
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "# Oatmeal"},
+                ]},
+            ],
+        })))
+        .with_status(200)
+        .with_body(r#"{"type":"message_stop"}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_leaves_out_empty_text() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![
+                Turn::new(Role::User, "Say hi to the world"),
+                Turn::new(Role::Assistant, ""),
+            ],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![ImageAttachment {
+            name: "image.png".to_string(),
+            media_type: "image/png".to_string(),
+            data: "aW1hZ2U=".to_string(),
+        }],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_body(mockito::Matcher::PartialJson(json!({
+            "messages": [
+                {"role": "user", "content": "Say hi to the world"},
+                {"role": "user", "content": [
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "aW1hZ2U="}},
+                ]},
+            ],
+        })))
+        .with_status(200)
+        .with_body(r#"{"type":"message_stop"}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_an_invalid_thinking_budget() {
+    let prompt = BackendPrompt::new("Say hi".to_string(), Transcript::default());
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut backend = Claude::with_url("http://localhost".to_string());
+    backend.thinking_budget = "100".to_string();
+    let res = backend.get_completion(prompt, &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid value for claude-thinking-budget: 100. Expected a whole number of at least 1024."
+    );
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_the_provider_error() -> Result<()> {
+    let mut prompt = BackendPrompt::new("Say hi".to_string(), Transcript::default());
+    prompt.model = "model-1".to_string();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/messages")
+        .match_query(mockito::Matcher::Any)
+        .with_status(400)
+        .with_body(r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: Field required"}}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Claude::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Claude. max_tokens: Field required"
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,243 @@
+#[cfg(test)]
+#[path = "event_stream_test.rs"]
+mod tests;
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::bail;
+use futures::stream::TryStreamExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::StreamReader;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::infrastructure::backends::stream::convert_err;
+
+/// Total length, headers length and the prelude checksum.
+const PRELUDE_LEN: usize = 12;
+/// The checksum of the whole message, after the payload.
+const CHECKSUM_LEN: usize = 4;
+/// AWS caps event stream messages at 16MB.
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// A message from an AWS event stream. Only string headers are kept, which
+/// covers the headers that describe the event.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventMessage {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventMessage {
+    /// Returns the header's value, or an empty string when it's missing.
+    pub fn header(&self, name: &str) -> &str {
+        return self
+            .headers
+            .get(name)
+            .map(|e| return e.as_str())
+            .unwrap_or_default();
+    }
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Result<u16> {
+    return match bytes.get(pos..pos + 2) {
+        Some(slice) => Ok(u16::from_be_bytes([slice[0], slice[1]])),
+        None => bail!("Event stream header is truncated"),
+    };
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    return u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]);
+}
+
+/// Parses the headers section, where each header is a length prefixed name, a
+/// type byte and a value whose size depends on the type.
+fn decode_headers(bytes: &[u8]) -> Result<HashMap<String, String>> {
+    let mut headers = HashMap::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let name_len = bytes[pos] as usize;
+        pos += 1;
+        let name = match bytes.get(pos..pos + name_len) {
+            Some(name) => String::from_utf8_lossy(name).to_string(),
+            None => bail!("Event stream header is truncated"),
+        };
+        pos += name_len;
+
+        let value_type = match bytes.get(pos) {
+            Some(value_type) => *value_type,
+            None => bail!("Event stream header is truncated"),
+        };
+        pos += 1;
+
+        let value_len = match value_type {
+            // Booleans are stored in the type itself.
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            // Byte arrays and strings.
+            6 | 7 => {
+                let len = read_u16(bytes, pos)? as usize;
+                pos += 2;
+                len
+            }
+            9 => 16,
+            _ => bail!(format!("Unknown event stream header type {value_type}")),
+        };
+        let value = match bytes.get(pos..pos + value_len) {
+            Some(value) => value,
+            None => bail!("Event stream header is truncated"),
+        };
+        pos += value_len;
+
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).to_string());
+        }
+    }
+
+    return Ok(headers);
+}
+
+/// Decodes a single message, checking both of its checksums.
+pub fn decode_message(bytes: &[u8]) -> Result<EventMessage> {
+    if bytes.len() < PRELUDE_LEN + CHECKSUM_LEN {
+        bail!("Event stream message is too short");
+    }
+
+    let total_len = read_u32(bytes, 0) as usize;
+    let headers_len = read_u32(bytes, 4) as usize;
+    if total_len != bytes.len() || PRELUDE_LEN + headers_len + CHECKSUM_LEN > total_len {
+        bail!("Event stream message has an invalid length");
+    }
+    if crc32fast::hash(&bytes[..8]) != read_u32(bytes, 8) {
+        bail!("Event stream message has an invalid prelude checksum");
+    }
+    let checksum_pos = total_len - CHECKSUM_LEN;
+    if crc32fast::hash(&bytes[..checksum_pos]) != read_u32(bytes, checksum_pos) {
+        bail!("Event stream message has an invalid checksum");
+    }
+
+    let payload_pos = PRELUDE_LEN + headers_len;
+    return Ok(EventMessage {
+        headers: decode_headers(&bytes[PRELUDE_LEN..payload_pos])?,
+        payload: bytes[payload_pos..checksum_pos].to_vec(),
+    });
+}
+
+/// Decodes the binary `application/vnd.amazon.eventstream` framing AWS uses
+/// for streamed responses.
+///
+/// Like `StreamDecoder`, when the response times out the stream ends early, so
+/// backends can keep the partial response, and the timeout is returned from
+/// `finish`.
+pub struct EventStreamDecoder {
+    reader: Box<dyn AsyncRead + Unpin + Send>,
+    idle_timeout: Option<Duration>,
+    timeout_message: Option<String>,
+}
+
+impl EventStreamDecoder {
+    pub fn new(res: reqwest::Response) -> EventStreamDecoder {
+        let stream = Box::pin(res.bytes_stream().map_err(convert_err));
+        let mut decoder = EventStreamDecoder::from_reader(Box::new(StreamReader::new(stream)));
+
+        let idle_timeout_ms = Config::get(ConfigKey::StreamIdleTimeout)
+            .parse::<u64>()
+            .unwrap_or_default();
+        if idle_timeout_ms > 0 {
+            decoder.idle_timeout = Some(Duration::from_millis(idle_timeout_ms));
+        }
+
+        return decoder;
+    }
+
+    fn from_reader(reader: Box<dyn AsyncRead + Unpin + Send>) -> EventStreamDecoder {
+        return EventStreamDecoder {
+            reader,
+            idle_timeout: None,
+            timeout_message: None,
+        };
+    }
+
+    /// Reads the next raw message, or `None` if the stream ended between
+    /// messages.
+    async fn read_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut prelude = [0; PRELUDE_LEN];
+        let mut read = 0;
+        while read < PRELUDE_LEN {
+            let len = self.reader.read(&mut prelude[read..]).await?;
+            if len == 0 && read == 0 {
+                return Ok(None);
+            }
+            if len == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            read += len;
+        }
+
+        let total_len = read_u32(&prelude, 0) as usize;
+        if !(PRELUDE_LEN + CHECKSUM_LEN..=MAX_MESSAGE_LEN).contains(&total_len) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Event stream message has an invalid length",
+            ));
+        }
+
+        let mut message = prelude.to_vec();
+        message.resize(total_len, 0);
+        self.reader.read_exact(&mut message[PRELUDE_LEN..]).await?;
+
+        return Ok(Some(message));
+    }
+
+    /// Returns the next message, or `None` once the stream ends or times out.
+    pub async fn next(&mut self) -> Result<Option<EventMessage>> {
+        let message_res = match self.idle_timeout {
+            Some(idle_timeout) => tokio::time::timeout(idle_timeout, self.read_message()).await,
+            None => Ok(self.read_message().await),
+        };
+
+        let message = match message_res {
+            Ok(message) => message,
+            Err(_) => {
+                self.timeout_message = Some(format!(
+                    "The backend stopped responding for {:.1}s, so the response is incomplete.",
+                    self.idle_timeout.unwrap_or_default().as_secs_f32()
+                ));
+                return Ok(None);
+            }
+        };
+
+        return match message {
+            Ok(Some(bytes)) => Ok(Some(decode_message(&bytes)?)),
+            Ok(None) => Ok(None),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                self.timeout_message = Some(
+                    "The backend request timed out, so the response is incomplete.".to_string(),
+                );
+                Ok(None)
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                bail!("The event stream ended in the middle of a message")
+            }
+            Err(err) => Err(err.into()),
+        };
+    }
+
+    /// Returns an error if the stream ended because it timed out. Call it once
+    /// the partial response has been sent.
+    pub fn finish(&self) -> Result<()> {
+        if let Some(message) = &self.timeout_message {
+            bail!(message.to_string());
+        }
+
+        return Ok(());
+    }
+}
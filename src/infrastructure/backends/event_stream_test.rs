@@ -0,0 +1,158 @@
+use std::io;
+use std::io::Cursor;
+use std::time::Duration;
+
+use anyhow::Result;
+use test_utils::event_stream_message;
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::StreamReader;
+
+use super::EventMessage;
+use super::EventStreamDecoder;
+use super::decode_message;
+
+fn delta(text: &str) -> Vec<u8> {
+    return event_stream_message(
+        &[
+            (":message-type", "event"),
+            (":event-type", "contentBlockDelta"),
+        ],
+        &format!(r#"{{"delta":{{"text":"{text}"}}}}"#),
+    );
+}
+
+fn decoder(body: Vec<u8>) -> EventStreamDecoder {
+    return EventStreamDecoder::from_reader(Box::new(Cursor::new(body)));
+}
+
+async fn collect(decoder: &mut EventStreamDecoder) -> Result<Vec<EventMessage>> {
+    let mut messages = vec![];
+    while let Some(message) = decoder.next().await? {
+        messages.push(message);
+    }
+
+    return Ok(messages);
+}
+
+#[test]
+fn it_decodes_a_message() -> Result<()> {
+    let message = decode_message(&delta("Hello"))?;
+
+    assert_eq!(message.header(":event-type"), "contentBlockDelta");
+    assert_eq!(message.header(":message-type"), "event");
+    assert_eq!(message.header(":missing"), "");
+    assert_eq!(message.payload, br#"{"delta":{"text":"Hello"}}"#.to_vec());
+
+    return Ok(());
+}
+
+#[test]
+fn it_skips_headers_that_arent_strings() -> Result<()> {
+    let mut bytes = delta("Hello");
+    // Appends a boolean and an int header to the headers section, then fixes up
+    // the lengths and checksums.
+    let extra = [&[4u8][..], b"flag", &[0], &[5], b"count", &[4, 0, 0, 0, 9]].concat();
+    let headers_len = u32::from_be_bytes(bytes[4..8].try_into()?) as usize;
+    bytes.splice(12 + headers_len..12 + headers_len, extra.iter().copied());
+    let total_len = bytes.len() as u32;
+    bytes[0..4].copy_from_slice(&total_len.to_be_bytes());
+    bytes[4..8].copy_from_slice(&((headers_len + extra.len()) as u32).to_be_bytes());
+    let prelude_crc = crc32fast::hash(&bytes[..8]);
+    bytes[8..12].copy_from_slice(&prelude_crc.to_be_bytes());
+    let checksum_pos = bytes.len() - 4;
+    let crc = crc32fast::hash(&bytes[..checksum_pos]);
+    bytes[checksum_pos..].copy_from_slice(&crc.to_be_bytes());
+
+    let message = decode_message(&bytes)?;
+
+    assert_eq!(message.headers.len(), 2);
+    assert_eq!(message.header(":event-type"), "contentBlockDelta");
+
+    return Ok(());
+}
+
+#[test]
+fn it_rejects_invalid_checksums() {
+    let mut bytes = delta("Hello");
+    let last = bytes.len() - 5;
+    bytes[last] ^= 0xff;
+
+    assert_eq!(
+        decode_message(&bytes).unwrap_err().to_string(),
+        "Event stream message has an invalid checksum"
+    );
+
+    let mut bytes = delta("Hello");
+    bytes[8] ^= 0xff;
+
+    assert_eq!(
+        decode_message(&bytes).unwrap_err().to_string(),
+        "Event stream message has an invalid prelude checksum"
+    );
+}
+
+#[tokio::test]
+async fn it_decodes_a_stream_of_messages() -> Result<()> {
+    let mut decoder = decoder([delta("Hello"), delta(" World")].concat());
+
+    let messages = collect(&mut decoder).await?;
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(
+        messages[1].payload,
+        br#"{"delta":{"text":" World"}}"#.to_vec()
+    );
+    assert!(decoder.finish().is_ok());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_decodes_messages_split_across_chunks() -> Result<()> {
+    let body = [delta("Hello"), delta(" World")].concat();
+    let chunks = body
+        .chunks(7)
+        .map(|chunk| return Ok::<Cursor<Vec<u8>>, io::Error>(Cursor::new(chunk.to_vec())))
+        .collect::<Vec<io::Result<Cursor<Vec<u8>>>>>();
+    let reader = StreamReader::new(futures::stream::iter(chunks));
+    let mut decoder = EventStreamDecoder::from_reader(Box::new(reader));
+
+    assert_eq!(collect(&mut decoder).await?.len(), 2);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_on_truncated_messages() -> Result<()> {
+    let mut body = delta("Hello");
+    body.truncate(body.len() - 3);
+    let mut decoder = decoder(body);
+
+    assert_eq!(
+        decoder.next().await.unwrap_err().to_string(),
+        "The event stream ended in the middle of a message"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_when_idle() -> Result<()> {
+    let (mut writer, reader) = tokio::io::duplex(1024);
+    writer.write_all(&delta("Hello")).await?;
+
+    let mut decoder = EventStreamDecoder::from_reader(Box::new(reader));
+    decoder.idle_timeout = Some(Duration::from_millis(100));
+
+    assert!(decoder.next().await?.is_some());
+    assert_eq!(decoder.next().await?, None);
+    assert_eq!(
+        decoder.finish().unwrap_err().to_string(),
+        "The backend stopped responding for 0.1s, so the response is incomplete."
+    );
+
+    // The writer is held open until here so the stream stalls instead of ending.
+    drop(writer);
+
+    return Ok(());
+}
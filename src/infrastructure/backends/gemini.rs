@@ -7,12 +7,9 @@ use std::time::Duration;
 use anyhow::Result;
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::stream::TryStreamExt;
 use serde::Deserialize;
 use serde::Serialize;
-use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
-use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -22,17 +19,26 @@ use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
-
-fn convert_err(err: reqwest::Error) -> std::io::Error {
-    let err_msg = err.to_string();
-    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
-}
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::StreamDecoder;
+use crate::infrastructure::backends::stream::request_error;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Model {
     name: String,
     supported_generation_methods: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    input_token_limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_token_limit: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -47,42 +53,215 @@ struct ContentPartsBlob {
     data: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ContentPartsFunctionCall {
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ContentPartsFunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+/// A single part of a content. Each part sets one of the data fields, and
+/// thinking models may add a thought signature alongside it.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentParts {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<ContentPartsBlob>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_call: Option<ContentPartsFunctionCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_response: Option<ContentPartsFunctionResponse>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    thought_signature: String,
+}
+
+impl ContentParts {
+    fn text(text: &str) -> ContentParts {
+        return ContentParts {
+            text: Some(text.to_string()),
+            ..ContentParts::default()
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionDeclaration {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl FunctionDeclaration {
+    fn new(tool: ToolDefinition) -> FunctionDeclaration {
+        return FunctionDeclaration {
+            name: tool.name,
+            description: tool.description,
+            parameters: tool.parameters,
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-enum ContentParts {
-    Text(String),
-    InlineData(ContentPartsBlob),
+struct Tool {
+    function_declarations: Vec<FunctionDeclaration>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Content {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     role: String,
     parts: Vec<ContentParts>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct CompletionRequest {
     contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    generation_config: GenerationConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CandidateResponse {
+    #[serde(default)]
+    content: Option<Content>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadataResponse {
+    #[serde(default)]
+    prompt_token_count: Option<u64>,
+    #[serde(default)]
+    candidates_token_count: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct GenerateContentResponse {
-    text: String,
+    #[serde(default)]
+    candidates: Vec<CandidateResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage_metadata: Option<UsageMetadataResponse>,
+}
+
+/// Renders the transcript as Gemini contents. Tool calls and their results
+/// are sent as function call and function response parts.
+fn contents_from_transcript(transcript: &Transcript) -> Vec<Content> {
+    return transcript
+        .turns
+        .iter()
+        .map(|turn| {
+            if turn.role == Role::Assistant {
+                let mut parts = vec![];
+                if !turn.text.is_empty() || turn.tool_calls.is_empty() {
+                    parts.push(ContentParts::text(&turn.text));
+                }
+                parts.extend(turn.tool_calls.iter().map(|tool_call| {
+                    return ContentParts {
+                        function_call: Some(ContentPartsFunctionCall {
+                            name: tool_call.name.to_string(),
+                            args: tool_call.arguments.clone(),
+                        }),
+                        ..ContentParts::default()
+                    };
+                }));
+                // The signature goes back on the part it came with, which is
+                // the first function call, or the text when there are none.
+                let signed = parts
+                    .iter()
+                    .position(|part| return part.function_call.is_some())
+                    .unwrap_or(0);
+                if let Some(part) = parts.get_mut(signed) {
+                    part.thought_signature = turn.thought_signature.to_string();
+                }
+
+                return Content {
+                    role: "model".to_string(),
+                    parts,
+                };
+            }
+
+            let mut parts = vec![ContentParts::text(&turn.text)];
+            parts.extend(turn.images.iter().map(|image| {
+                return ContentParts {
+                    inline_data: Some(ContentPartsBlob {
+                        mime_type: image.media_type.to_string(),
+                        data: image.data.to_string(),
+                    }),
+                    ..ContentParts::default()
+                };
+            }));
+            if !turn.tool_results.is_empty() {
+                parts = turn
+                    .tool_results
+                    .iter()
+                    .map(|tool_result| {
+                        let mut key = "content";
+                        if tool_result.is_error {
+                            key = "error";
+                        }
+                        let mut response = serde_json::Map::new();
+                        response.insert(key.to_string(), tool_result.content.to_string().into());
+
+                        return ContentParts {
+                            function_response: Some(ContentPartsFunctionResponse {
+                                name: tool_result.name.to_string(),
+                                response: response.into(),
+                            }),
+                            ..ContentParts::default()
+                        };
+                    })
+                    .collect();
+            }
+
+            return Content {
+                role: "user".to_string(),
+                parts,
+            };
+        })
+        .collect();
 }
 
 pub struct Gemini {
     url: String,
     token: String,
     timeout: String,
+    client: reqwest::Client,
 }
 
-impl Default for Gemini {
-    fn default() -> Gemini {
+impl Gemini {
+    pub fn new(client: reqwest::Client) -> Gemini {
         return Gemini {
             url: "https://generativelanguage.googleapis.com".to_string(),
             token: Config::get(ConfigKey::GeminiToken),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
         };
     }
 }
@@ -109,7 +288,8 @@ impl Backend for Gemini {
             key = self.token
         );
 
-        let res = reqwest::Client::new()
+        let res = self
+            .client
             .get(&url)
             .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
             .send()
@@ -130,8 +310,9 @@ impl Backend for Gemini {
     }
 
     #[allow(clippy::implicit_return)]
-    async fn list_models(&self) -> Result<Vec<String>> {
-        let res = reqwest::Client::new()
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .client
             .get(format!(
                 "{url}/v1beta/models?key={key}",
                 url = self.url,
@@ -142,7 +323,7 @@ impl Backend for Gemini {
             .json::<ModelListResponse>()
             .await?;
 
-        let mut models: Vec<String> = res
+        let mut models: Vec<ModelInfo> = res
             .models
             .iter()
             .filter(|model| {
@@ -151,7 +332,11 @@ impl Backend for Gemini {
                     .contains(&"generateContent".to_string())
             })
             .map(|model| {
-                return model.name.to_string();
+                return ModelInfo {
+                    input_token_limit: model.input_token_limit,
+                    output_token_limit: model.output_token_limit,
+                    ..ModelInfo::new(&model.name)
+                };
             })
             .collect();
 
@@ -166,83 +351,134 @@ impl Backend for Gemini {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
-        let mut contents: Vec<Content> = vec![];
-        if !prompt.backend_context.is_empty() {
-            contents = serde_json::from_str(&prompt.backend_context)?;
+        let mut transcript = prompt.to_transcript();
+
+        let mut system_instruction = None;
+        if !prompt.system_prompt.is_empty() {
+            system_instruction = Some(Content {
+                role: String::new(),
+                parts: vec![ContentParts::text(&prompt.system_prompt)],
+            });
         }
-        contents.push(Content {
-            role: "user".to_string(),
-            parts: vec![ContentParts::Text(prompt.text)],
-        });
 
-        let req = CompletionRequest {
-            contents: contents.clone(),
+        let mut req = CompletionRequest {
+            contents: contents_from_transcript(&transcript),
+            system_instruction,
+            generation_config: GenerationConfig {
+                temperature: prompt.params.temperature,
+                max_output_tokens: prompt.params.max_tokens,
+                top_p: prompt.params.top_p,
+                stop_sequences: prompt.params.stop,
+            },
+            tools: vec![],
         };
+        if !prompt.tools.is_empty() {
+            req.tools = vec![Tool {
+                function_declarations: prompt
+                    .tools
+                    .into_iter()
+                    .map(FunctionDeclaration::new)
+                    .collect(),
+            }];
+        }
 
-        let res = reqwest::Client::new()
+        let req_builder = self
+            .client
             .post(format!(
-                "{url}/v1beta/{model}:streamGenerateContent?key={key}",
+                "{url}/v1beta/{model}:streamGenerateContent?alt=sse&key={key}",
                 url = self.url,
-                model = Config::get(ConfigKey::Model),
+                model = prompt.model,
                 key = self.token,
             ))
-            .json(&req)
-            .send()
-            .await?;
+            .json(&req);
+        let res = retry::send(req_builder, tx).await?;
 
         if !res.status().is_success() {
-            tracing::error!(
-                status = res.status().as_u16(),
-                "Failed to make completion request to Gemini"
-            );
-            bail!(format!(
-                "Failed to make completion request to Gemini, {}",
-                res.status().as_u16()
-            ));
+            return Err(request_error("Gemini", res).await);
         }
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+        let mut decoder = StreamDecoder::new(res);
 
         let mut last_message = String::new();
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
+        let mut usage = Usage::default();
+        let mut function_calls: Vec<ContentPartsFunctionCall> = vec![];
+        let mut thought_signature = String::new();
+        while let Some(ores) = decoder.next::<GenerateContentResponse>().await? {
+            tracing::debug!(body = ?ores, "Completion response");
+
+            // Every chunk carries the running usageMetadata totals, so the last
+            // values seen are the totals for the whole response.
+            if let Some(usage_metadata) = ores.usage_metadata {
+                usage.prompt_tokens = usage_metadata.prompt_token_count.or(usage.prompt_tokens);
+                usage.completion_tokens = usage_metadata
+                    .candidates_token_count
+                    .or(usage.completion_tokens);
             }
 
-            let cleaned_line = line.unwrap().trim().to_string();
-            if !cleaned_line.starts_with("\"text\":") {
+            let text = ores
+                .candidates
+                .into_iter()
+                .take(1)
+                .filter_map(|candidate| return candidate.content)
+                .flat_map(|content| return content.parts)
+                .filter_map(|part| {
+                    // Only the first function call's signature is kept, as
+                    // that's the one Gemini requires back.
+                    if !part.thought_signature.is_empty()
+                        && (thought_signature.is_empty() || function_calls.is_empty())
+                    {
+                        thought_signature = part.thought_signature;
+                    }
+                    if let Some(function_call) = part.function_call {
+                        function_calls.push(function_call);
+                    }
+                    return part.text;
+                })
+                .collect::<String>();
+            if text.is_empty() {
                 continue;
             }
 
-            let ores: GenerateContentResponse =
-                serde_json::from_str(&format!("{{ {cleaned_line} }}")).unwrap();
-
-            if ores.text.is_empty() || ores.text.is_empty() || ores.text == "\n" {
-                break;
-            }
-
-            last_message += &ores.text;
+            last_message += &text;
             let msg = BackendResponse {
                 author: Author::Model,
-                text: ores.text,
+                text,
+                thinking: String::new(),
                 done: false,
-                context: None,
+                transcript: None,
+                usage: None,
+                tool_calls: vec![],
             };
             tx.send(Event::BackendPromptResponse(msg))?;
         }
 
-        contents.push(Content {
-            role: "model".to_string(),
-            parts: vec![ContentParts::Text(last_message.clone())],
-        });
+        // Gemini doesn't give function calls an ID, so the name is used.
+        let tool_calls = function_calls
+            .into_iter()
+            .map(|function_call| {
+                return ToolCall {
+                    id: function_call.name.to_string(),
+                    name: function_call.name,
+                    arguments: function_call.args,
+                };
+            })
+            .collect::<Vec<ToolCall>>();
+
+        let mut turn = Turn::new(Role::Assistant, &last_message);
+        turn.tool_calls = tool_calls.clone();
+        turn.thought_signature = thought_signature;
+        transcript.push(turn);
 
         let msg = BackendResponse {
             author: Author::Model,
             text: String::new(),
+            thinking: String::new(),
             done: true,
-            context: Some(serde_json::to_string(&contents)?),
+            transcript: Some(transcript),
+            usage: Some(usage),
+            tool_calls,
         };
         tx.send(Event::BackendPromptResponse(msg))?;
+        decoder.finish()?;
 
         return Ok(());
     }
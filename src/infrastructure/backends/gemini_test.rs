@@ -1,11 +1,10 @@
 use anyhow::Result;
 use anyhow::bail;
+use serde_json::json;
 use test_utils::insta_snapshot;
 use tokio::sync::mpsc;
 
 use super::Config;
-use super::Content;
-use super::ContentParts;
 use super::Gemini;
 use super::Model;
 use super::ModelListResponse;
@@ -15,6 +14,17 @@ use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::ToolResult;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
 
 impl Gemini {
     fn with_url(url: String) -> Gemini {
@@ -22,6 +32,7 @@ impl Gemini {
             url,
             token: "abc".to_string(),
             timeout: "200".to_string(),
+            client: reqwest::Client::new(),
         };
     }
 }
@@ -66,6 +77,7 @@ async fn it_successfully_health_checks_with_official_api() {
         url: "https://generativelanguage.googleapis.com".to_string(),
         token,
         timeout: "500".to_string(),
+        client: reqwest::Client::new(),
     };
 
     let res = backend.health_check().await;
@@ -96,10 +108,18 @@ async fn it_lists_models() -> Result<()> {
             Model {
                 name: "first".to_string(),
                 supported_generation_methods: vec!["generateContent".to_string()],
+                input_token_limit: Some(30720),
+                output_token_limit: Some(2048),
             },
             Model {
                 name: "second".to_string(),
                 supported_generation_methods: vec!["generateContent".to_string()],
+                ..Model::default()
+            },
+            Model {
+                name: "embedding".to_string(),
+                supported_generation_methods: vec!["embedContent".to_string()],
+                ..Model::default()
             },
         ],
     })?;
@@ -116,52 +136,46 @@ async fn it_lists_models() -> Result<()> {
     let res = backend.list_models().await?;
     mock.assert();
 
-    assert_eq!(res, vec!["first".to_string(), "second".to_string()]);
+    assert_eq!(res, vec![
+        ModelInfo {
+            input_token_limit: Some(30720),
+            output_token_limit: Some(2048),
+            ..ModelInfo::new("first")
+        },
+        ModelInfo::new("second"),
+    ]);
 
     return Ok(());
 }
 
 #[tokio::test]
 async fn it_gets_completions() -> Result<()> {
-    Config::set(ConfigKey::Model, "model-1");
-    let body = r#"
-{
-  "contents": [
-    {
-      "parts": [
-        {
-          "text": "Hello "
-        }
-      ]
-    },
-    {
-      "parts": [
-        {
-          "text": "World"
-        }
-      ]
-    },
-    {
-      "parts": [
-        {
-          "text": ""
-        }
-      ]
-    }
-  ]
-}
-        "#;
+    let body = [
+        r#"data: {"candidates": [{"content": {"parts": [{"text": "Hello "}],"role": "model"}}]}"#,
+        r#"data: {"candidates": [{"content": {"parts": [{"text": "World"}],"role": "model"}}]}"#,
+        r#"data: {"candidates": [{"content": {"parts": [{"text": ""}],"role": "model"}}]}"#,
+    ]
+    .join("\n\n");
     let prompt = BackendPrompt {
         text: "Say hi to the world".to_string(),
-        backend_context: serde_json::to_string(&vec![Content {
-            role: "model".to_string(),
-            parts: vec![ContentParts::Text("Hello".to_string())],
-        }])?,
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "Hello")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
     };
 
     let mut server = mockito::Server::new_async().await;
     let mock = server
-        .mock("POST", "/v1beta/model-1:streamGenerateContent?key=abc")
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
         .with_status(200)
         .with_body(body)
         .create_async()
@@ -181,19 +195,388 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(first_recv.author, Author::Model);
     assert_eq!(first_recv.text, "Hello ".to_string());
     assert!(!first_recv.done);
-    assert_eq!(first_recv.context, None);
+    assert_eq!(first_recv.transcript, None);
 
     assert_eq!(second_recv.author, Author::Model);
     assert_eq!(second_recv.text, "World".to_string());
     assert!(!second_recv.done);
-    assert_eq!(second_recv.context, None);
+    assert_eq!(second_recv.transcript, None);
 
     assert_eq!(third_recv.author, Author::Model);
     assert_eq!(third_recv.text, "".to_string());
     assert!(third_recv.done);
     insta_snapshot(|| {
-        insta::assert_toml_snapshot!(third_recv.context);
+        insta::assert_toml_snapshot!(third_recv.transcript);
     });
 
     return Ok(());
 }
+
+#[tokio::test]
+async fn it_gets_completions_with_system_prompt() -> Result<()> {
+    let body = r#"data: {"candidates": [{"content": {"parts": [{"text": ""}]}}]}"#;
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "You are a pirate".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"systemInstruction":{"parts":[{"text":"You are a pirate"}]}}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_images() -> Result<()> {
+    let body = r#"data: {"candidates": [{"content": {"parts": [{"text": ""}]}}]}"#;
+    let prompt = BackendPrompt {
+        text: "What is this?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![ImageAttachment {
+            name: "diagram.png".to_string(),
+            media_type: "image/png".to_string(),
+            data: "aGVsbG8=".to_string(),
+        }],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"contents":[{"role":"user","parts":[{"text":"What is this?"},{"inlineData":{"mimeType":"image/png","data":"aGVsbG8="}}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_usage() -> Result<()> {
+    let body = [
+        r#"data: {"candidates": [{"content": {"parts": [{"text": "Hello"}],"role": "model"}}],"usageMetadata": {"promptTokenCount": 8,"candidatesTokenCount": 1,"totalTokenCount": 9}}"#,
+        r#"data: {"candidates": [{"content": {"parts": [{"text": " World"}],"role": "model"}}],"usageMetadata": {"promptTokenCount": 8,"candidatesTokenCount": 2,"totalTokenCount": 10}}"#,
+    ]
+    .join("\n\n");
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello".to_string());
+    assert_eq!(second_recv.text, " World".to_string());
+    assert!(third_recv.done);
+    assert_eq!(
+        third_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(8),
+            completion_tokens: Some(2),
+            ..Usage::default()
+        })
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_calls() -> Result<()> {
+    let body = r#"data: {"candidates": [{"content": {"parts": [{"functionCall": {"name": "read_file", "args": {"path": "README.md"}}}]}}]}"#;
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Reads a file.".to_string(),
+            parameters: json!({"type": "object"}),
+        }],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"tools":[{"functionDeclarations":[{"name":"read_file","description":"Reads a file.","parameters":{"type":"object"}}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert_eq!(recv.tool_calls, vec![ToolCall {
+        id: "read_file".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }]);
+    assert_eq!(
+        recv.transcript.unwrap().turns[1].tool_calls,
+        recv.tool_calls
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_signed_tool_calls() -> Result<()> {
+    let body = [
+        r#"data: {"candidates": [{"content": {"role": "model", "parts": [{"text": "Let me check."}]}}]}"#,
+        r#"data: {"candidates": [{"content": {"role": "model", "parts": [{"functionCall": {"name": "read_file", "args": {"path": "README.md"}}, "thoughtSignature": "c2lnLTE="}, {"functionCall": {"name": "list_files", "args": {}}}]}}]}"#,
+    ]
+    .join("\n\n");
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert_eq!(recv.text, "Let me check.");
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert_eq!(recv.tool_calls.len(), 2);
+    let turn = &recv.transcript.unwrap().turns[1];
+    assert_eq!(turn.text, "Let me check.");
+    assert_eq!(turn.tool_calls, recv.tool_calls);
+    assert_eq!(turn.thought_signature, "c2lnLTE=");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_back_thought_signatures() -> Result<()> {
+    let body = r#"data: {"candidates": [{"content": {"parts": [{"text": "It says hello."}]}}]}"#;
+    let mut turn = Turn::new(Role::Assistant, "");
+    turn.tool_calls = vec![ToolCall {
+        id: "read_file".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }];
+    turn.thought_signature = "c2lnLTE=".to_string();
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::User, "What's in the readme?"), turn],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "read_file".to_string(),
+            name: "read_file".to_string(),
+            content: "# Hello".to_string(),
+            is_error: false,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"contents":[{"role":"user","parts":[{"text":"What's in the readme?"}]},{"role":"model","parts":[{"functionCall":{"name":"read_file","args":{"path":"README.md"}},"thoughtSignature":"c2lnLTE="}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert_eq!(recv.text, "It says hello.");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_results() -> Result<()> {
+    let body = r#"data: {"candidates": [{"content": {"parts": [{"text": "It says hello."}]}}]}"#;
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "read_file".to_string(),
+            name: "read_file".to_string(),
+            content: "# Hello".to_string(),
+            is_error: false,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock(
+            "POST",
+            "/v1beta/model-1:streamGenerateContent?alt=sse&key=abc",
+        )
+        .match_body(mockito::Matcher::PartialJsonString(
+            r##"{"contents":[{"role":"user","parts":[{"functionResponse":{"name":"read_file","response":{"content":"# Hello"}}}]}]}"##.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert_eq!(recv.text, "It says hello.");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_the_provider_error() -> Result<()> {
+    let mut prompt = BackendPrompt::new("Say hi".to_string(), Transcript::default());
+    prompt.model = "model-1".to_string();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1beta/model-1:streamGenerateContent")
+        .match_query(mockito::Matcher::Any)
+        .with_status(400)
+        .with_body(r#"[{"error":{"code":400,"message":"API key not valid. Please pass a valid API key.","status":"INVALID_ARGUMENT"}}]"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Gemini::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Gemini. API key not valid. Please pass a valid API key."
+    );
+
+    return Ok(());
+}
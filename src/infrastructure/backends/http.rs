@@ -0,0 +1,106 @@
+#[cfg(test)]
+#[path = "http_test.rs"]
+mod tests;
+
+use std::fs;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use reqwest::Certificate;
+use reqwest::Client;
+use reqwest::Proxy;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
+/// Settings for the HTTP client shared by every backend.
+#[derive(Default)]
+pub struct ClientSettings {
+    pub proxy: String,
+    pub ca_bundle: String,
+    pub headers: String,
+    /// Limits connecting to a backend. Waiting for a response is limited
+    /// separately when requests are sent, so streamed responses and model
+    /// downloads aren't cut off partway.
+    pub connect_timeout: Option<Duration>,
+}
+
+impl ClientSettings {
+    pub fn from_config() -> Result<ClientSettings> {
+        let timeout_ms = Config::get(ConfigKey::RequestTimeout)
+            .parse::<u64>()
+            .map_err(|_| {
+                return anyhow!(
+                    "Invalid value for request-timeout, expected a number of milliseconds"
+                );
+            })?;
+
+        return Ok(ClientSettings {
+            proxy: Config::get(ConfigKey::HttpProxy),
+            ca_bundle: Config::get(ConfigKey::HttpCaBundle),
+            headers: Config::get(ConfigKey::HttpHeaders),
+            connect_timeout: (timeout_ms > 0).then(|| return Duration::from_millis(timeout_ms)),
+        });
+    }
+
+    pub fn build(&self) -> Result<Client> {
+        let mut builder = Client::builder().default_headers(parse_headers(&self.headers)?);
+
+        if !self.proxy.is_empty() {
+            let proxy = Proxy::all(&self.proxy).map_err(|err| {
+                return anyhow!("Invalid value for http-proxy '{}': {err}", self.proxy);
+            })?;
+            builder = builder.proxy(proxy);
+        }
+
+        if !self.ca_bundle.is_empty() {
+            let pem = fs::read(&self.ca_bundle).map_err(|err| {
+                return anyhow!("Failed to read http-ca-bundle '{}': {err}", self.ca_bundle);
+            })?;
+            let certs = Certificate::from_pem_bundle(&pem).map_err(|err| {
+                return anyhow!(
+                    "Invalid certificates in http-ca-bundle '{}': {err}",
+                    self.ca_bundle
+                );
+            })?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        return Ok(builder.build()?);
+    }
+}
+
+/// Parses comma separated `Name: value` pairs into a header map.
+fn parse_headers(headers: &str) -> Result<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+
+    for pair in headers.split(',').filter(|e| return !e.trim().is_empty()) {
+        let (name, value) = pair.split_once(':').ok_or_else(|| {
+            return anyhow!("Invalid value for http-headers '{pair}', expected 'Name: value'");
+        })?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| {
+            return anyhow!("Invalid header name in http-headers '{}'", name.trim());
+        })?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| {
+            return anyhow!("Invalid header value in http-headers for '{name}'");
+        })?;
+        header_map.insert(name, value);
+    }
+
+    return Ok(header_map);
+}
+
+/// Builds the HTTP client shared by every backend from the config.
+pub fn build_client() -> Result<Client> {
+    return ClientSettings::from_config()?.build();
+}
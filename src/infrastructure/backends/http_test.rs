@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use super::ClientSettings;
+use super::parse_headers;
+
+#[test]
+fn it_parses_headers() -> Result<()> {
+    let headers = parse_headers("X-Gateway-Key: abc=, X-Team:ml")?;
+
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers.get("x-gateway-key").unwrap(), "abc=");
+    assert_eq!(headers.get("x-team").unwrap(), "ml");
+
+    return Ok(());
+}
+
+#[test]
+fn it_parses_empty_headers() -> Result<()> {
+    assert!(parse_headers("")?.is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_parse_headers_without_separator() {
+    let res = parse_headers("X-Gateway-Key abc");
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid value for http-headers 'X-Gateway-Key abc', expected 'Name: value'"
+    );
+}
+
+#[test]
+fn it_fails_to_build_with_invalid_proxy() {
+    let settings = ClientSettings {
+        proxy: "not a url".to_string(),
+        ..ClientSettings::default()
+    };
+
+    let res = settings.build();
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .starts_with("Invalid value for http-proxy 'not a url'")
+    );
+}
+
+#[test]
+fn it_fails_to_build_with_missing_ca_bundle() {
+    let settings = ClientSettings {
+        ca_bundle: "/does/not/exist.pem".to_string(),
+        ..ClientSettings::default()
+    };
+
+    let res = settings.build();
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .starts_with("Failed to read http-ca-bundle '/does/not/exist.pem'")
+    );
+}
+
+#[tokio::test]
+async fn it_sends_extra_headers() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/")
+        .match_header("X-Gateway-Key", "abc")
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let client = ClientSettings {
+        headers: "X-Gateway-Key: abc".to_string(),
+        connect_timeout: Some(Duration::from_secs(5)),
+        ..ClientSettings::default()
+    }
+    .build()?;
+    let res = client.get(server.url()).send().await?;
+
+    mock.assert();
+    assert_eq!(res.status().as_u16(), 200);
+
+    return Ok(());
+}
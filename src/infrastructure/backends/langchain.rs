@@ -9,13 +9,10 @@ use anyhow::Result;
 use anyhow::anyhow;
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::stream::TryStreamExt;
 use itertools::Itertools;
 use serde::Deserialize;
 use serde::Serialize;
-use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
-use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -25,11 +22,12 @@ use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
-
-fn convert_err(err: reqwest::Error) -> std::io::Error {
-    let err_msg = err.to_string();
-    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
-}
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::Turn;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::StreamDecoder;
+use crate::infrastructure::backends::stream::request_error;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Empty {}
@@ -54,13 +52,15 @@ struct CompletionResponse {
 pub struct LangChain {
     url: String,
     timeout: String,
+    client: reqwest::Client,
 }
 
-impl Default for LangChain {
-    fn default() -> LangChain {
+impl LangChain {
+    pub fn new(client: reqwest::Client) -> LangChain {
         return LangChain {
             url: Config::get(ConfigKey::LangChainURL),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
         };
     }
 }
@@ -77,7 +77,8 @@ impl Backend for LangChain {
             bail!("LangChain URL is not defined");
         }
 
-        let res = reqwest::Client::new()
+        let res = self
+            .client
             .get(format!("{url}/openapi.json", url = self.url))
             .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
             .send()
@@ -98,8 +99,9 @@ impl Backend for LangChain {
     }
 
     #[allow(clippy::implicit_return)]
-    async fn list_models(&self) -> Result<Vec<String>> {
-        let res = reqwest::Client::new()
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .client
             .get(format!("{url}/openapi.json", url = self.url))
             .send()
             .await?
@@ -122,7 +124,10 @@ impl Backend for LangChain {
 
         models.sort();
 
-        return Ok(models);
+        return Ok(models
+            .iter()
+            .map(|model| return ModelInfo::new(model))
+            .collect());
     }
 
     #[allow(clippy::implicit_return)]
@@ -131,47 +136,42 @@ impl Backend for LangChain {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
+        if !prompt.images.is_empty() {
+            bail!("The LangChain backend doesn't support images");
+        }
+
+        // LangChain doesn't take the history, but the transcript is still kept so
+        // the conversation can carry on with another backend.
+        let mut transcript = prompt.to_transcript();
+
         let mut input = HashMap::new();
         // TODO consider making the key configurable.
         input.insert("question".to_string(), prompt.text);
 
         let req = CompletionRequest { input };
 
-        let res = reqwest::Client::new()
+        let req_builder = self
+            .client
             .post(format!(
                 "{url}/{model}/stream",
                 url = self.url,
-                model = Config::get(ConfigKey::Model)
+                model = prompt.model
             ))
-            .json(&req)
-            .send()
-            .await?;
+            .json(&req);
+        let res = retry::send(req_builder, tx).await?;
 
         if !res.status().is_success() {
-            tracing::error!(
-                status = res.status().as_u16(),
-                "Failed to make completion request to LangChain"
-            );
-            bail!("Failed to make completion request to LangChain");
+            return Err(request_error("LangChain", res).await);
         }
 
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+        let mut decoder = StreamDecoder::new(res);
 
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
-            }
-            let mut cleaned_line = line.unwrap().trim().to_string();
-            if !cleaned_line.starts_with("data:") {
-                continue;
-            }
-            cleaned_line = cleaned_line.split_off(5).trim().to_string();
-            let ores: CompletionResponse = serde_json::from_str(&cleaned_line).unwrap();
+        let mut last_message = String::new();
 
+        while let Some(ores) = decoder.next::<CompletionResponse>().await? {
             if let Some(status_code) = ores.status_code {
                 if status_code >= 400 {
-                    return Err(anyhow!(ores.message.unwrap()));
+                    return Err(anyhow!(ores.message.unwrap_or_default()));
                 }
             }
 
@@ -183,22 +183,31 @@ impl Backend for LangChain {
                 continue;
             }
 
+            last_message += &text;
             let msg = BackendResponse {
                 author: Author::Model,
                 text,
+                thinking: String::new(),
                 done: false,
-                context: None,
+                transcript: None,
+                usage: None,
+                tool_calls: vec![],
             };
             tx.send(Event::BackendPromptResponse(msg))?;
         }
 
+        transcript.push(Turn::new(Role::Assistant, &last_message));
         let msg = BackendResponse {
             author: Author::Model,
             text: String::new(),
+            thinking: String::new(),
             done: true,
-            context: Some("not-supported".to_string()),
+            transcript: Some(transcript),
+            usage: None,
+            tool_calls: vec![],
         };
         tx.send(Event::BackendPromptResponse(msg))?;
+        decoder.finish()?;
 
         return Ok(());
     }
@@ -6,13 +6,14 @@ use tokio::sync::mpsc;
 
 use super::CompletionResponse;
 use super::LangChain;
-use crate::configuration::Config;
-use crate::configuration::ConfigKey;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Transcript;
 use crate::infrastructure::backends::langchain::Empty;
 use crate::infrastructure::backends::langchain::OpenAPIJSONResponse;
 
@@ -21,6 +22,7 @@ impl LangChain {
         return LangChain {
             url,
             timeout: "200".to_string(),
+            client: reqwest::Client::new(),
         };
     }
 }
@@ -87,15 +89,16 @@ async fn it_lists_models() -> Result<()> {
     let res = backend.list_models().await?;
     mock.assert();
 
-    assert_eq!(res, vec!["model-1".to_string(), "model-2".to_string()]);
+    assert_eq!(res, vec![
+        ModelInfo::new("model-1"),
+        ModelInfo::new("model-2")
+    ]);
 
     return Ok(());
 }
 
 #[tokio::test]
 async fn it_gets_completions() -> Result<()> {
-    Config::set(ConfigKey::Model, "model-1");
-
     let first_line = serde_json::to_string(&CompletionResponse {
         status_code: None,
         message: None,
@@ -119,7 +122,13 @@ async fn it_gets_completions() -> Result<()> {
     .join("\n");
     let prompt = BackendPrompt {
         text: "Say hi to the world".to_string(),
-        backend_context: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
     };
 
     let mut server = mockito::Server::new_async().await;
@@ -144,17 +153,17 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(first_recv.author, Author::Model);
     assert_eq!(first_recv.text, "Hello ".to_string());
     assert!(!first_recv.done);
-    assert_eq!(first_recv.context, None);
+    assert_eq!(first_recv.transcript, None);
 
     assert_eq!(second_recv.author, Author::Model);
     assert_eq!(second_recv.text, "World".to_string());
     assert!(!second_recv.done);
-    assert_eq!(second_recv.context, None);
+    assert_eq!(second_recv.transcript, None);
 
     assert_eq!(third_recv.author, Author::Model);
     assert!(third_recv.text.is_empty());
     assert!(third_recv.done);
-    assert_eq!(third_recv.context, Some("not-supported".to_string()));
+    assert_eq!(third_recv.transcript.unwrap().turns.len(), 2);
 
     return Ok(());
 }
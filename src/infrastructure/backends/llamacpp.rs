@@ -0,0 +1,190 @@
+#[cfg(test)]
+#[path = "llamacpp_test.rs"]
+mod tests;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::configuration::Provider;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+use crate::domain::models::ModelInfo;
+use crate::infrastructure::backends::openai::OpenAI;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n_params: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n_ctx_train: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Model {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    meta: Option<ModelMeta>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelListResponse {
+    data: Vec<Model>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct GenerationSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    n_ctx: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PropsResponse {
+    #[serde(default)]
+    default_generation_settings: GenerationSettings,
+}
+
+/// Formats a parameter count the way Ollama reports it, such as `8.0B`.
+fn format_params(n_params: u64) -> String {
+    let n_params = n_params as f64;
+    if n_params >= 1_000_000_000.0 {
+        return format!("{:.1}B", n_params / 1_000_000_000.0);
+    }
+
+    return format!("{:.0}M", n_params / 1_000_000.0);
+}
+
+/// The llama.cpp server, `llama-server`, which serves a single model.
+/// Completions go through its OpenAI compatible chat endpoint.
+pub struct LlamaCpp {
+    url: String,
+    timeout: String,
+    client: reqwest::Client,
+    chat: OpenAI,
+}
+
+impl LlamaCpp {
+    pub fn new(client: reqwest::Client) -> LlamaCpp {
+        return LlamaCpp::from_url(Config::get(ConfigKey::LlamacppURL), client);
+    }
+
+    fn from_url(url: String, client: reqwest::Client) -> LlamaCpp {
+        let provider = Provider {
+            name: "llama.cpp".to_string(),
+            url: url.to_string(),
+            token: String::new(),
+            headers: HashMap::new(),
+            models: vec![],
+        };
+
+        return LlamaCpp {
+            chat: OpenAI::from_provider(&provider, client.clone()),
+            url,
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+        };
+    }
+
+    /// Returns the context size the server was started with, which is usually
+    /// smaller than what the model was trained with.
+    async fn context_length(&self) -> Option<u64> {
+        let res = self
+            .client
+            .get(format!("{url}/props", url = self.url))
+            .send()
+            .await
+            .ok()?
+            .json::<PropsResponse>()
+            .await
+            .ok()?;
+
+        return res.default_generation_settings.n_ctx;
+    }
+}
+
+#[async_trait]
+impl Backend for LlamaCpp {
+    fn name(&self) -> BackendName {
+        return BackendName::LlamaCpp;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.url.is_empty() {
+            bail!("llama.cpp URL is not defined");
+        }
+
+        let res = match self
+            .client
+            .get(format!("{url}/health", url = self.url))
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!(error = ?err, "llama.cpp is not running");
+                bail!("llama.cpp is not running");
+            }
+        };
+
+        let status = res.status().as_u16();
+        if status == 503 {
+            bail!("llama.cpp is still loading the model");
+        }
+        if status != 200 {
+            tracing::error!(status = status, "llama.cpp health check failed");
+            bail!("llama.cpp health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .client
+            .get(format!("{url}/v1/models", url = self.url))
+            .send()
+            .await?
+            .json::<ModelListResponse>()
+            .await?;
+
+        let context_length = self.context_length().await;
+        let mut models = res
+            .data
+            .iter()
+            .map(|model| {
+                let meta = model.meta.clone().unwrap_or_default();
+                return ModelInfo {
+                    parameter_size: meta.n_params.map(format_params),
+                    context_length: context_length.or(meta.n_ctx_train),
+                    ..ModelInfo::new(&model.id)
+                };
+            })
+            .collect::<Vec<ModelInfo>>();
+
+        models.sort();
+
+        return Ok(models);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        return self.chat.get_completion(prompt, tx).await;
+    }
+}
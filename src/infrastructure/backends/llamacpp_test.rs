@@ -0,0 +1,230 @@
+use anyhow::Result;
+use anyhow::bail;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use super::LlamaCpp;
+use super::Model;
+use super::ModelListResponse;
+use super::ModelMeta;
+use super::format_params;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+
+impl LlamaCpp {
+    fn with_url(url: String) -> LlamaCpp {
+        let mut backend = LlamaCpp::from_url(url, reqwest::Client::new());
+        backend.timeout = "200".to_string();
+
+        return backend;
+    }
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+fn model_list_body() -> Result<String> {
+    return Ok(serde_json::to_string(&ModelListResponse {
+        data: vec![Model {
+            id: "qwen2.5-7b-instruct-q4_k_m.gguf".to_string(),
+            meta: Some(ModelMeta {
+                n_params: Some(7_615_616_512),
+                n_ctx_train: Some(32768),
+            }),
+        }],
+    })?);
+}
+
+#[test]
+fn it_formats_params() {
+    assert_eq!(format_params(7_615_616_512), "7.6B");
+    assert_eq!(format_params(135_000_000), "135M");
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/health")
+        .with_status(200)
+        .with_body(json!({"status": "ok"}).to_string())
+        .create_async()
+        .await;
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_while_loading() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/health")
+        .with_status(503)
+        .with_body(json!({"error": {"code": 503, "message": "Loading model"}}).to_string())
+        .create_async()
+        .await;
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "llama.cpp is still loading the model"
+    );
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/health")
+        .with_status(500)
+        .create_async()
+        .await;
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "llama.cpp health check failed"
+    );
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_lists_models() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .with_status(200)
+        .with_body(model_list_body()?)
+        .create_async()
+        .await;
+    let props_mock = server
+        .mock("GET", "/props")
+        .with_status(200)
+        .with_body(json!({"default_generation_settings": {"n_ctx": 4096}}).to_string())
+        .create_async()
+        .await;
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.list_models().await?;
+
+    assert_eq!(res, vec![ModelInfo {
+        parameter_size: Some("7.6B".to_string()),
+        context_length: Some(4096),
+        ..ModelInfo::new("qwen2.5-7b-instruct-q4_k_m.gguf")
+    }]);
+    mock.assert();
+    props_mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_models_with_trained_context_without_props() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .with_status(200)
+        .with_body(model_list_body()?)
+        .create_async()
+        .await;
+    let props_mock = server
+        .mock("GET", "/props")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.list_models().await?;
+
+    assert_eq!(res[0].context_length, Some(32768));
+    mock.assert();
+    props_mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let body = [
+        json!({"choices": [{"delta": {"content": "Hello "}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {"content": "World"}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {}, "finish_reason": "stop"}]}),
+    ]
+    .iter()
+    .map(|line| return format!("data: {line}\n\n"))
+    .collect::<String>();
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "qwen2.5-7b-instruct-q4_k_m.gguf".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_header("Authorization", mockito::Matcher::Missing)
+        .match_body(mockito::Matcher::PartialJsonString(
+            json!({"model": "qwen2.5-7b-instruct-q4_k_m.gguf", "stream": true}).to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = LlamaCpp::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert!(third_recv.done);
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::Assistant, "How may I help you?"),
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
@@ -0,0 +1,220 @@
+#[cfg(test)]
+#[path = "mistral_test.rs"]
+mod tests;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+use crate::domain::models::FimPrompt;
+use crate::domain::models::ModelInfo;
+use crate::infrastructure::backends::openai;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::request_error;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelCapabilities {
+    #[serde(default)]
+    completion_chat: bool,
+    #[serde(default)]
+    completion_fim: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Model {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owned_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_context_length: Option<u64>,
+    #[serde(default)]
+    capabilities: ModelCapabilities,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelListResponse {
+    data: Vec<Model>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FimRequest {
+    model: String,
+    prompt: String,
+    suffix: String,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FimMessageResponse {
+    content: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FimChoiceResponse {
+    message: FimMessageResponse,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FimResponse {
+    choices: Vec<FimChoiceResponse>,
+}
+
+/// Mistral's La Plateforme. Chat completions use the OpenAI format, and its
+/// code models can also fill in the middle between a prefix and a suffix.
+pub struct Mistral {
+    url: String,
+    token: String,
+    fim_model: String,
+    timeout: String,
+    client: reqwest::Client,
+}
+
+impl Mistral {
+    pub fn new(client: reqwest::Client) -> Mistral {
+        return Mistral {
+            url: "https://api.mistral.ai".to_string(),
+            token: Config::get(ConfigKey::MistralToken),
+            fim_model: Config::get(ConfigKey::MistralFimModel),
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+        };
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        return self
+            .client
+            .request(method, format!("{url}/v1/{path}", url = self.url))
+            .bearer_auth(&self.token);
+    }
+}
+
+#[async_trait]
+impl Backend for Mistral {
+    fn name(&self) -> BackendName {
+        return BackendName::Mistral;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.token.is_empty() {
+            bail!("Mistral token is not defined");
+        }
+
+        let res = match self
+            .request(reqwest::Method::GET, "models")
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!(error = ?err, "Mistral is not reachable");
+                bail!("Mistral is not reachable");
+            }
+        };
+
+        let status = res.status().as_u16();
+        if status >= 400 {
+            tracing::error!(status = status, "Mistral health check failed");
+            bail!("Mistral health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .request(reqwest::Method::GET, "models")
+            .send()
+            .await?
+            .json::<ModelListResponse>()
+            .await?;
+
+        // Embedding, moderation and OCR models are listed too, but can't
+        // complete anything.
+        let mut models = res
+            .data
+            .iter()
+            .filter(|model| {
+                return model.capabilities.completion_chat || model.capabilities.completion_fim;
+            })
+            .map(|model| {
+                return ModelInfo {
+                    owned_by: model.owned_by.clone(),
+                    context_length: model.max_context_length,
+                    ..ModelInfo::new(&model.id)
+                };
+            })
+            .collect::<Vec<ModelInfo>>();
+
+        models.sort();
+
+        return Ok(models);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn fill_in_middle<'a>(
+        &self,
+        prompt: FimPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<String> {
+        let req = FimRequest {
+            model: self.fim_model.to_string(),
+            prompt: prompt.prefix,
+            suffix: prompt.suffix,
+            stream: false,
+            temperature: prompt.params.temperature,
+            max_tokens: prompt.params.max_tokens,
+            top_p: prompt.params.top_p,
+            stop: prompt.params.stop,
+        };
+
+        let req_builder = self
+            .request(reqwest::Method::POST, "fim/completions")
+            .json(&req);
+        let res = retry::send(req_builder, tx).await?;
+
+        if !res.status().is_success() {
+            return Err(request_error("Mistral", res).await);
+        }
+
+        let res = res.json::<FimResponse>().await?;
+        return match res.choices.into_iter().next() {
+            Some(choice) => Ok(choice.message.content),
+            None => bail!("Mistral returned no fill in the middle completion"),
+        };
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let req_builder = self.request(reqwest::Method::POST, "chat/completions");
+
+        // Mistral sends usage on the last chunk unasked, and rejects
+        // `stream_options`.
+        return openai::send_completion("Mistral", req_builder, prompt, false, tx).await;
+    }
+}
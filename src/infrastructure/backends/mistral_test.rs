@@ -0,0 +1,367 @@
+use anyhow::Result;
+use anyhow::bail;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use super::FimChoiceResponse;
+use super::FimMessageResponse;
+use super::FimResponse;
+use super::Mistral;
+use super::Model;
+use super::ModelCapabilities;
+use super::ModelListResponse;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::FimPrompt;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+
+impl Mistral {
+    fn with_url(url: String) -> Mistral {
+        return Mistral {
+            url,
+            token: "abc".to_string(),
+            fim_model: "codestral-latest".to_string(),
+            timeout: "200".to_string(),
+            client: reqwest::Client::new(),
+        };
+    }
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+fn fim_prompt() -> FimPrompt {
+    return FimPrompt {
+        language: "python".to_string(),
+        prefix: "def add(a, b):\n".to_string(),
+        suffix: "\nprint(add(1, 2))\n".to_string(),
+        params: GenerationParams::default(),
+    };
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("Authorization", "Bearer abc")
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .with_status(401)
+        .create_async()
+        .await;
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert_eq!(res.unwrap_err().to_string(), "Mistral health check failed");
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_a_token() {
+    let mut backend = Mistral::with_url("http://localhost".to_string());
+    backend.token = "".to_string();
+    let res = backend.health_check().await;
+
+    assert_eq!(res.unwrap_err().to_string(), "Mistral token is not defined");
+}
+
+#[tokio::test]
+async fn it_lists_models() -> Result<()> {
+    let body = serde_json::to_string(&ModelListResponse {
+        data: vec![
+            Model {
+                id: "mistral-large-latest".to_string(),
+                owned_by: Some("mistralai".to_string()),
+                max_context_length: Some(131072),
+                capabilities: ModelCapabilities {
+                    completion_chat: true,
+                    completion_fim: false,
+                },
+            },
+            Model {
+                id: "codestral-latest".to_string(),
+                owned_by: Some("mistralai".to_string()),
+                max_context_length: Some(262144),
+                capabilities: ModelCapabilities {
+                    completion_chat: true,
+                    completion_fim: true,
+                },
+            },
+            Model {
+                id: "mistral-embed".to_string(),
+                owned_by: Some("mistralai".to_string()),
+                max_context_length: Some(8192),
+                capabilities: ModelCapabilities::default(),
+            },
+        ],
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("Authorization", "Bearer abc")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.list_models().await?;
+
+    assert_eq!(res, vec![
+        ModelInfo {
+            owned_by: Some("mistralai".to_string()),
+            context_length: Some(262144),
+            ..ModelInfo::new("codestral-latest")
+        },
+        ModelInfo {
+            owned_by: Some("mistralai".to_string()),
+            context_length: Some(131072),
+            ..ModelInfo::new("mistral-large-latest")
+        },
+    ]);
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let body = [
+        json!({"choices": [{"delta": {"content": "Hello "}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {"content": "World"}, "finish_reason": null}]}),
+        json!({"choices": [{"delta": {}, "finish_reason": "stop"}], "usage": {"prompt_tokens": 12, "completion_tokens": 2}}),
+    ]
+    .iter()
+    .map(|line| return format!("data: {line}\n\n"))
+    .collect::<String>()
+        + "data: [DONE]\n\n";
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "mistral-large-latest".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_header("Authorization", "Bearer abc")
+        .match_body(mockito::Matcher::PartialJsonString(
+            json!({"model": "mistral-large-latest", "stream": true}).to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Mistral::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert!(third_recv.done);
+    assert_eq!(
+        third_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(12),
+            completion_tokens: Some(2),
+            ..Usage::default()
+        })
+    );
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::Assistant, "How may I help you?"),
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_omits_stream_options_from_completions() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "mistral-large-latest".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::Regex("stream_options".to_string()))
+        .with_status(422)
+        .expect(0)
+        .create_async()
+        .await;
+    let ok_mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_body("data: [DONE]\n\n")
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Mistral::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+    ok_mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fills_in_the_middle() -> Result<()> {
+    let body = serde_json::to_string(&FimResponse {
+        choices: vec![FimChoiceResponse {
+            message: FimMessageResponse {
+                content: "    return a + b\n".to_string(),
+            },
+        }],
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/fim/completions")
+        .match_header("Authorization", "Bearer abc")
+        .match_body(mockito::Matcher::Json(json!({
+            "model": "codestral-latest",
+            "prompt": "def add(a, b):\n",
+            "suffix": "\nprint(add(1, 2))\n",
+            "stream": false,
+        })))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.fill_in_middle(fim_prompt(), &tx).await?;
+
+    assert_eq!(res, "    return a + b\n");
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_retries_filling_in_the_middle() -> Result<()> {
+    let body = serde_json::to_string(&FimResponse {
+        choices: vec![FimChoiceResponse {
+            message: FimMessageResponse {
+                content: "    return a + b\n".to_string(),
+            },
+        }],
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let unavailable_mock = server
+        .mock("POST", "/v1/fim/completions")
+        .with_status(503)
+        .with_header("Retry-After", "0")
+        .expect(1)
+        .create_async()
+        .await;
+    let ok_mock = server
+        .mock("POST", "/v1/fim/completions")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.fill_in_middle(fim_prompt(), &tx).await?;
+
+    assert_eq!(res, "    return a + b\n");
+    assert!(matches!(rx.recv().await, Some(Event::BackendRetry(_))));
+    unavailable_mock.assert();
+    ok_mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_to_fill_in_the_middle() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/fim/completions")
+        .with_status(400)
+        .with_body(
+            json!({"object": "error", "message": "Invalid model: mistral-small", "type": "invalid_model"}).to_string(),
+        )
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Mistral::with_url(server.url());
+    let res = backend.fill_in_middle(fim_prompt(), &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Mistral. Invalid model: mistral-small"
+    );
+    mock.assert();
+
+    return Ok(());
+}
@@ -1,36 +1,87 @@
+pub mod aws;
+pub mod azure;
+pub mod bedrock;
 pub mod claude;
+pub mod event_stream;
 pub mod gemini;
+pub mod http;
 pub mod langchain;
+pub mod llamacpp;
+pub mod mistral;
+pub mod model_cache;
 pub mod ollama;
 pub mod openai;
+pub mod process;
+pub mod retry;
+pub mod stream;
+pub mod thinking;
 use anyhow::Result;
 use anyhow::bail;
 
+use crate::configuration::Config;
 use crate::domain::models::BackendBox;
 use crate::domain::models::BackendName;
 
 pub struct BackendManager {}
 
 impl BackendManager {
-    pub fn get(name: BackendName) -> Result<BackendBox> {
-        if name == BackendName::LangChain {
-            return Ok(Box::<langchain::LangChain>::default());
+    /// Returns a backend by name, resolving either a built in backend or an
+    /// OpenAI compatible provider profile from the config file. Backends send
+    /// all requests through the given client so connections are pooled, and
+    /// keep their model list for the session.
+    pub fn get(name: &str, client: reqwest::Client) -> Result<BackendBox> {
+        let backend = BackendManager::resolve(name, client)?;
+        return Ok(Box::new(model_cache::ModelCache::new(name, backend)));
+    }
+
+    fn resolve(name: &str, client: reqwest::Client) -> Result<BackendBox> {
+        if let Some(provider) = Config::get_provider(name) {
+            return Ok(Box::new(openai::OpenAI::from_provider(&provider, client)));
+        }
+
+        let backend_name = match BackendName::parse(name.to_string()) {
+            Some(backend_name) => backend_name,
+            None => bail!(format!("No backend implemented for {name}")),
+        };
+
+        if backend_name == BackendName::LangChain {
+            return Ok(Box::new(langchain::LangChain::new(client)));
+        }
+
+        if backend_name == BackendName::Ollama {
+            return Ok(Box::new(ollama::Ollama::new(client)));
+        }
+
+        if backend_name == BackendName::OpenAI {
+            return Ok(Box::new(openai::OpenAI::new(client)));
+        }
+
+        if backend_name == BackendName::Claude {
+            return Ok(Box::new(claude::Claude::new(client)));
+        }
+
+        if backend_name == BackendName::Gemini {
+            return Ok(Box::new(gemini::Gemini::new(client)));
+        }
+
+        if backend_name == BackendName::LlamaCpp {
+            return Ok(Box::new(llamacpp::LlamaCpp::new(client)));
         }
 
-        if name == BackendName::Ollama {
-            return Ok(Box::<ollama::Ollama>::default());
+        if backend_name == BackendName::Azure {
+            return Ok(Box::new(azure::Azure::new(client)));
         }
 
-        if name == BackendName::OpenAI {
-            return Ok(Box::<openai::OpenAI>::default());
+        if backend_name == BackendName::Mistral {
+            return Ok(Box::new(mistral::Mistral::new(client)));
         }
 
-        if name == BackendName::Claude {
-            return Ok(Box::<claude::Claude>::default());
+        if backend_name == BackendName::Bedrock {
+            return Ok(Box::new(bedrock::Bedrock::new(client)));
         }
 
-        if name == BackendName::Gemini {
-            return Ok(Box::<gemini::Gemini>::default());
+        if backend_name == BackendName::Process {
+            return Ok(Box::new(process::Process::new()));
         }
 
         bail!(format!("No backend implemented for {name}"))
@@ -0,0 +1,119 @@
+#[cfg(test)]
+#[path = "model_cache_test.rs"]
+mod tests;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tokio::sync::mpsc;
+
+use crate::domain::models::Backend;
+use crate::domain::models::BackendBox;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+use crate::domain::models::FimPrompt;
+use crate::domain::models::ModelInfo;
+
+/// Models listed so far, keyed by the name the backend was resolved with.
+/// Provider profiles all share the OpenAI backend, so its name alone isn't
+/// enough to tell them apart.
+static MODELS: Lazy<DashMap<String, Vec<ModelInfo>>> = Lazy::new(DashMap::new);
+
+/// Wraps a backend to keep its model list for the rest of the session, so
+/// commands such as `/model N` don't fetch it again. Pulling or removing a
+/// model clears the list. A backend's fallback list is never kept.
+pub struct ModelCache {
+    key: String,
+    backend: BackendBox,
+}
+
+impl ModelCache {
+    pub fn new(key: &str, backend: BackendBox) -> ModelCache {
+        return ModelCache {
+            key: key.to_string(),
+            backend,
+        };
+    }
+}
+
+#[async_trait]
+impl Backend for ModelCache {
+    fn name(&self) -> BackendName {
+        return self.backend.name();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        return self.backend.health_check().await;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        if let Some(models) = MODELS.get(&self.key) {
+            return Ok(models.clone());
+        }
+
+        let models = match self.backend.list_models().await {
+            Ok(models) => models,
+            Err(err) => {
+                let fallback = self.backend.fallback_models();
+                if fallback.is_empty() {
+                    return Err(err);
+                }
+                tracing::error!(error = ?err, "Failed to list models, using fallback list");
+                return Ok(fallback);
+            }
+        };
+        MODELS.insert(self.key.to_string(), models.clone());
+
+        return Ok(models);
+    }
+
+    fn fallback_models(&self) -> Vec<ModelInfo> {
+        return self.backend.fallback_models();
+    }
+
+    fn can_manage_models(&self) -> bool {
+        return self.backend.can_manage_models();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn pull_model<'a>(
+        &self,
+        model: &str,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        // Cleared once the backend is done, as a listing while the pull runs
+        // would otherwise cache the old list again.
+        let res = self.backend.pull_model(model, tx).await;
+        MODELS.remove(&self.key);
+        return res;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn delete_model(&self, model: &str) -> Result<()> {
+        let res = self.backend.delete_model(model).await;
+        MODELS.remove(&self.key);
+        return res;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn fill_in_middle<'a>(
+        &self,
+        prompt: FimPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<String> {
+        return self.backend.fill_in_middle(prompt, tx).await;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        return self.backend.get_completion(prompt, tx).await;
+    }
+}
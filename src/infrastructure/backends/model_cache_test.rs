@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use anyhow::Result;
+use anyhow::bail;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::MODELS;
+use super::ModelCache;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+use crate::domain::models::ModelInfo;
+
+struct CountingBackend {
+    key: String,
+    lists: Arc<AtomicUsize>,
+    fails: bool,
+}
+
+#[async_trait]
+impl Backend for CountingBackend {
+    fn name(&self) -> BackendName {
+        return BackendName::Ollama;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        self.lists.fetch_add(1, Ordering::SeqCst);
+        if self.fails {
+            bail!("401 Unauthorized");
+        }
+        return Ok(vec![ModelInfo::new("first")]);
+    }
+
+    fn fallback_models(&self) -> Vec<ModelInfo> {
+        return vec![ModelInfo::new("fallback")];
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn delete_model(&self, _model: &str) -> Result<()> {
+        // Stands in for a listing that runs while the model is being removed.
+        MODELS.insert(self.key.to_string(), vec![ModelInfo::new("stale")]);
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        _prompt: BackendPrompt,
+        _tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        return Ok(());
+    }
+}
+
+fn backend(key: &str, fails: bool) -> (ModelCache, Arc<AtomicUsize>) {
+    let lists = Arc::new(AtomicUsize::new(0));
+    let backend = CountingBackend {
+        key: key.to_string(),
+        lists: lists.clone(),
+        fails,
+    };
+
+    return (ModelCache::new(key, Box::new(backend)), lists);
+}
+
+fn cache(key: &str) -> (ModelCache, Arc<AtomicUsize>) {
+    return backend(key, false);
+}
+
+#[tokio::test]
+async fn it_lists_models_once_per_session() -> Result<()> {
+    let (backend, lists) = cache("model-cache-once");
+    assert_eq!(backend.list_models().await?, vec![ModelInfo::new("first")]);
+    assert_eq!(backend.list_models().await?, vec![ModelInfo::new("first")]);
+
+    // Backends resolved later with the same name share the list.
+    let (other_backend, other_lists) = cache("model-cache-once");
+    other_backend.list_models().await?;
+
+    assert_eq!(lists.load(Ordering::SeqCst), 1);
+    assert_eq!(other_lists.load(Ordering::SeqCst), 0);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_models_again_after_removing_one() -> Result<()> {
+    let (backend, lists) = cache("model-cache-removed");
+    backend.list_models().await?;
+    backend.delete_model("first").await?;
+    backend.list_models().await?;
+
+    assert_eq!(lists.load(Ordering::SeqCst), 2);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_clears_models_listed_while_removing_one() -> Result<()> {
+    let (backend, _) = cache("model-cache-removing");
+    backend.delete_model("first").await?;
+
+    assert_eq!(backend.list_models().await?, vec![ModelInfo::new("first")]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_does_not_keep_fallback_models() -> Result<()> {
+    let (backend, lists) = backend("model-cache-fallback", true);
+    assert_eq!(backend.list_models().await?, vec![ModelInfo::new(
+        "fallback"
+    )]);
+    assert_eq!(backend.list_models().await?, vec![ModelInfo::new(
+        "fallback"
+    )]);
+
+    assert_eq!(lists.load(Ordering::SeqCst), 2);
+
+    return Ok(());
+}
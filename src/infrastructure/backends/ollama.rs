@@ -7,12 +7,10 @@ use std::time::Duration;
 use anyhow::Result;
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::stream::TryStreamExt;
+use futures::future;
 use serde::Deserialize;
 use serde::Serialize;
-use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
-use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -22,29 +20,129 @@ use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::StreamDecoder;
+use crate::infrastructure::backends::stream::request_error;
+use crate::infrastructure::backends::thinking::ThinkSplitter;
 
-fn convert_err(err: reqwest::Error) -> std::io::Error {
-    let err_msg = err.to_string();
-    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionCallRequest {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolCallRequest {
+    function: FunctionCallRequest,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageRequest {
+    role: String,
+    content: String,
+    /// Reasoning from models run with thinking enabled. Older reasoning models
+    /// write it inline in `<think>` tags instead.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    thinking: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCallRequest>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    tool_name: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionDefinitionRequest {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolRequest {
+    #[serde(rename = "type")]
+    _type: String,
+    function: FunctionDefinitionRequest,
+}
+
+impl ToolRequest {
+    fn new(tool: ToolDefinition) -> ToolRequest {
+        return ToolRequest {
+            _type: "function".to_string(),
+            function: FunctionDefinitionRequest {
+                name: tool.name,
+                description: tool.description,
+                parameters: tool.parameters,
+            },
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CompletionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct CompletionRequest {
     model: String,
-    prompt: String,
-    context: Option<Vec<i32>>,
+    messages: Vec<MessageRequest>,
+    stream: bool,
+    options: CompletionOptions,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolRequest>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct CompletionResponse {
-    pub response: String,
-    pub done: bool,
-    pub context: Option<Vec<i32>>,
+    message: Option<MessageRequest>,
+    done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prompt_eval_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eval_count: Option<u64>,
+    /// Time spent generating the response in nanoseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eval_duration: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelDetails {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    parameter_size: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    quantization_level: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Model {
     name: String,
+    #[serde(default)]
+    details: ModelDetails,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelShowResponse {
+    /// Model metadata, with keys prefixed by the model's architecture such as
+    /// `llama.context_length`.
+    #[serde(default)]
+    model_info: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -52,18 +150,141 @@ struct ModelListResponse {
     pub models: Vec<Model>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelRequest {
+    model: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PullResponse {
+    status: String,
+    /// Size in bytes of the layer being downloaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed: Option<u64>,
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1_000_000_000 {
+        return format!("{:.1} GB", bytes as f64 / 1_000_000_000.0);
+    }
+
+    return format!("{:.1} MB", bytes as f64 / 1_000_000.0);
+}
+
+fn pull_progress(model: &str, res: &PullResponse) -> String {
+    if let (Some(total), Some(completed)) = (res.total, res.completed)
+        && total > 0
+    {
+        return format!(
+            "Pulling {model}: {} {}% ({} of {})",
+            res.status,
+            completed * 100 / total,
+            format_bytes(completed),
+            format_bytes(total)
+        );
+    }
+
+    return format!("Pulling {model}: {}", res.status);
+}
+
+/// Renders the transcript as chat messages. Tool results are sent as one
+/// `tool` message per call rather than a user message.
+fn messages_from_transcript(transcript: &Transcript) -> Vec<MessageRequest> {
+    let mut messages = vec![];
+    for turn in transcript.turns.iter() {
+        if turn.role == Role::Assistant {
+            messages.push(MessageRequest {
+                role: "assistant".to_string(),
+                content: turn.text.to_string(),
+                tool_calls: turn
+                    .tool_calls
+                    .iter()
+                    .map(|tool_call| {
+                        return ToolCallRequest {
+                            function: FunctionCallRequest {
+                                name: tool_call.name.to_string(),
+                                arguments: tool_call.arguments.clone(),
+                            },
+                        };
+                    })
+                    .collect(),
+                ..MessageRequest::default()
+            });
+            continue;
+        }
+
+        if turn.tool_results.is_empty() {
+            messages.push(MessageRequest {
+                role: "user".to_string(),
+                content: turn.text.to_string(),
+                images: turn
+                    .images
+                    .iter()
+                    .map(|e| return e.data.to_string())
+                    .collect(),
+                ..MessageRequest::default()
+            });
+        }
+        for tool_result in turn.tool_results.iter() {
+            messages.push(MessageRequest {
+                role: "tool".to_string(),
+                content: tool_result.content.to_string(),
+                tool_name: tool_result.name.to_string(),
+                ..MessageRequest::default()
+            });
+        }
+    }
+
+    return messages;
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    return Some(text.to_string()).filter(|e| return !e.is_empty());
+}
+
 pub struct Ollama {
     url: String,
     timeout: String,
+    client: reqwest::Client,
 }
 
-impl Default for Ollama {
-    fn default() -> Ollama {
+impl Ollama {
+    pub fn new(client: reqwest::Client) -> Ollama {
         return Ollama {
             url: Config::get(ConfigKey::OllamaURL),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
         };
     }
+
+    /// Looks up the context length of a model, which is only reported by
+    /// `/api/show`. Models that can't be looked up are listed without it.
+    async fn context_length(&self, model: &str) -> Option<u64> {
+        let req = ModelRequest {
+            model: model.to_string(),
+            stream: false,
+        };
+        let res = self
+            .client
+            .post(format!("{url}/api/show", url = self.url))
+            .json(&req)
+            .send()
+            .await
+            .ok()?
+            .json::<ModelShowResponse>()
+            .await
+            .ok()?;
+
+        return res
+            .model_info
+            .iter()
+            .find(|(key, _)| return key.ends_with(".context_length"))
+            .and_then(|(_, value)| return value.as_u64());
+    }
 }
 
 #[async_trait]
@@ -74,7 +295,8 @@ impl Backend for Ollama {
 
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
-        let res = reqwest::Client::new()
+        let res = self
+            .client
             .get(&self.url)
             .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
             .send()
@@ -95,80 +317,226 @@ impl Backend for Ollama {
     }
 
     #[allow(clippy::implicit_return)]
-    async fn list_models(&self) -> Result<Vec<String>> {
-        let res = reqwest::Client::new()
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .client
             .get(format!("{url}/api/tags", url = self.url))
             .send()
             .await?
             .json::<ModelListResponse>()
             .await?;
 
-        let mut models: Vec<String> = res
-            .models
-            .iter()
-            .map(|model| {
-                return model.name.to_string();
-            })
-            .collect();
+        let mut models = future::join_all(res.models.iter().map(|model| {
+            return async move {
+                return ModelInfo {
+                    parameter_size: non_empty(&model.details.parameter_size),
+                    quantization: non_empty(&model.details.quantization_level),
+                    context_length: self.context_length(&model.name).await,
+                    ..ModelInfo::new(&model.name)
+                };
+            };
+        }))
+        .await;
 
         models.sort();
 
         return Ok(models);
     }
 
+    fn can_manage_models(&self) -> bool {
+        return true;
+    }
+
     #[allow(clippy::implicit_return)]
-    async fn get_completion<'a>(
+    async fn pull_model<'a>(
         &self,
-        prompt: BackendPrompt,
+        model: &str,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
-        let mut req = CompletionRequest {
-            model: Config::get(ConfigKey::Model),
-            prompt: prompt.text,
-            context: None,
+        let req = ModelRequest {
+            model: model.to_string(),
+            stream: true,
         };
+        let res = self
+            .client
+            .post(format!("{url}/api/pull", url = self.url))
+            .json(&req)
+            .send()
+            .await?;
 
-        if !prompt.backend_context.is_empty() {
-            req.context = Some(serde_json::from_str(&prompt.backend_context)?);
+        if !res.status().is_success() {
+            tracing::error!(
+                status = res.status().as_u16(),
+                "Failed to pull model from Ollama"
+            );
+            bail!(format!("Failed to pull {model} from Ollama"));
+        }
+
+        let mut decoder = StreamDecoder::new(res);
+        let mut status = String::new();
+        while let Some(pres) = decoder.next::<PullResponse>().await? {
+            tracing::debug!(body = ?pres, "Pull response");
+            tx.send(Event::BackendProgress(pull_progress(model, &pres)))?;
+            status = pres.status;
         }
+        decoder.finish()?;
 
-        let res = reqwest::Client::new()
-            .post(format!("{url}/api/generate", url = self.url))
+        if status != "success" {
+            bail!(format!("Ollama stopped before {model} finished pulling"));
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn delete_model(&self, model: &str) -> Result<()> {
+        let req = ModelRequest {
+            model: model.to_string(),
+            stream: false,
+        };
+        let res = self
+            .client
+            .delete(format!("{url}/api/delete", url = self.url))
             .json(&req)
             .send()
             .await?;
 
+        if res.status() == 404 {
+            bail!(format!("No model named {model} found in Ollama"));
+        }
         if !res.status().is_success() {
             tracing::error!(
                 status = res.status().as_u16(),
-                "Failed to make completion request to Ollama"
+                "Failed to remove model from Ollama"
             );
-            bail!("Failed to make completion request to Ollama");
+            bail!(format!("Failed to remove {model} from Ollama"));
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let mut transcript = prompt.to_transcript();
+
+        // The system prompt is sent on every request rather than being stored in
+        // the transcript, so it can be changed mid-session with /system.
+        let mut messages: Vec<MessageRequest> = vec![];
+        if !prompt.system_prompt.is_empty() {
+            messages.push(MessageRequest {
+                role: "system".to_string(),
+                content: prompt.system_prompt,
+                ..MessageRequest::default()
+            });
         }
+        messages.extend(messages_from_transcript(&transcript));
 
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+        let req = CompletionRequest {
+            model: prompt.model,
+            messages,
+            stream: true,
+            options: CompletionOptions {
+                temperature: prompt.params.temperature,
+                num_predict: prompt.params.max_tokens,
+                top_p: prompt.params.top_p,
+                stop: prompt.params.stop,
+            },
+            tools: prompt.tools.into_iter().map(ToolRequest::new).collect(),
+        };
 
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
-            }
+        let req_builder = self
+            .client
+            .post(format!("{url}/api/chat", url = self.url))
+            .json(&req);
+        let res = retry::send(req_builder, tx).await?;
 
-            let ores: CompletionResponse = serde_json::from_str(&line.unwrap()).unwrap();
+        if !res.status().is_success() {
+            return Err(request_error("Ollama", res).await);
+        }
+
+        let mut decoder = StreamDecoder::new(res);
+
+        let mut last_message = String::new();
+        let mut tool_calls: Vec<ToolCallRequest> = vec![];
+        let mut splitter = ThinkSplitter::new();
+        let mut done = false;
+        while let Some(ores) = decoder.next::<CompletionResponse>().await? {
             tracing::debug!(body = ?ores, "Completion response");
+
+            let mut text = String::new();
+            let mut thinking = String::new();
+            if let Some(message) = ores.message {
+                let (content, content_thinking) = splitter.push(&message.content);
+                text = content;
+                thinking = message.thinking + &content_thinking;
+                tool_calls.extend(message.tool_calls);
+            }
+            if ores.done {
+                let (rest, rest_thinking) = splitter.finish();
+                text += &rest;
+                thinking += &rest_thinking;
+                done = true;
+            }
+            last_message += &text;
+
             let mut msg = BackendResponse {
                 author: Author::Model,
-                text: ores.response,
+                text,
+                thinking,
                 done: ores.done,
-                context: None,
+                transcript: None,
+                usage: None,
+                tool_calls: vec![],
             };
-            if ores.done && ores.context.is_some() {
-                msg.context = Some(serde_json::to_string(&ores.context)?);
+            if ores.done {
+                msg.usage = Some(Usage {
+                    prompt_tokens: ores.prompt_eval_count,
+                    completion_tokens: ores.eval_count,
+                    generation_ms: ores.eval_duration.map(|e| return e / 1_000_000),
+                    ..Usage::default()
+                });
+                // Ollama doesn't give tool calls an ID, so the name is used.
+                let mut turn = Turn::new(Role::Assistant, &last_message);
+                turn.tool_calls = tool_calls
+                    .iter()
+                    .map(|tool_call| {
+                        return ToolCall {
+                            id: tool_call.function.name.to_string(),
+                            name: tool_call.function.name.to_string(),
+                            arguments: tool_call.function.arguments.clone(),
+                        };
+                    })
+                    .collect();
+                transcript.push(turn.clone());
+                msg.transcript = Some(transcript.clone());
+                msg.tool_calls = turn.tool_calls;
             }
 
             tx.send(Event::BackendPromptResponse(msg))?;
         }
 
-        return Ok(());
+        // Keep whatever arrived as the reply when the stream ends without
+        // `done`, whether it timed out or was cut off.
+        if !done {
+            let (text, thinking) = splitter.finish();
+            last_message += &text;
+            transcript.push(Turn::new(Role::Assistant, &last_message));
+            let msg = BackendResponse {
+                author: Author::Model,
+                text,
+                thinking,
+                done: true,
+                transcript: Some(transcript),
+                usage: None,
+                tool_calls: vec![],
+            };
+            tx.send(Event::BackendPromptResponse(msg))?;
+        }
+
+        return decoder.finish();
     }
 }
@@ -1,22 +1,38 @@
 use anyhow::Result;
 use anyhow::bail;
+use serde_json::json;
 use tokio::sync::mpsc;
 
 use super::CompletionResponse;
+use super::MessageRequest;
 use super::Model;
+use super::ModelDetails;
 use super::ModelListResponse;
 use super::Ollama;
+use super::PullResponse;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::ToolResult;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
 
 impl Ollama {
     fn with_url(url: String) -> Ollama {
         return Ollama {
             url,
             timeout: "200".to_string(),
+            client: reqwest::Client::new(),
         };
     }
 }
@@ -67,10 +83,15 @@ async fn it_lists_models() -> Result<()> {
     let body = serde_json::to_string(&ModelListResponse {
         models: vec![
             Model {
-                name: "first".to_string(),
+                name: "second".to_string(),
+                ..Model::default()
             },
             Model {
-                name: "second".to_string(),
+                name: "first".to_string(),
+                details: ModelDetails {
+                    parameter_size: "8.0B".to_string(),
+                    quantization_level: "Q4_0".to_string(),
+                },
             },
         ],
     })?;
@@ -82,12 +103,156 @@ async fn it_lists_models() -> Result<()> {
         .with_body(body)
         .create_async()
         .await;
+    let show_mock = server
+        .mock("POST", "/api/show")
+        .match_body(mockito::Matcher::PartialJsonString(
+            json!({"model": "first"}).to_string(),
+        ))
+        .with_status(200)
+        .with_body(
+            json!({"model_info": {"general.architecture": "llama", "llama.context_length": 8192}})
+                .to_string(),
+        )
+        .create_async()
+        .await;
+    let missing_show_mock = server
+        .mock("POST", "/api/show")
+        .match_body(mockito::Matcher::PartialJsonString(
+            json!({"model": "second"}).to_string(),
+        ))
+        .with_status(404)
+        .create_async()
+        .await;
 
     let backend = Ollama::with_url(server.url());
     let res = backend.list_models().await?;
 
-    assert_eq!(res, vec!["first".to_string(), "second".to_string()]);
+    assert_eq!(res, vec![
+        ModelInfo {
+            parameter_size: Some("8.0B".to_string()),
+            quantization: Some("Q4_0".to_string()),
+            context_length: Some(8192),
+            ..ModelInfo::new("first")
+        },
+        ModelInfo::new("second"),
+    ]);
+    mock.assert();
+    show_mock.assert();
+    missing_show_mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_pulls_models() -> Result<()> {
+    let body = [
+        serde_json::to_string(&PullResponse {
+            status: "pulling manifest".to_string(),
+            ..PullResponse::default()
+        })?,
+        serde_json::to_string(&PullResponse {
+            status: "pulling 6a0746a1ec1a".to_string(),
+            total: Some(4_000_000_000),
+            completed: Some(1_000_000_000),
+        })?,
+        serde_json::to_string(&PullResponse {
+            status: "success".to_string(),
+            ..PullResponse::default()
+        })?,
+    ]
+    .join("\n");
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/pull")
+        .match_body(mockito::Matcher::JsonString(
+            r#"{"model":"llama3","stream":true}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let backend = Ollama::with_url(server.url());
+    backend.pull_model("llama3", &tx).await?;
+    drop(tx);
+
+    mock.assert();
+
+    let mut progress = vec![];
+    while let Some(Event::BackendProgress(text)) = rx.recv().await {
+        progress.push(text);
+    }
+    assert_eq!(progress, vec![
+        "Pulling llama3: pulling manifest".to_string(),
+        "Pulling llama3: pulling 6a0746a1ec1a 25% (1.0 GB of 4.0 GB)".to_string(),
+        "Pulling llama3: success".to_string(),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_to_pull_missing_models() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/pull")
+        .with_status(200)
+        .with_body(r#"{"error":"pull model manifest: file does not exist"}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+    let backend = Ollama::with_url(server.url());
+    let res = backend.pull_model("nope", &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "pull model manifest: file does not exist"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_deletes_models() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("DELETE", "/api/delete")
+        .match_body(mockito::Matcher::JsonString(
+            r#"{"model":"llama3"}"#.to_string(),
+        ))
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let backend = Ollama::with_url(server.url());
+    backend.delete_model("llama3").await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_to_delete_missing_models() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("DELETE", "/api/delete")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let backend = Ollama::with_url(server.url());
+    let res = backend.delete_model("nope").await;
+
     mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "No model named nope found in Ollama"
+    );
 
     return Ok(());
 }
@@ -95,26 +260,46 @@ async fn it_lists_models() -> Result<()> {
 #[tokio::test]
 async fn it_gets_completions() -> Result<()> {
     let first_line = serde_json::to_string(&CompletionResponse {
-        response: "Hello ".to_string(),
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "Hello ".to_string(),
+            ..MessageRequest::default()
+        }),
         done: false,
-        context: None,
+        ..CompletionResponse::default()
     })?;
 
     let second_line = serde_json::to_string(&CompletionResponse {
-        response: "World".to_string(),
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "World".to_string(),
+            ..MessageRequest::default()
+        }),
         done: true,
-        context: Some(vec![1, 2, 3]),
+        ..CompletionResponse::default()
     })?;
 
     let body = [first_line, second_line].join("\n");
     let prompt = BackendPrompt {
         text: "Say hi to the world".to_string(),
-        backend_context: serde_json::to_string(&vec![1])?,
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
     };
 
     let mut server = mockito::Server::new_async().await;
     let mock = server
-        .mock("POST", "/api/generate")
+        .mock("POST", "/api/chat")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"assistant","content":"How may I help you?"},{"role":"user","content":"Say hi to the world"}],"stream":true}"#.to_string(),
+        ))
         .with_status(200)
         .with_body(body)
         .create_async()
@@ -133,12 +318,408 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(first_recv.author, Author::Model);
     assert_eq!(first_recv.text, "Hello ".to_string());
     assert!(!first_recv.done);
-    assert_eq!(first_recv.context, None);
+    assert_eq!(first_recv.transcript, None);
 
     assert_eq!(second_recv.author, Author::Model);
     assert_eq!(second_recv.text, "World".to_string());
     assert!(second_recv.done);
-    assert_eq!(second_recv.context, Some("[1,2,3]".to_string()));
+    assert_eq!(second_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::Assistant, "How may I help you?"),
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_finishes_completions_when_the_stream_ends_early() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "Hello ".to_string(),
+            ..MessageRequest::default()
+        }),
+        done: false,
+        ..CompletionResponse::default()
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut prompt = BackendPrompt::new("Say hi to the world".to_string(), Transcript::default());
+    prompt.model = "model-1".to_string();
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert!(second_recv.done);
+    assert_eq!(second_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, "Hello "),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_thinking() -> Result<()> {
+    let first_line = serde_json::to_string(&CompletionResponse {
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "<think>Hmm, a greeting".to_string(),
+            ..MessageRequest::default()
+        }),
+        done: false,
+        ..CompletionResponse::default()
+    })?;
+
+    let second_line = serde_json::to_string(&CompletionResponse {
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "</think>\n\nHello".to_string(),
+            thinking: " then".to_string(),
+            ..MessageRequest::default()
+        }),
+        done: true,
+        ..CompletionResponse::default()
+    })?;
+
+    let body = [first_line, second_line].join("\n");
+    let prompt = BackendPrompt {
+        text: "Say hi".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "".to_string());
+    assert_eq!(first_recv.thinking, "Hmm, a greeting".to_string());
+
+    assert_eq!(second_recv.text, "Hello".to_string());
+    assert_eq!(second_recv.thinking, " then".to_string());
+    assert!(second_recv.done);
+    assert_eq!(second_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi"),
+        Turn::new(Role::Assistant, "Hello"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_generation_params() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "Hello".to_string(),
+            ..MessageRequest::default()
+        }),
+        done: true,
+        ..CompletionResponse::default()
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams {
+            temperature: Some(0.5),
+            max_tokens: Some(100),
+            top_p: Some(0.9),
+            stop: vec![],
+        },
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"options":{"temperature":0.5,"num_predict":100,"top_p":0.9}}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_images() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        done: true,
+        ..CompletionResponse::default()
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "What is this?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![ImageAttachment {
+            name: "diagram.png".to_string(),
+            media_type: "image/png".to_string(),
+            data: "aGVsbG8=".to_string(),
+        }],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"user","content":"What is this?","images":["aGVsbG8="]}]}"#
+                .to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_usage() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        message: Some(MessageRequest {
+            role: "assistant".to_string(),
+            content: "Hello".to_string(),
+            ..MessageRequest::default()
+        }),
+        done: true,
+        prompt_eval_count: Some(26),
+        eval_count: Some(290),
+        eval_duration: Some(4_709_213_000),
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert_eq!(
+        recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(26),
+            completion_tokens: Some(290),
+            generation_ms: Some(4709),
+            latency_ms: None,
+        })
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_calls() -> Result<()> {
+    let body = [
+        r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"read_file","arguments":{"path":"README.md"}}}]},"done":false}"#,
+        r#"{"message":{"role":"assistant","content":""},"done":true}"#,
+    ]
+    .join("\n");
+
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Reads a file.".to_string(),
+            parameters: json!({"type": "object"}),
+        }],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"tools":[{"type":"function","function":{"name":"read_file","description":"Reads a file.","parameters":{"type":"object"}}}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    assert!(first_recv.tool_calls.is_empty());
+
+    let second_recv = to_res(rx.recv().await)?;
+    assert!(second_recv.done);
+    assert_eq!(second_recv.tool_calls, vec![ToolCall {
+        id: "read_file".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_results() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        done: true,
+        ..CompletionResponse::default()
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "read_file".to_string(),
+            name: "read_file".to_string(),
+            content: "# Hello".to_string(),
+            is_error: false,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r##"{"messages":[{"role":"tool","content":"# Hello","tool_name":"read_file"}]}"##
+                .to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_the_provider_error() -> Result<()> {
+    let mut prompt = BackendPrompt::new("Say hi".to_string(), Transcript::default());
+    prompt.model = "model-1".to_string();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/api/chat")
+        .match_query(mockito::Matcher::Any)
+        .with_status(404)
+        .with_body(r#"{"error":"model 'model-1' not found"}"#)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to Ollama. model 'model-1' not found"
+    );
 
     return Ok(());
 }
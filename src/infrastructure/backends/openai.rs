@@ -2,35 +2,49 @@
 #[path = "openai_test.rs"]
 mod tests;
 
+use std::collections::HashMap;
 use std::time::Duration;
 
 use anyhow::Result;
 use anyhow::bail;
 use async_trait::async_trait;
-use futures::stream::TryStreamExt;
 use serde::Deserialize;
 use serde::Serialize;
-use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
-use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::Provider;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
-
-fn convert_err(err: reqwest::Error) -> std::io::Error {
-    let err_msg = err.to_string();
-    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
-}
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::retry;
+use crate::infrastructure::backends::stream::StreamDecoder;
+use crate::infrastructure::backends::stream::request_error;
+use crate::infrastructure::backends::thinking::ThinkSplitter;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Model {
     id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owned_by: Option<String>,
+    /// Reported by some compatible servers such as OpenRouter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context_length: Option<u64>,
+    /// Reported by vLLM.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_model_len: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -38,22 +52,189 @@ struct ModelListResponse {
     data: Vec<Model>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// Message content is plain text, or a list of parts when images are attached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Default for MessageContent {
+    fn default() -> MessageContent {
+        return MessageContent::Text(String::new());
+    }
+}
+
+impl MessageContent {
+    fn new(text: String, images: &[ImageAttachment]) -> MessageContent {
+        if images.is_empty() {
+            return MessageContent::Text(text);
+        }
+
+        let mut parts = vec![ContentPart::Text { text }];
+        parts.extend(images.iter().map(|image| {
+            return ContentPart::ImageUrl {
+                image_url: ImageUrl {
+                    url: image.data_url(),
+                },
+            };
+        }));
+
+        return MessageContent::Parts(parts);
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionCallRequest {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolCallRequest {
+    id: String,
+    #[serde(rename = "type")]
+    _type: String,
+    function: FunctionCallRequest,
+}
+
+impl ToolCallRequest {
+    fn from_tool_call(tool_call: &ToolCall) -> ToolCallRequest {
+        // Arguments that weren't valid JSON are kept as the raw string.
+        let arguments = match &tool_call.arguments {
+            serde_json::Value::String(arguments) => arguments.to_string(),
+            arguments => arguments.to_string(),
+        };
+
+        return ToolCallRequest {
+            id: tool_call.id.to_string(),
+            _type: "function".to_string(),
+            function: FunctionCallRequest {
+                name: tool_call.name.to_string(),
+                arguments,
+            },
+        };
+    }
+
+    fn to_tool_call(&self) -> ToolCall {
+        let arguments = match self.function.arguments.trim() {
+            "" => serde_json::json!({}),
+            arguments => {
+                serde_json::from_str(arguments)
+                    .unwrap_or_else(|_| return serde_json::Value::String(arguments.to_string()))
+            }
+        };
+
+        return ToolCall {
+            id: self.id.to_string(),
+            name: self.function.name.to_string(),
+            arguments,
+        };
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct MessageRequest {
     role: String,
-    content: String,
+    content: MessageContent,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCallRequest>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    tool_call_id: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionDefinitionRequest {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolRequest {
+    #[serde(rename = "type")]
+    _type: String,
+    function: FunctionDefinitionRequest,
+}
+
+impl ToolRequest {
+    fn new(tool: ToolDefinition) -> ToolRequest {
+        return ToolRequest {
+            _type: "function".to_string(),
+            function: FunctionDefinitionRequest {
+                name: tool.name,
+                description: tool.description,
+                parameters: tool.parameters,
+            },
+        };
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct CompletionRequest {
     model: String,
     messages: Vec<MessageRequest>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolRequest>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FunctionCallDeltaResponse {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ToolCallDeltaResponse {
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<FunctionCallDeltaResponse>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct CompletionDeltaResponse {
     content: Option<String>,
+    /// Reasoning from compatible servers such as DeepSeek and vLLM.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reasoning_content: Option<String>,
+    /// Reasoning from compatible servers such as OpenRouter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reasoning: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCallDeltaResponse>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -62,25 +243,244 @@ struct CompletionChoiceResponse {
     finish_reason: Option<String>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletionUsageResponse {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct CompletionResponse {
     choices: Vec<CompletionChoiceResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<CompletionUsageResponse>,
+}
+
+/// Renders the transcript as chat messages. Tool results are sent as one
+/// `tool` message per call rather than a user message.
+fn messages_from_transcript(transcript: &Transcript) -> Vec<MessageRequest> {
+    let mut messages = vec![];
+    for turn in transcript.turns.iter() {
+        if turn.role == Role::Assistant {
+            messages.push(MessageRequest {
+                role: "assistant".to_string(),
+                content: MessageContent::Text(turn.text.to_string()),
+                tool_calls: turn
+                    .tool_calls
+                    .iter()
+                    .map(ToolCallRequest::from_tool_call)
+                    .collect(),
+                ..MessageRequest::default()
+            });
+            continue;
+        }
+
+        if turn.tool_results.is_empty() {
+            messages.push(MessageRequest {
+                role: "user".to_string(),
+                content: MessageContent::new(turn.text.to_string(), &turn.images),
+                ..MessageRequest::default()
+            });
+        }
+        for tool_result in turn.tool_results.iter() {
+            messages.push(MessageRequest {
+                role: "tool".to_string(),
+                content: MessageContent::Text(tool_result.content.to_string()),
+                tool_call_id: tool_result.id.to_string(),
+                ..MessageRequest::default()
+            });
+        }
+    }
+
+    return messages;
+}
+
+/// Sends the prompt as a streamed chat completion, passing each part of the
+/// response through the channel. Shared with backends that speak the same
+/// format but address and authorize requests differently. `request_usage`
+/// asks for token counts with `stream_options`, which APIs that always send
+/// usage may reject.
+pub async fn send_completion(
+    name: &str,
+    req_builder: reqwest::RequestBuilder,
+    prompt: BackendPrompt,
+    request_usage: bool,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<()> {
+    let mut transcript = prompt.to_transcript();
+
+    // The system prompt is sent on every request rather than being stored in
+    // the transcript, so it can be changed mid-session with /system.
+    let mut messages: Vec<MessageRequest> = vec![];
+    if !prompt.system_prompt.is_empty() {
+        messages.push(MessageRequest {
+            role: "system".to_string(),
+            content: MessageContent::Text(prompt.system_prompt),
+            ..MessageRequest::default()
+        });
+    }
+    messages.extend(messages_from_transcript(&transcript));
+
+    let req = CompletionRequest {
+        model: prompt.model,
+        messages,
+        stream: true,
+        temperature: prompt.params.temperature,
+        max_tokens: prompt.params.max_tokens,
+        top_p: prompt.params.top_p,
+        stop: prompt.params.stop,
+        stream_options: request_usage.then_some(StreamOptions {
+            include_usage: true,
+        }),
+        tools: prompt.tools.into_iter().map(ToolRequest::new).collect(),
+    };
+
+    let res = retry::send(req_builder.json(&req), tx).await?;
+
+    if !res.status().is_success() {
+        return Err(request_error(name, res).await);
+    }
+
+    let mut decoder = StreamDecoder::new(res);
+
+    let mut last_message = String::new();
+    let mut usage = None;
+    let mut tool_calls: Vec<ToolCallRequest> = vec![];
+    let mut splitter = ThinkSplitter::new();
+    while let Some(ores) = decoder.next::<CompletionResponse>().await? {
+        tracing::debug!(body = ?ores, "Completion response");
+
+        // With include_usage set, usage arrives in a final chunk with no
+        // choices after the finish reason.
+        if let Some(res_usage) = ores.usage {
+            usage = Some(Usage {
+                prompt_tokens: Some(res_usage.prompt_tokens),
+                completion_tokens: Some(res_usage.completion_tokens),
+                ..Usage::default()
+            });
+        }
+        let choice = match ores.choices.first() {
+            Some(choice) => choice,
+            None => continue,
+        };
+
+        // Tool calls are streamed in pieces keyed by index, with the
+        // arguments split across chunks.
+        for delta in choice.delta.tool_calls.iter() {
+            if tool_calls.len() <= delta.index {
+                tool_calls.resize(delta.index + 1, ToolCallRequest {
+                    _type: "function".to_string(),
+                    ..ToolCallRequest::default()
+                });
+            }
+            let tool_call = &mut tool_calls[delta.index];
+            if let Some(id) = &delta.id {
+                tool_call.id = id.to_string();
+            }
+            if let Some(function) = &delta.function {
+                tool_call.function.name += function.name.as_deref().unwrap_or_default();
+                tool_call.function.arguments += function.arguments.as_deref().unwrap_or_default();
+            }
+        }
+
+        let (text, content_thinking) =
+            splitter.push(choice.delta.content.as_deref().unwrap_or_default());
+        let thinking = choice
+            .delta
+            .reasoning_content
+            .clone()
+            .or_else(|| return choice.delta.reasoning.clone())
+            .unwrap_or_default()
+            + &content_thinking;
+        if text.is_empty() && thinking.is_empty() {
+            continue;
+        }
+
+        last_message += &text;
+        let msg = BackendResponse {
+            author: Author::Model,
+            text,
+            thinking,
+            done: false,
+            transcript: None,
+            usage: None,
+            tool_calls: vec![],
+        };
+
+        tx.send(Event::BackendPromptResponse(msg))?;
+    }
+
+    let (text, thinking) = splitter.finish();
+    last_message += &text;
+
+    let mut turn = Turn::new(Role::Assistant, &last_message);
+    turn.tool_calls = tool_calls
+        .iter()
+        .map(ToolCallRequest::to_tool_call)
+        .collect();
+    transcript.push(turn.clone());
+
+    let msg = BackendResponse {
+        author: Author::Model,
+        text,
+        thinking,
+        done: true,
+        transcript: Some(transcript),
+        usage,
+        tool_calls: turn.tool_calls,
+    };
+    tx.send(Event::BackendPromptResponse(msg))?;
+    decoder.finish()?;
+
+    return Ok(());
 }
 
 pub struct OpenAI {
+    name: String,
     url: String,
     token: String,
+    headers: HashMap<String, String>,
+    models: Vec<String>,
     timeout: String,
+    client: reqwest::Client,
 }
 
-impl Default for OpenAI {
-    fn default() -> OpenAI {
+impl OpenAI {
+    pub fn new(client: reqwest::Client) -> OpenAI {
         return OpenAI {
+            name: "OpenAI".to_string(),
             url: Config::get(ConfigKey::OpenAiURL),
             token: Config::get(ConfigKey::OpenAiToken),
+            headers: HashMap::new(),
+            models: vec![],
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
+        };
+    }
+
+    pub fn from_provider(provider: &Provider, client: reqwest::Client) -> OpenAI {
+        return OpenAI {
+            name: provider.name.to_string(),
+            url: provider.url.to_string(),
+            token: provider.token.to_string(),
+            headers: provider.headers.clone(),
+            models: provider.models.clone(),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+            client,
         };
     }
+
+    fn request(&self, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        let mut req = self.client.request(method, url);
+        if !self.token.is_empty() {
+            req = req.header("Authorization", format!("Bearer {}", self.token));
+        }
+        for (key, value) in self.headers.iter() {
+            req = req.header(key, value);
+        }
+
+        return req;
+    }
 }
 
 #[async_trait]
@@ -91,54 +491,64 @@ impl Backend for OpenAI {
 
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
+        let name = &self.name;
         if self.url.is_empty() {
-            bail!("OpenAI URL is not defined");
-        }
-        if self.token.is_empty() {
-            bail!("OpenAI token is not defined");
+            bail!(format!("{name} URL is not defined"));
         }
 
         // OpenAI are trolls with their API where the index either returns a 404 or a
         // 418. If using the official API, don't bother health checking it.
         if self.url == "https://api.openai.com" {
+            if self.token.is_empty() {
+                bail!(format!("{name} token is not defined"));
+            }
             return Ok(());
         }
 
-        let res = reqwest::Client::new()
-            .get(&self.url)
+        let res = self
+            .request(reqwest::Method::GET, self.url.to_string())
             .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
             .send()
             .await;
 
         if res.is_err() {
-            tracing::error!(error = ?res.unwrap_err(), "OpenAI is not reachable");
-            bail!("OpenAI is not reachable");
+            tracing::error!(error = ?res.unwrap_err(), "{name} is not reachable");
+            bail!(format!("{name} is not reachable"));
         }
 
         let status = res.unwrap().status().as_u16();
         if status >= 400 {
-            tracing::error!(status = status, "OpenAI health check failed");
-            bail!("OpenAI health check failed");
+            tracing::error!(status = status, "{name} health check failed");
+            bail!(format!("{name} health check failed"));
         }
 
         return Ok(());
     }
 
     #[allow(clippy::implicit_return)]
-    async fn list_models(&self) -> Result<Vec<String>> {
-        let res = reqwest::Client::new()
-            .get(format!("{url}/v1/models", url = self.url))
-            .header("Authorization", format!("Bearer {}", self.token))
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let res = self
+            .request(
+                reqwest::Method::GET,
+                format!("{url}/v1/models", url = self.url),
+            )
             .send()
             .await?
             .json::<ModelListResponse>()
             .await?;
 
-        let mut models: Vec<String> = res
+        let mut models: Vec<ModelInfo> = res
             .data
             .iter()
+            .filter(|model| {
+                return self.models.is_empty() || self.models.contains(&model.id);
+            })
             .map(|model| {
-                return model.id.to_string();
+                return ModelInfo {
+                    owned_by: model.owned_by.clone(),
+                    context_length: model.context_length.or(model.max_model_len),
+                    ..ModelInfo::new(&model.id)
+                };
             })
             .collect();
 
@@ -153,93 +563,11 @@ impl Backend for OpenAI {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
-        let mut messages: Vec<MessageRequest> = vec![];
-        if !prompt.backend_context.is_empty() {
-            messages = serde_json::from_str(&prompt.backend_context)?;
-        }
-        messages.push(MessageRequest {
-            role: "user".to_string(),
-            content: prompt.text,
-        });
-
-        let req = CompletionRequest {
-            model: Config::get(ConfigKey::Model),
-            messages: messages.clone(),
-            stream: true,
-        };
-
-        let res = reqwest::Client::new()
-            .post(format!("{url}/v1/chat/completions", url = self.url))
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&req)
-            .send()
-            .await?;
-
-        if !res.status().is_success() {
-            tracing::error!(
-                status = res.status().as_u16(),
-                "Failed to make completion request to OpenAI"
-            );
-            bail!("Failed to make completion request to OpenAI");
-        }
-
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+        let req_builder = self.request(
+            reqwest::Method::POST,
+            format!("{url}/v1/chat/completions", url = self.url),
+        );
 
-        let mut last_message = String::new();
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
-            }
-
-            let mut cleaned_line = line.unwrap().trim().to_string();
-            if cleaned_line.starts_with("data:") {
-                cleaned_line = cleaned_line.split_off(5).trim().to_string();
-            }
-            if cleaned_line.is_empty() {
-                continue;
-            }
-
-            let ores: CompletionResponse = serde_json::from_str(&cleaned_line).unwrap();
-            tracing::debug!(body = ?ores, "Completion response");
-
-            let choice = &ores.choices[0];
-            if choice.finish_reason.is_some() {
-                break;
-            }
-            if choice.delta.content.is_none() {
-                continue;
-            }
-
-            let text = choice.delta.content.clone().unwrap().to_string();
-            if text.is_empty() {
-                continue;
-            }
-
-            last_message += &text;
-            let msg = BackendResponse {
-                author: Author::Model,
-                text,
-                done: false,
-                context: None,
-            };
-
-            tx.send(Event::BackendPromptResponse(msg))?;
-        }
-
-        messages.push(MessageRequest {
-            role: "assistant".to_string(),
-            content: last_message.to_string(),
-        });
-
-        let msg = BackendResponse {
-            author: Author::Model,
-            text: String::new(),
-            done: true,
-            context: Some(serde_json::to_string(&messages)?),
-        };
-        tx.send(Event::BackendPromptResponse(msg))?;
-
-        return Ok(());
+        return send_completion(&self.name, req_builder, prompt, true, tx).await;
     }
 }
@@ -1,27 +1,45 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use anyhow::bail;
+use serde_json::json;
 use test_utils::insta_snapshot;
 use tokio::sync::mpsc;
 
 use super::CompletionChoiceResponse;
 use super::CompletionDeltaResponse;
 use super::CompletionResponse;
-use super::MessageRequest;
 use super::Model;
 use super::ModelListResponse;
 use super::OpenAI;
+use crate::configuration::Provider;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::TRANSCRIPT_VERSION;
+use crate::domain::models::ToolCall;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::ToolResult;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
 
 impl OpenAI {
     fn with_url(url: String) -> OpenAI {
         return OpenAI {
+            name: "OpenAI".to_string(),
             url,
             token: "abc".to_string(),
+            headers: HashMap::new(),
+            models: vec![],
             timeout: "200".to_string(),
+            client: reqwest::Client::new(),
         };
     }
 }
@@ -81,9 +99,14 @@ async fn it_lists_models() -> Result<()> {
         data: vec![
             Model {
                 id: "first".to_string(),
+                owned_by: Some("system".to_string()),
+                context_length: Some(8192),
+                ..Model::default()
             },
             Model {
                 id: "second".to_string(),
+                max_model_len: Some(4096),
+                ..Model::default()
             },
         ],
     })?;
@@ -101,45 +124,133 @@ async fn it_lists_models() -> Result<()> {
     let res = backend.list_models().await?;
     mock.assert();
 
-    assert_eq!(res, vec!["first".to_string(), "second".to_string()]);
+    assert_eq!(res, vec![
+        ModelInfo {
+            context_length: Some(8192),
+            owned_by: Some("system".to_string()),
+            ..ModelInfo::new("first")
+        },
+        ModelInfo {
+            context_length: Some(4096),
+            ..ModelInfo::new("second")
+        },
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_models_from_provider_allowlist() -> Result<()> {
+    let body = serde_json::to_string(&ModelListResponse {
+        data: vec![
+            Model {
+                id: "first".to_string(),
+                ..Model::default()
+            },
+            Model {
+                id: "second".to_string(),
+                ..Model::default()
+            },
+        ],
+    })?;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/v1/models")
+        .match_header("Authorization", "Bearer xyz")
+        .match_header("X-Gateway", "team-a")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let backend = OpenAI::from_provider(
+        &Provider {
+            name: "groq".to_string(),
+            url: server.url(),
+            token: "xyz".to_string(),
+            headers: HashMap::from([("X-Gateway".to_string(), "team-a".to_string())]),
+            models: vec!["second".to_string()],
+        },
+        reqwest::Client::new(),
+    );
+    let res = backend.list_models().await?;
+    mock.assert();
+
+    assert_eq!(res, vec![ModelInfo::new("second")]);
 
     return Ok(());
 }
 
+#[tokio::test]
+async fn it_successfully_health_checks_provider_without_token() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/")
+        .match_header("Authorization", mockito::Matcher::Missing)
+        .with_status(200)
+        .create_async()
+        .await;
+
+    let backend = OpenAI::from_provider(
+        &Provider {
+            name: "lmstudio".to_string(),
+            url: server.url(),
+            ..Provider::default()
+        },
+        reqwest::Client::new(),
+    );
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
 #[tokio::test]
 async fn it_gets_completions() -> Result<()> {
     let first_line = serde_json::to_string(&CompletionResponse {
         choices: vec![CompletionChoiceResponse {
             delta: CompletionDeltaResponse {
                 content: Some("Hello ".to_string()),
+                ..CompletionDeltaResponse::default()
             },
             finish_reason: None,
         }],
+        usage: None,
     })?;
 
     let second_line = serde_json::to_string(&CompletionResponse {
         choices: vec![CompletionChoiceResponse {
             delta: CompletionDeltaResponse {
                 content: Some("World".to_string()),
+                ..CompletionDeltaResponse::default()
             },
             finish_reason: None,
         }],
+        usage: None,
     })?;
 
     let third_line = serde_json::to_string(&CompletionResponse {
         choices: vec![CompletionChoiceResponse {
-            delta: CompletionDeltaResponse { content: None },
+            delta: CompletionDeltaResponse::default(),
             finish_reason: Some("stop".to_string()),
         }],
+        usage: None,
     })?;
 
     let body = [first_line, second_line, third_line].join("\n");
     let prompt = BackendPrompt {
         text: "Say hi to the world".to_string(),
-        backend_context: serde_json::to_string(&vec![MessageRequest {
-            role: "assistant".to_string(),
-            content: "How may I help you?".to_string(),
-        }])?,
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::Assistant, "How may I help you?")],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
     };
 
     let mut server = mockito::Server::new_async().await;
@@ -165,19 +276,485 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(first_recv.author, Author::Model);
     assert_eq!(first_recv.text, "Hello ".to_string());
     assert!(!first_recv.done);
-    assert_eq!(first_recv.context, None);
+    assert_eq!(first_recv.transcript, None);
 
     assert_eq!(second_recv.author, Author::Model);
     assert_eq!(second_recv.text, "World".to_string());
     assert!(!second_recv.done);
-    assert_eq!(second_recv.context, None);
+    assert_eq!(second_recv.transcript, None);
 
     assert_eq!(third_recv.author, Author::Model);
     assert!(third_recv.text.is_empty());
     assert!(third_recv.done);
     insta_snapshot(|| {
-        insta::assert_toml_snapshot!(third_recv.context);
+        insta::assert_toml_snapshot!(third_recv.transcript);
     });
 
     return Ok(());
 }
+
+#[tokio::test]
+async fn it_gets_completions_with_reasoning() -> Result<()> {
+    let first_line = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse {
+                reasoning_content: Some("Hmm, a greeting".to_string()),
+                ..CompletionDeltaResponse::default()
+            },
+            finish_reason: None,
+        }],
+        usage: None,
+    })?;
+
+    let second_line = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse {
+                content: Some("Hello".to_string()),
+                ..CompletionDeltaResponse::default()
+            },
+            finish_reason: Some("stop".to_string()),
+        }],
+        usage: None,
+    })?;
+
+    let body = [first_line, second_line].join("\n");
+    let prompt = BackendPrompt {
+        text: "Say hi".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "".to_string());
+    assert_eq!(first_recv.thinking, "Hmm, a greeting".to_string());
+
+    assert_eq!(second_recv.text, "Hello".to_string());
+    assert_eq!(second_recv.thinking, "".to_string());
+
+    assert!(third_recv.done);
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi"),
+        Turn::new(Role::Assistant, "Hello"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_generation_params() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse::default(),
+            finish_reason: Some("stop".to_string()),
+        }],
+        usage: None,
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams {
+            temperature: Some(0.5),
+            max_tokens: Some(100),
+            top_p: None,
+            stop: vec!["END".to_string()],
+        },
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"temperature": 0.5, "max_tokens": 100, "stop": ["END"]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_system_prompt() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse::default(),
+            finish_reason: Some("stop".to_string()),
+        }],
+        usage: None,
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "You are a pirate".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"system","content":"You are a pirate"},{"role":"user","content":"Say hi to the world"}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert_eq!(recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi to the world"),
+        Turn::new(Role::Assistant, ""),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_images() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse::default(),
+            finish_reason: Some("stop".to_string()),
+        }],
+        usage: None,
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "What is this?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![ImageAttachment {
+            name: "diagram.png".to_string(),
+            media_type: "image/png".to_string(),
+            data: "aGVsbG8=".to_string(),
+        }],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"messages":[{"role":"user","content":[{"type":"text","text":"What is this?"},{"type":"image_url","image_url":{"url":"data:image/png;base64,aGVsbG8="}}]}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_usage() -> Result<()> {
+    let body = [
+        r#"data: {"choices":[{"delta":{"content":"Hello"},"finish_reason":null}]}"#,
+        r#"data: {"choices":[{"delta":{},"finish_reason":"stop"}]}"#,
+        r#"data: {"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":1,"total_tokens":13}}"#,
+        "data: [DONE]",
+    ]
+    .join("\n\n");
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"stream_options":{"include_usage":true}}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello".to_string());
+    assert_eq!(first_recv.usage, None);
+    assert!(second_recv.done);
+    assert_eq!(
+        second_recv.usage,
+        Some(Usage {
+            prompt_tokens: Some(12),
+            completion_tokens: Some(1),
+            ..Usage::default()
+        })
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_on_stream_error() -> Result<()> {
+    let body = [
+        ": keepalive",
+        r#"data: {"choices":[{"delta":{"content":"Hello"},"finish_reason":null}]}"#,
+        r#"data: {"error":{"message":"The server had an error while processing your request.","type":"server_error"}}"#,
+    ]
+    .join("\n\n");
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The server had an error while processing your request."
+    );
+    assert_eq!(to_res(rx.recv().await)?.text, "Hello".to_string());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_calls() -> Result<()> {
+    let body = [
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"read_file","arguments":""}}]},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"path\":"}}]},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"README.md\"}"}}]},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#,
+    ]
+    .join("\n");
+
+    let prompt = BackendPrompt {
+        text: "What's in the readme?".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript::default(),
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Reads a file.".to_string(),
+            parameters: json!({"type": "object"}),
+        }],
+        tool_results: vec![],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"tools":[{"type":"function","function":{"name":"read_file","description":"Reads a file.","parameters":{"type":"object"}}}]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert_eq!(recv.tool_calls, vec![ToolCall {
+        id: "call_1".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "README.md"}),
+    }]);
+
+    let transcript = recv.transcript.unwrap();
+    assert_eq!(transcript.turns[1].tool_calls, recv.tool_calls);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_tool_results() -> Result<()> {
+    let body = serde_json::to_string(&CompletionResponse {
+        choices: vec![CompletionChoiceResponse {
+            delta: CompletionDeltaResponse::default(),
+            finish_reason: Some("stop".to_string()),
+        }],
+        usage: None,
+    })?;
+
+    let prompt = BackendPrompt {
+        text: "".to_string(),
+        model: "model-1".to_string(),
+        transcript: Transcript {
+            version: TRANSCRIPT_VERSION,
+            turns: vec![Turn::new(Role::User, "What's in the readme?"), Turn {
+                tool_calls: vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: json!({"path": "README.md"}),
+                }],
+                ..Turn::new(Role::Assistant, "")
+            }],
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![ToolResult {
+            id: "call_1".to_string(),
+            name: "read_file".to_string(),
+            content: "# Hello".to_string(),
+            is_error: false,
+        }],
+    };
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r##"{"messages":[{"role":"user"},{"role":"assistant","tool_calls":[{"id":"call_1","function":{"name":"read_file","arguments":"{\"path\":\"README.md\"}"}}]},{"role":"tool","content":"# Hello","tool_call_id":"call_1"}]}"##.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let recv = to_res(rx.recv().await)?;
+    assert!(recv.done);
+    assert!(recv.tool_calls.is_empty());
+
+    assert_eq!(recv.transcript.unwrap().turns.len(), 4);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_the_provider_error() -> Result<()> {
+    let mut prompt = BackendPrompt::new("Say hi".to_string(), Transcript::default());
+    prompt.model = "model-1".to_string();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v1/chat/completions")
+        .match_query(mockito::Matcher::Any)
+        .with_status(401)
+        .with_body(
+            r#"{"error":{"message":"Incorrect API key provided.","type":"invalid_request_error"}}"#,
+        )
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = OpenAI::with_url(server.url());
+    let res = backend.get_completion(prompt, &tx).await;
+
+    mock.assert();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Failed to make completion request to OpenAI. Incorrect API key provided."
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,338 @@
+#[cfg(test)]
+#[path = "process_test.rs"]
+mod tests;
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Child;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+use crate::infrastructure::backends::stream::StreamDecoder;
+
+/// Sent with every request so plugins can reject versions they don't
+/// understand. Bumped on breaking changes to the protocol.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Request {
+    version: u32,
+    #[serde(flatten)]
+    body: RequestBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RequestBody {
+    Health,
+    ListModels,
+    Completion(CompletionRequest),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageRequest {
+    role: Role,
+    content: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CompletionRequest {
+    model: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    system_prompt: String,
+    messages: Vec<MessageRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelResponse {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owned_by: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    Models {
+        models: Vec<ModelResponse>,
+    },
+    Chunk {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        thinking: String,
+    },
+    Done {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// A running plugin answering a single request.
+struct Exchange {
+    child: Child,
+    decoder: StreamDecoder,
+    stderr: JoinHandle<String>,
+}
+
+impl Exchange {
+    /// Returns the next response. Error responses, and the plugin exiting
+    /// before it sends `done`, are returned as errors.
+    async fn next(&mut self) -> Result<Response> {
+        let res = match self.decoder.next::<Response>().await? {
+            Some(res) => res,
+            None => {
+                self.decoder.finish()?;
+                let stderr = self.stderr().await;
+                bail!(format!(
+                    "The process backend exited before finishing the request. {stderr}"
+                ));
+            }
+        };
+
+        if let Response::Error { message } = res {
+            bail!(format!("The process backend failed: {message}"));
+        }
+
+        return Ok(res);
+    }
+
+    /// Reads the last line the plugin wrote to stderr once it exits, to
+    /// explain why it stopped early.
+    async fn stderr(&mut self) -> String {
+        let _ = self.child.wait().await;
+        let stderr = (&mut self.stderr).await.unwrap_or_default();
+
+        return stderr.trim().lines().last().unwrap_or_default().to_string();
+    }
+}
+
+pub struct Process {
+    command: String,
+    timeout: String,
+}
+
+impl Process {
+    pub fn new() -> Process {
+        return Process {
+            command: Config::get(ConfigKey::ProcessCommand),
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+        };
+    }
+
+    /// Starts the plugin and writes the request as a single line to its stdin,
+    /// which is then closed. Each request runs its own process, which is
+    /// killed if the request is dropped early.
+    ///
+    /// The command is split into arguments as a shell would, so paths with
+    /// spaces can be quoted.
+    async fn send(&self, body: RequestBody) -> Result<Exchange> {
+        let parts = shell_words::split(&self.command)
+            .map_err(|err| return anyhow!(format!("Invalid process command: {err}")))?;
+        let (program, args) = match parts.split_first() {
+            Some(parts) => parts,
+            None => bail!("Process command is not defined"),
+        };
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| {
+                return anyhow!(format!("Failed to start process backend {program}: {err}"));
+            })?;
+
+        let mut line = serde_json::to_string(&Request {
+            version: PROTOCOL_VERSION,
+            body,
+        })?;
+        line.push('\n');
+
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(line.as_bytes()).await?;
+        drop(stdin);
+
+        let mut stderr = child.stderr.take().unwrap();
+        let stderr = tokio::spawn(async move {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text).await;
+            return text;
+        });
+        let decoder = StreamDecoder::from_process(child.stdout.take().unwrap());
+
+        return Ok(Exchange {
+            child,
+            decoder,
+            stderr,
+        });
+    }
+}
+
+#[async_trait]
+impl Backend for Process {
+    fn name(&self) -> BackendName {
+        return BackendName::Process;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        let timeout = Duration::from_millis(self.timeout.parse::<u64>()?);
+        let res = tokio::time::timeout(timeout, async {
+            let mut exchange = self.send(RequestBody::Health).await?;
+            while !matches!(exchange.next().await?, Response::Done { .. }) {}
+            return Ok::<(), anyhow::Error>(());
+        })
+        .await;
+
+        return match res {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => {
+                tracing::error!(error = ?err, "Process backend health check failed");
+                Err(err)
+            }
+            Err(_) => {
+                bail!("Process backend health check timed out")
+            }
+        };
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut exchange = self.send(RequestBody::ListModels).await?;
+
+        let mut models = vec![];
+        loop {
+            match exchange.next().await? {
+                Response::Models { models: res } => {
+                    models.extend(res.into_iter().map(|model| {
+                        return ModelInfo {
+                            context_length: model.context_length,
+                            owned_by: model.owned_by,
+                            ..ModelInfo::new(&model.name)
+                        };
+                    }));
+                }
+                Response::Done { .. } => break,
+                _ => continue,
+            }
+        }
+
+        models.sort();
+
+        return Ok(models);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        if !prompt.images.is_empty() {
+            bail!("The process backend doesn't support images");
+        }
+        if !prompt.tools.is_empty() {
+            bail!("The process backend doesn't support tools. Turn off `tools` to use it.");
+        }
+
+        let mut transcript = prompt.to_transcript();
+        let messages = transcript
+            .turns
+            .iter()
+            .map(|turn| {
+                return MessageRequest {
+                    role: turn.role,
+                    content: turn.text.to_string(),
+                };
+            })
+            .collect();
+
+        let req = CompletionRequest {
+            model: prompt.model.to_string(),
+            system_prompt: prompt.system_prompt.to_string(),
+            messages,
+            temperature: prompt.params.temperature,
+            max_tokens: prompt.params.max_tokens,
+            top_p: prompt.params.top_p,
+            stop: prompt.params.stop.clone(),
+        };
+        let mut exchange = self.send(RequestBody::Completion(req)).await?;
+
+        let mut last_message = String::new();
+        let usage = loop {
+            match exchange.next().await? {
+                Response::Chunk { text, thinking } => {
+                    if text.is_empty() && thinking.is_empty() {
+                        continue;
+                    }
+
+                    last_message += &text;
+                    let msg = BackendResponse {
+                        author: Author::Model,
+                        text,
+                        thinking,
+                        done: false,
+                        transcript: None,
+                        usage: None,
+                        tool_calls: vec![],
+                    };
+                    tx.send(Event::BackendPromptResponse(msg))?;
+                }
+                Response::Done { usage } => break usage,
+                _ => continue,
+            }
+        };
+
+        transcript.push(Turn::new(Role::Assistant, &last_message));
+        let msg = BackendResponse {
+            author: Author::Model,
+            text: String::new(),
+            thinking: String::new(),
+            done: true,
+            transcript: Some(transcript),
+            usage,
+            tool_calls: vec![],
+        };
+        tx.send(Event::BackendPromptResponse(msg))?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,271 @@
+use std::env;
+use std::fs;
+use std::path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::bail;
+use tokio::sync::mpsc;
+
+use super::Process;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+use crate::domain::models::GenerationParams;
+use crate::domain::models::ImageAttachment;
+use crate::domain::models::ModelInfo;
+use crate::domain::models::Role;
+use crate::domain::models::ToolDefinition;
+use crate::domain::models::Transcript;
+use crate::domain::models::Turn;
+use crate::domain::models::Usage;
+
+/// The example plugin, which cargo builds alongside the tests into the
+/// `examples` directory of the target profile. It isn't built when only the
+/// library tests are built, so tests using it are skipped then.
+fn example_plugin() -> Option<PathBuf> {
+    let name = format!("process_plugin{}", env::consts::EXE_SUFFIX);
+    let plugin = env::current_exe()
+        .ok()?
+        .ancestors()
+        .map(|dir| return dir.join("examples").join(&name))
+        .find(|plugin| return plugin.exists());
+
+    if plugin.is_none() {
+        eprintln!(
+            "Skipping the test, as the process_plugin example isn't built. Run `cargo build --examples` first."
+        );
+    }
+
+    return plugin;
+}
+
+/// Quotes the example plugin's path as a command.
+fn example_command() -> Option<String> {
+    let plugin = example_plugin()?;
+    return Some(shell_words::quote(&plugin.to_string_lossy()).to_string());
+}
+
+impl Process {
+    fn with_command(command: &str) -> Process {
+        return Process {
+            command: command.to_string(),
+            timeout: "5000".to_string(),
+        };
+    }
+}
+
+fn prompt(text: &str) -> BackendPrompt {
+    return BackendPrompt {
+        text: text.to_string(),
+        model: "echo".to_string(),
+        transcript: Transcript {
+            turns: vec![
+                Turn::new(Role::User, "Say hi"),
+                Turn::new(Role::Assistant, "hi"),
+            ],
+            ..Transcript::default()
+        },
+        system_prompt: "".to_string(),
+        params: GenerationParams::default(),
+        images: vec![],
+        tools: vec![],
+        tool_results: vec![],
+    };
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let Some(command) = example_command() else {
+        return;
+    };
+    let backend = Process::with_command(&command);
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_a_command() {
+    let backend = Process::with_command("");
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Process command is not defined"
+    );
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_for_missing_executables() {
+    let command = path::Path::new("missing").join("plugin");
+    let backend = Process::with_command(&command.to_string_lossy());
+    let res = backend.health_check().await;
+
+    assert!(
+        res.unwrap_err()
+            .to_string()
+            .starts_with("Failed to start process backend")
+    );
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_for_invalid_commands() {
+    let backend = Process::with_command("'unterminated");
+    let res = backend.health_check().await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Invalid process command: missing closing quote"
+    );
+}
+
+#[tokio::test]
+async fn it_runs_commands_with_spaces_in_the_path() -> Result<()> {
+    let Some(plugin) = example_plugin() else {
+        return Ok(());
+    };
+    let dir = tempfile::tempdir()?;
+    let copy = dir
+        .path()
+        .join("oatmeal plugins")
+        .join(plugin.file_name().unwrap());
+    fs::create_dir_all(copy.parent().unwrap())?;
+    fs::copy(&plugin, &copy)?;
+
+    let command = format!("\"{}\"", copy.display());
+    let backend = Process::with_command(&command);
+    let res = backend.list_models().await?;
+
+    assert_eq!(res.len(), 1);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_models() -> Result<()> {
+    let Some(command) = example_command() else {
+        return Ok(());
+    };
+    let backend = Process::with_command(&command);
+    let res = backend.list_models().await?;
+
+    assert_eq!(res, vec![ModelInfo {
+        context_length: Some(4096),
+        ..ModelInfo::new("echo")
+    }]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let Some(command) = example_command() else {
+        return Ok(());
+    };
+    let backend = Process::with_command(&command);
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    backend.get_completion(prompt("Hello World"), &tx).await?;
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert_eq!(third_recv.text, "".to_string());
+    assert!(third_recv.done);
+    assert_eq!(
+        third_recv.usage,
+        Some(Usage {
+            completion_tokens: Some(2),
+            ..Usage::default()
+        })
+    );
+    assert_eq!(third_recv.transcript.unwrap().turns, vec![
+        Turn::new(Role::User, "Say hi"),
+        Turn::new(Role::Assistant, "hi"),
+        Turn::new(Role::User, "Hello World"),
+        Turn::new(Role::Assistant, "Hello World"),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_when_the_plugin_exits_early() -> Result<()> {
+    let Some(command) = example_command() else {
+        return Ok(());
+    };
+    let backend = Process::with_command(&command);
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let res = backend.get_completion(prompt("fail"), &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The process backend exited before finishing the request. echo: asked to fail"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_images() -> Result<()> {
+    let Some(command) = example_command() else {
+        return Ok(());
+    };
+    let backend = Process::with_command(&command);
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut prompt = prompt("What's this?");
+    prompt.images = vec![ImageAttachment {
+        name: "cat.png".to_string(),
+        media_type: "image/png".to_string(),
+        data: "aGVsbG8=".to_string(),
+    }];
+    let res = backend.get_completion(prompt, &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The process backend doesn't support images"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_completions_with_tools() -> Result<()> {
+    let backend = Process::with_command("plugin");
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut prompt = prompt("What's in the readme?");
+    prompt.tools = vec![ToolDefinition {
+        name: "read_file".to_string(),
+        description: "Reads a file.".to_string(),
+        parameters: serde_json::json!({"type": "object"}),
+    }];
+    let res = backend.get_completion(prompt, &tx).await;
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The process backend doesn't support tools. Turn off `tools` to use it."
+    );
+
+    return Ok(());
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+#[path = "retry_test.rs"]
+mod tests;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use chrono::DateTime;
+use chrono::Utc;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use tokio::sync::mpsc;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Event;
+
+/// Upper bound on how long to wait between attempts, regardless of backoff or
+/// what a `Retry-After` header asks for.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// How long to wait for the response to start. The body isn't limited by
+    /// it, as streamed responses have their own idle timeout.
+    pub response_timeout: Option<Duration>,
+}
+
+impl RetryPolicy {
+    pub fn from_config() -> RetryPolicy {
+        return RetryPolicy {
+            max_retries: Config::get(ConfigKey::BackendMaxRetries)
+                .parse::<u32>()
+                .unwrap_or_default(),
+            base_delay: Duration::from_millis(
+                Config::get(ConfigKey::BackendRetryDelay)
+                    .parse::<u64>()
+                    .unwrap_or_default(),
+            ),
+            response_timeout: Config::get(ConfigKey::RequestTimeout)
+                .parse::<u64>()
+                .ok()
+                .filter(|timeout_ms| return *timeout_ms > 0)
+                .map(Duration::from_millis),
+        };
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        return self.base_delay.saturating_mul(factor).min(MAX_DELAY);
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    return status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::INTERNAL_SERVER_ERROR
+        || status == StatusCode::BAD_GATEWAY
+        || status == StatusCode::SERVICE_UNAVAILABLE
+        || status == StatusCode::GATEWAY_TIMEOUT
+        // Anthropic's overloaded status.
+        || status.as_u16() == 529;
+}
+
+/// Parses a `Retry-After` header, which is either a number of seconds or an
+/// HTTP date.
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds).min(MAX_DELAY));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+    return Some(delay.to_std().unwrap_or_default().min(MAX_DELAY));
+}
+
+/// Sends a request, retrying rate limits, server errors, connection failures
+/// and responses that don't start in time with exponential backoff as
+/// configured. The UI is notified through `tx` before each retry.
+pub async fn send(req: RequestBuilder, tx: &mpsc::UnboundedSender<Event>) -> Result<Response> {
+    return send_with_policy(req, tx, &RetryPolicy::from_config()).await;
+}
+
+pub async fn send_with_policy(
+    req: RequestBuilder,
+    tx: &mpsc::UnboundedSender<Event>,
+    policy: &RetryPolicy,
+) -> Result<Response> {
+    let mut attempt = 0;
+
+    loop {
+        let current_req = req
+            .try_clone()
+            .ok_or_else(|| return anyhow!("Request can't be retried"))?;
+        // Resolves once the response headers arrive, before the body is read.
+        let res = match policy.response_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, current_req.send()).await,
+            None => Ok(current_req.send().await),
+        };
+
+        let should_retry = match &res {
+            Ok(Ok(res)) => is_retryable_status(res.status()),
+            Ok(Err(err)) => err.is_connect() || err.is_timeout(),
+            Err(_) => true,
+        };
+        if !should_retry || attempt >= policy.max_retries {
+            return match res {
+                Ok(res) => Ok(res?),
+                Err(_) => {
+                    bail!(format!(
+                        "The backend didn't start responding within {}ms",
+                        policy.response_timeout.unwrap_or_default().as_millis()
+                    ))
+                }
+            };
+        }
+
+        attempt += 1;
+        let delay = res
+            .as_ref()
+            .ok()
+            .and_then(|res| return res.as_ref().ok())
+            .and_then(retry_after)
+            .unwrap_or_else(|| return policy.backoff(attempt));
+
+        let reason = match &res {
+            Ok(Ok(res)) => res.status().to_string(),
+            Ok(Err(err)) => err.to_string(),
+            Err(_) => "response timed out".to_string(),
+        };
+        tracing::warn!(reason, attempt, "Retrying backend request");
+
+        tx.send(Event::BackendRetry(format!(
+            "Attempt {} of {}, retrying in {:.1}s...",
+            attempt + 1,
+            policy.max_retries + 1,
+            delay.as_secs_f32()
+        )))?;
+        tokio::time::sleep(delay).await;
+    }
+}
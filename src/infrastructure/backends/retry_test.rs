@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+use super::RetryPolicy;
+use super::send_with_policy;
+use crate::domain::models::Event;
+
+fn policy(max_retries: u32, base_delay_ms: u64) -> RetryPolicy {
+    return RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(base_delay_ms),
+        response_timeout: None,
+    };
+}
+
+fn retry_messages(rx: &mut mpsc::UnboundedReceiver<Event>) -> Vec<String> {
+    let mut messages = vec![];
+    while let Ok(event) = rx.try_recv() {
+        if let Event::BackendRetry(text) = event {
+            messages.push(text);
+        }
+    }
+
+    return messages;
+}
+
+#[test]
+fn it_backs_off_exponentially() {
+    let policy = policy(5, 100);
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(400));
+}
+
+#[test]
+fn it_caps_backoff() {
+    let policy = policy(20, 1000);
+    assert_eq!(policy.backoff(20), Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn it_retries_server_errors() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let failed_mock = server
+        .mock("POST", "/")
+        .with_status(503)
+        .expect(2)
+        .create_async()
+        .await;
+    let success_mock = server
+        .mock("POST", "/")
+        .with_status(200)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(server.url());
+    let res = send_with_policy(req, &tx, &policy(3, 1)).await?;
+
+    failed_mock.assert();
+    success_mock.assert();
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(retry_messages(&mut rx), vec![
+        "Attempt 2 of 4, retrying in 0.0s...".to_string(),
+        "Attempt 3 of 4, retrying in 0.0s...".to_string(),
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gives_up_after_max_retries() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/")
+        .with_status(429)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(server.url());
+    let res = send_with_policy(req, &tx, &policy(2, 1)).await?;
+
+    mock.assert();
+    assert_eq!(res.status().as_u16(), 429);
+    assert_eq!(retry_messages(&mut rx).len(), 2);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_does_not_retry_client_errors() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/")
+        .with_status(400)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(server.url());
+    let res = send_with_policy(req, &tx, &policy(3, 1)).await?;
+
+    mock.assert();
+    assert_eq!(res.status().as_u16(), 400);
+    assert!(retry_messages(&mut rx).is_empty());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_respects_retry_after() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/")
+        .with_status(429)
+        .with_header("Retry-After", "0")
+        .expect(2)
+        .create_async()
+        .await;
+
+    // The base delay would time out the test if Retry-After was ignored.
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(server.url());
+    let res = send_with_policy(req, &tx, &policy(1, 60_000)).await?;
+
+    mock.assert();
+    assert_eq!(res.status().as_u16(), 429);
+    assert_eq!(retry_messages(&mut rx), vec![
+        "Attempt 2 of 2, retrying in 0.0s...".to_string()
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_times_out_responses_that_dont_start() -> Result<()> {
+    // Accepts connections without ever answering them.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let server = tokio::spawn(async move {
+        let mut connections = vec![];
+        while let Ok((connection, _)) = listener.accept().await {
+            connections.push(connection);
+        }
+    });
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(url);
+    let policy = RetryPolicy {
+        response_timeout: Some(Duration::from_millis(50)),
+        ..policy(1, 1)
+    };
+    let res = send_with_policy(req, &tx, &policy).await;
+    server.abort();
+
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "The backend didn't start responding within 50ms"
+    );
+    assert_eq!(retry_messages(&mut rx).len(), 1);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_does_not_time_out_streamed_bodies() -> Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/")
+        .with_status(200)
+        .with_chunked_body(|writer| {
+            writer.write_all(b"Hello ")?;
+            writer.flush()?;
+            std::thread::sleep(Duration::from_millis(200));
+            return writer.write_all(b"World");
+        })
+        .create_async()
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+    let req = reqwest::Client::new().post(server.url());
+    let policy = RetryPolicy {
+        response_timeout: Some(Duration::from_millis(50)),
+        ..policy(0, 1)
+    };
+    let res = send_with_policy(req, &tx, &policy).await?;
+
+    mock.assert();
+    assert_eq!(res.text().await?, "Hello World");
+
+    return Ok(());
+}
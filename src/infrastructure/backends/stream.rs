@@ -0,0 +1,228 @@
+#[cfg(test)]
+#[path = "stream_test.rs"]
+mod tests;
+
+use std::io;
+use std::time::Duration;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use futures::stream::TryStreamExt;
+use serde::de::DeserializeOwned;
+use tokio::io::AsyncBufRead;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::process::ChildStdout;
+use tokio_util::io::StreamReader;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
+pub fn convert_err(err: reqwest::Error) -> io::Error {
+    let err_msg = err.to_string();
+    // Keep timeouts distinguishable so a partial response can be kept.
+    if err.is_timeout() {
+        return io::Error::new(io::ErrorKind::TimedOut, err_msg);
+    }
+    return io::Error::new(io::ErrorKind::Interrupted, err_msg);
+}
+
+/// Strips Server-Sent Events framing from a line, returning the JSON payload.
+/// Lines without a `data:` prefix are treated as newline delimited JSON.
+fn parse_line(line: &str) -> Option<&str> {
+    let line = line.trim();
+
+    // Blank lines separate events, and lines starting with a colon are
+    // comments, often used by proxies as keepalives.
+    if line.is_empty() || line.starts_with(':') {
+        return None;
+    }
+
+    if let Some(data) = line.strip_prefix("data:") {
+        let data = data.trim();
+        if data.is_empty() {
+            return None;
+        }
+        return Some(data);
+    }
+
+    if ["event:", "id:", "retry:"]
+        .iter()
+        .any(|field| return line.starts_with(field))
+    {
+        return None;
+    }
+
+    return Some(line);
+}
+
+/// Finds the message from an error payload, covering the shapes used by the
+/// supported providers: `{"error": "..."}` and `{"error": {"message": "..."}}`.
+fn error_message(value: &serde_json::Value) -> Option<String> {
+    let error = value.get("error").filter(|e| return !e.is_null())?;
+
+    if let Some(message) = error.as_str() {
+        return Some(message.to_string());
+    }
+    if let Some(message) = error.get("message").and_then(|e| return e.as_str()) {
+        return Some(message.to_string());
+    }
+
+    return Some(error.to_string());
+}
+
+/// Builds the error for a completion request the provider rejected, including
+/// the message from the response body when there is one. Mistral puts the
+/// message at the top of the body rather than under `error`.
+pub async fn request_error(name: &str, res: reqwest::Response) -> anyhow::Error {
+    let status = res.status().as_u16();
+    let body = res.text().await.unwrap_or_default();
+    tracing::error!(
+        status = status,
+        body = body,
+        "Failed to make completion request to {name}"
+    );
+
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| {
+            // Gemini wraps its errors in a list.
+            if let Some(first) = value.as_array().and_then(|e| return e.first()) {
+                return error_message(first);
+            }
+            return error_message(&value).or_else(|| {
+                return value
+                    .get("message")
+                    .and_then(|e| return e.as_str())
+                    .map(|e| return e.to_string());
+            });
+        });
+
+    return match message {
+        Some(message) => anyhow!("Failed to make completion request to {name}. {message}"),
+        None => anyhow!("Failed to make completion request to {name}"),
+    };
+}
+
+/// Decodes JSON payloads from a streamed Server-Sent Events or newline delimited
+/// JSON response body. Malformed lines are logged and skipped rather than
+/// failing the whole response.
+///
+/// When the response times out the stream ends early, so backends can keep the
+/// partial response, and the timeout is returned from `finish`.
+pub struct StreamDecoder {
+    lines: Lines<Box<dyn AsyncBufRead + Unpin + Send>>,
+    idle_timeout: Option<Duration>,
+    timeout_message: Option<String>,
+}
+
+impl StreamDecoder {
+    pub fn new(res: reqwest::Response) -> StreamDecoder {
+        let stream = Box::pin(res.bytes_stream().map_err(convert_err));
+        return StreamDecoder::with_idle_timeout(Box::new(StreamReader::new(stream)));
+    }
+
+    /// Decodes the newline delimited JSON a child process writes to its stdout.
+    pub fn from_process(stdout: ChildStdout) -> StreamDecoder {
+        return StreamDecoder::with_idle_timeout(Box::new(BufReader::new(stdout)));
+    }
+
+    fn with_idle_timeout(reader: Box<dyn AsyncBufRead + Unpin + Send>) -> StreamDecoder {
+        let idle_timeout_ms = Config::get(ConfigKey::StreamIdleTimeout)
+            .parse::<u64>()
+            .unwrap_or_default();
+
+        let mut decoder = StreamDecoder::from_reader(reader);
+        if idle_timeout_ms > 0 {
+            decoder.idle_timeout = Some(Duration::from_millis(idle_timeout_ms));
+        }
+
+        return decoder;
+    }
+
+    fn from_reader(reader: Box<dyn AsyncBufRead + Unpin + Send>) -> StreamDecoder {
+        return StreamDecoder {
+            lines: reader.lines(),
+            idle_timeout: None,
+            timeout_message: None,
+        };
+    }
+
+    async fn next_line(&mut self) -> Result<Option<String>> {
+        let line_res = match self.idle_timeout {
+            Some(idle_timeout) => tokio::time::timeout(idle_timeout, self.lines.next_line()).await,
+            None => Ok(self.lines.next_line().await),
+        };
+
+        let line = match line_res {
+            Ok(line) => line,
+            Err(_) => {
+                self.timeout_message = Some(format!(
+                    "The backend stopped responding for {:.1}s, so the response is incomplete.",
+                    self.idle_timeout.unwrap_or_default().as_secs_f32()
+                ));
+                return Ok(None);
+            }
+        };
+
+        if line
+            .as_ref()
+            .is_err_and(|err| return err.kind() == io::ErrorKind::TimedOut)
+        {
+            self.timeout_message =
+                Some("The backend request timed out, so the response is incomplete.".to_string());
+            return Ok(None);
+        }
+
+        return Ok(line?);
+    }
+
+    /// Returns the next payload, or `None` once the stream ends, times out or a
+    /// `[DONE]` sentinel is received. Error payloads from the provider are
+    /// returned as errors.
+    pub async fn next<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        while let Some(line) = self.next_line().await? {
+            let payload = match parse_line(&line) {
+                Some(payload) => payload,
+                None => continue,
+            };
+            if payload == "[DONE]" {
+                return Ok(None);
+            }
+
+            let value = match serde_json::from_str::<serde_json::Value>(payload) {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::warn!(line = payload, error = ?err, "Skipping malformed stream line");
+                    continue;
+                }
+            };
+
+            if let Some(message) = error_message(&value) {
+                tracing::error!(body = payload, "Stream returned an error");
+                bail!(message);
+            }
+
+            match serde_json::from_value::<T>(value) {
+                Ok(res) => return Ok(Some(res)),
+                Err(err) => {
+                    tracing::warn!(line = payload, error = ?err, "Skipping unexpected stream line");
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+
+    /// Returns an error if the stream ended because it timed out. Call it once
+    /// the partial response has been sent.
+    pub fn finish(&self) -> Result<()> {
+        if let Some(message) = &self.timeout_message {
+            bail!(message.to_string());
+        }
+
+        return Ok(());
+    }
+}
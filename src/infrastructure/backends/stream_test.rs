@@ -0,0 +1,175 @@
+use std::io;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio_util::io::StreamReader;
+
+use super::StreamDecoder;
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+struct Chunk {
+    text: String,
+}
+
+fn decoder(body: &'static str) -> StreamDecoder {
+    return StreamDecoder::from_reader(Box::new(body.as_bytes()));
+}
+
+async fn collect(mut decoder: StreamDecoder) -> Result<Vec<String>> {
+    return collect_until_end(&mut decoder).await;
+}
+
+async fn collect_until_end(decoder: &mut StreamDecoder) -> Result<Vec<String>> {
+    let mut texts = vec![];
+    while let Some(chunk) = decoder.next::<Chunk>().await? {
+        texts.push(chunk.text);
+    }
+
+    return Ok(texts);
+}
+
+#[tokio::test]
+async fn it_decodes_server_sent_events() -> Result<()> {
+    let body = r#"event: message
+data: {"text":"Hello"}
+
+data:{"text":" World"}
+
+"#;
+    assert_eq!(collect(decoder(body)).await?, vec![
+        "Hello".to_string(),
+        " World".to_string()
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_decodes_newline_delimited_json() -> Result<()> {
+    let body = "{\"text\":\"Hello\"}\n{\"text\":\" World\"}\n";
+    assert_eq!(collect(decoder(body)).await?, vec![
+        "Hello".to_string(),
+        " World".to_string()
+    ]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_at_done_sentinel() -> Result<()> {
+    let body = "data: {\"text\":\"Hello\"}\n\ndata: [DONE]\n\ndata: {\"text\":\"Ignored\"}\n";
+    assert_eq!(collect(decoder(body)).await?, vec!["Hello".to_string()]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_skips_comments_and_malformed_lines() -> Result<()> {
+    let body = r#": keepalive
+id: 1
+retry: 1000
+data: {"text":"Hello"
+data: {"other":"shape"}
+data: {"text":"World"}
+"#;
+    assert_eq!(collect(decoder(body)).await?, vec!["World".to_string()]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_error_payloads() {
+    let body = r#"data: {"text":"Hello"}
+data: {"error":{"message":"Rate limit reached","type":"requests"}}
+"#;
+    let mut decoder = decoder(body);
+
+    let first = decoder.next::<Chunk>().await;
+    assert_eq!(
+        first.unwrap(),
+        Some(Chunk {
+            text: "Hello".to_string()
+        })
+    );
+
+    let second = decoder.next::<Chunk>().await;
+    assert_eq!(second.unwrap_err().to_string(), "Rate limit reached");
+}
+
+#[tokio::test]
+async fn it_returns_string_error_payloads() {
+    let body = "{\"error\":\"model 'llama' not found\"}\n";
+    let res = decoder(body).next::<Chunk>().await;
+    assert_eq!(res.unwrap_err().to_string(), "model 'llama' not found");
+}
+
+#[tokio::test]
+async fn it_ignores_null_errors() -> Result<()> {
+    let body = "{\"text\":\"Hello\",\"error\":null}\n";
+    assert_eq!(collect(decoder(body)).await?, vec!["Hello".to_string()]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_when_idle() -> Result<()> {
+    let (mut writer, reader) = tokio::io::duplex(1024);
+    writer.write_all(b"data: {\"text\":\"Hello\"}\n\n").await?;
+
+    let mut decoder = StreamDecoder::from_reader(Box::new(BufReader::new(reader)));
+    decoder.idle_timeout = Some(Duration::from_millis(100));
+
+    assert_eq!(
+        decoder.next::<Chunk>().await?,
+        Some(Chunk {
+            text: "Hello".to_string()
+        })
+    );
+    assert_eq!(decoder.next::<Chunk>().await?, None);
+    assert_eq!(
+        decoder.finish().unwrap_err().to_string(),
+        "The backend stopped responding for 0.1s, so the response is incomplete."
+    );
+
+    // The writer is held open until here so the stream stalls instead of ending.
+    drop(writer);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_when_request_times_out() -> Result<()> {
+    let chunks: Vec<io::Result<&'static [u8]>> = vec![
+        Ok(b"data: {\"text\":\"Hello\"}\n\n"),
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "operation timed out",
+        )),
+    ];
+    let reader = StreamReader::new(futures::stream::iter(chunks));
+    let mut decoder = StreamDecoder::from_reader(Box::new(reader));
+
+    assert_eq!(collect_until_end(&mut decoder).await?, vec![
+        "Hello".to_string()
+    ]);
+    assert_eq!(
+        decoder.finish().unwrap_err().to_string(),
+        "The backend request timed out, so the response is incomplete."
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_finishes_without_timeout() -> Result<()> {
+    let mut decoder = decoder("{\"text\":\"Hello\"}\n");
+    assert_eq!(collect_until_end(&mut decoder).await?, vec![
+        "Hello".to_string()
+    ]);
+    assert!(decoder.finish().is_ok());
+
+    return Ok(());
+}
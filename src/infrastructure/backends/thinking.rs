@@ -0,0 +1,81 @@
+#[cfg(test)]
+#[path = "thinking_test.rs"]
+mod tests;
+
+const OPEN_TAG: &str = "<think>";
+const CLOSE_TAG: &str = "</think>";
+
+/// Splits `<think>` blocks out of streamed text, as models like DeepSeek-R1
+/// write their reasoning inline with the answer. A tag split across chunks is
+/// held back until the rest of it arrives.
+#[derive(Default)]
+pub struct ThinkSplitter {
+    in_thinking: bool,
+    trim_text_start: bool,
+    pending: String,
+}
+
+impl ThinkSplitter {
+    pub fn new() -> ThinkSplitter {
+        return ThinkSplitter::default();
+    }
+
+    /// Returns the answer and thinking text found in the chunk.
+    pub fn push(&mut self, chunk: &str) -> (String, String) {
+        let mut buf = std::mem::take(&mut self.pending) + chunk;
+        let mut text = String::new();
+        let mut thinking = String::new();
+
+        loop {
+            let tag = if self.in_thinking {
+                CLOSE_TAG
+            } else {
+                OPEN_TAG
+            };
+            if let Some(idx) = buf.find(tag) {
+                self.emit(&buf[..idx], &mut text, &mut thinking);
+                buf = buf[idx + tag.len()..].to_string();
+                self.in_thinking = !self.in_thinking;
+                // Models put blank lines between the reasoning and the answer.
+                self.trim_text_start = !self.in_thinking;
+                continue;
+            }
+
+            // Tags are ASCII, so a partial tag always starts on a char boundary.
+            let partial_len = (1..tag.len())
+                .rev()
+                .find(|len| return buf.ends_with(&tag[..*len]))
+                .unwrap_or(0);
+            self.pending = buf.split_off(buf.len() - partial_len);
+            self.emit(&buf, &mut text, &mut thinking);
+            break;
+        }
+
+        return (text, thinking);
+    }
+
+    /// Returns text held back at the end of the stream, which turned out not to
+    /// be a tag.
+    pub fn finish(&mut self) -> (String, String) {
+        let mut text = String::new();
+        let mut thinking = String::new();
+        let pending = std::mem::take(&mut self.pending);
+        self.emit(&pending, &mut text, &mut thinking);
+
+        return (text, thinking);
+    }
+
+    fn emit(&mut self, part: &str, text: &mut String, thinking: &mut String) {
+        if self.in_thinking {
+            *thinking += part;
+            return;
+        }
+
+        let mut part = part;
+        if self.trim_text_start {
+            part = part.trim_start();
+            self.trim_text_start = part.is_empty();
+        }
+        *text += part;
+    }
+}
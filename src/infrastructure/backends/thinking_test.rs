@@ -0,0 +1,57 @@
+use super::ThinkSplitter;
+
+#[test]
+fn it_passes_through_text_without_tags() {
+    let mut splitter = ThinkSplitter::new();
+
+    assert_eq!(
+        splitter.push("Hello World"),
+        ("Hello World".to_string(), "".to_string())
+    );
+    assert_eq!(splitter.finish(), ("".to_string(), "".to_string()));
+}
+
+#[test]
+fn it_splits_thinking_from_text() {
+    let mut splitter = ThinkSplitter::new();
+
+    assert_eq!(
+        splitter.push("<think>\nThe user said hi.\n</think>\n\nHello!"),
+        ("Hello!".to_string(), "\nThe user said hi.\n".to_string())
+    );
+}
+
+#[test]
+fn it_splits_tags_across_chunks() {
+    let mut splitter = ThinkSplitter::new();
+    let chunks = [
+        "<th",
+        "ink>Hmm",
+        "m.</thi",
+        "nk>",
+        "\n\n",
+        "Hi <b>there</b>",
+    ];
+
+    let mut text = String::new();
+    let mut thinking = String::new();
+    for chunk in chunks {
+        let (chunk_text, chunk_thinking) = splitter.push(chunk);
+        text += &chunk_text;
+        thinking += &chunk_thinking;
+    }
+
+    assert_eq!(text, "Hi <b>there</b>");
+    assert_eq!(thinking, "Hmmm.");
+}
+
+#[test]
+fn it_returns_partial_tags_when_finished() {
+    let mut splitter = ThinkSplitter::new();
+
+    assert_eq!(
+        splitter.push("a < b <thi"),
+        ("a < b ".to_string(), "".to_string())
+    );
+    assert_eq!(splitter.finish(), ("<thi".to_string(), "".to_string()));
+}
@@ -11,10 +11,10 @@ use std::process;
 
 use anyhow::Error;
 use domain::models::Action;
-use domain::models::BackendName;
 use domain::models::Event;
 use domain::services::clipboard::ClipboardService;
 use infrastructure::backends::BackendManager;
+use infrastructure::backends::http;
 use tokio::sync::mpsc;
 use tokio::task;
 use yansi::Paint;
@@ -99,14 +99,22 @@ async fn main() {
         process::exit(0);
     }
 
+    let client_res = http::build_client();
+    if let Err(client_err) = client_res {
+        handle_error(client_err);
+        return;
+    }
+    let client = client_res.unwrap();
+
     let (action_tx, mut action_rx) = mpsc::unbounded_channel::<Action>();
     let (event_tx, event_rx) = mpsc::unbounded_channel::<Event>();
 
     let mut background_futures = task::JoinSet::new();
+    let backend_client = client.clone();
     background_futures.spawn(async move {
-        let backend = BackendName::parse(Config::get(ConfigKey::Backend)).unwrap();
         return ActionsService::start(
-            BackendManager::get(backend).unwrap(),
+            BackendManager::get(&Config::get(ConfigKey::Backend), backend_client.clone()).unwrap(),
+            backend_client,
             event_tx,
             &mut action_rx,
         )
@@ -121,7 +129,7 @@ async fn main() {
         });
     }
 
-    let ui_future = ui::start(action_tx, event_rx);
+    let ui_future = ui::start(action_tx, event_rx, client);
 
     let res = tokio::select!(
         res = background_futures.join_next() => res.unwrap().unwrap(),
@@ -51,3 +51,26 @@ That's it!
 "#
     .trim()
 }
+
+/// Encodes a message in the AWS event stream framing, with string headers.
+pub fn event_stream_message(headers: &[(&str, &str)], payload: &str) -> Vec<u8> {
+    let mut header_bytes = vec![];
+    for (name, value) in headers {
+        header_bytes.push(name.len() as u8);
+        header_bytes.extend(name.as_bytes());
+        header_bytes.push(7);
+        header_bytes.extend((value.len() as u16).to_be_bytes());
+        header_bytes.extend(value.as_bytes());
+    }
+
+    let total_len = 12 + header_bytes.len() + payload.len() + 4;
+    let mut message = vec![];
+    message.extend((total_len as u32).to_be_bytes());
+    message.extend((header_bytes.len() as u32).to_be_bytes());
+    message.extend(crc32fast::hash(&message).to_be_bytes());
+    message.extend(header_bytes);
+    message.extend(payload.as_bytes());
+    message.extend(crc32fast::hash(&message).to_be_bytes());
+
+    message
+}